@@ -49,7 +49,7 @@ impl Service for MyService {
 // You can asychronously (de)initialize your service by returning
 // an AsyncHook task to poll.
 fn my_init() -> InitResult {
-    let hook = AsyncHook::io_task(async |_q: CommandQueue| Ok(()));
+    let hook = AsyncHook::io(async |_q: &mut CommandQueue| Ok(()));
     Ok(Some(hook))
 }
 // ... or keep it synchronous by returning Ok(None)
@@ -83,10 +83,12 @@ fn observe_status_update(
         match &**event {
             ServiceStatus::Down(DownReason::Failed(e)) => todo!(),
             ServiceStatus::Down(DownReason::SpunDown) => todo!(),
+            ServiceStatus::Down(DownReason::Custom(reason)) => todo!(),
             ServiceStatus::Down(DownReason::Uninitialized) => todo!(),
             ServiceStatus::Deinit(down_reason) => todo!(),
             ServiceStatus::Init => todo!(),
             ServiceStatus::Up => todo!(),
+            ServiceStatus::Standby => todo!(),
         }
     }
 }
@@ -97,7 +99,9 @@ impl Plugin for MyPlugin {
     fn build(&self, app: &mut App) {
         // Add your service to a plugin,
         // or directly on the application.
-        app.register_service::<MyService>();
+        app.register_service::<MyService>()
+            // Dependencies must be registered too.
+            .register_service::<MyOtherService>();
     }
 }
 
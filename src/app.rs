@@ -1,5 +1,10 @@
 use crate::prelude::*;
-use bevy_app::prelude::*;
+use bevy_app::{MainScheduleOrder, prelude::*};
+use bevy_ecs::{prelude::*, schedule::ScheduleLabel};
+use bevy_platform::prelude::*;
+use bevy_state::state::{FreelyMutableState, NextState};
+use std::sync::{Mutex, PoisonError};
+use tracing::error;
 
 /// Extensions to [App].
 pub trait ServiceAppExt {
@@ -12,7 +17,7 @@ pub trait ServiceAppExt {
     /// #[derive(Resource, Debug, Default)]
     /// pub struct ExampleService;
     /// impl Service for ExampleService {
-    ///     fn build(scope: &mut ServiceScope) {}
+    ///     fn build(scope: &mut ServiceScope<Self>) {}
     /// }
     ///
     /// fn main() {
@@ -23,34 +28,474 @@ pub trait ServiceAppExt {
     /// ## Panics
     ///
     /// This function panics if cycles are detected in the ServiceSpec's
-    /// dependencies.
-    fn register_service<T: Service>(&mut self) -> &mut Self;
-
-    // TODO: Dynamic system patching? Probably don't modify hooks.
-    // /// Patch a service using a [ServiceScope]. Useful for extending the service's functionality.
-    // /// the system is up. For similar use cases when the system is down or in
-    // /// another state, see [crate::run_conditions].
-    // ///
-    // /// ## Example usage
-    // /// ```rust
-    // /// # use q_service::prelude::*;
-    // /// # use bevy::prelude::*;
-    // /// # service!(ExampleService);
-    // /// #
-    // /// # fn main() {
-    // /// # let mut app = App::new();
-    // /// # fn sys_a() {}
-    // /// # fn sys_b() {}
-    // /// app.service_scope(|scope: ServiceScope<ExampleService>| {
-    // ///     scope.add_systems(Update, (sys_a, sys_b).chain());
-    // /// });
-    // /// # }
-    // /// ```
-    // fn service_scope<T: Service>(&mut self, cb: impl FnMut(ServiceScope<T>)) -> &mut Self;
+    /// dependencies. Services may be registered in any order, but once the
+    /// app is [finished](App::finish), it panics if any service depends on a
+    /// service which was never registered. Apps which are never finished log
+    /// a warning at startup instead. Registering a service twice logs a
+    /// warning and does nothing.
+    fn register_service<T: Service + Default>(&mut self) -> &mut Self;
+
+    /// Registers a [Service], as with [ServiceAppExt::register_service], but
+    /// returns an error instead of panicking if cycles are detected in the
+    /// ServiceSpec's dependencies. The service is left unregistered on error.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// let mut app = App::new();
+    /// if let Err(e) = app.try_register_service::<ExampleService>() {
+    ///     error!("Couldn't register service: {e}");
+    /// }
+    /// ```
+    fn try_register_service<T: Service + Default>(&mut self) -> Result<(), DepInitErr>;
+
+    /// Checks every registered service's dependencies at once, reporting each
+    /// cycle, loop, and unregistered dependency instead of stopping at the
+    /// first. Call it once every service has been registered. Cycles include
+    /// those which caused [ServiceAppExt::try_register_service] to reject a
+    /// service, so pair it with that function to get a full report for a
+    /// large set of plugins.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// let mut app = App::new();
+    /// let _ = app.try_register_service::<ExampleService>();
+    /// if let Err(errors) = app.validate_services() {
+    ///     for e in errors {
+    ///         error!("{e}");
+    ///     }
+    /// }
+    /// ```
+    fn validate_services(&self) -> Result<(), Vec<DepInitErr>>;
+
+    /// Registers a [Service] whose resource has no [Default], inserting the
+    /// value returned by `init` instead. Otherwise behaves exactly like
+    /// [ServiceAppExt::register_service]. If the resource is already present,
+    /// `init` is not called.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// #[derive(Resource, Debug)]
+    /// struct Server {
+    ///     port: u16,
+    /// }
+    /// impl Service for Server {
+    ///     fn build(_: &mut ServiceScope<Self>) {}
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.register_service_with(|| Server { port: 8080 });
+    /// ```
+    /// ## Panics
+    ///
+    /// Panics under the same conditions as [ServiceAppExt::register_service].
+    fn register_service_with<T: Service>(&mut self, init: impl FnOnce() -> T) -> &mut Self;
+
+    /// Registers a [Service], as with [ServiceAppExt::register_service], once
+    /// the resource `R` exists, e.g. when `R` is inserted by a plugin which
+    /// may be added after this one. The resource is checked for in a schedule
+    /// of its own, which runs right before [PreStartup] and then at the start
+    /// of every frame, before [First], until the service is registered. The
+    /// service may add systems to any of the main schedules.
+    ///
+    /// If `R` is only inserted after startup, the service won't spin up at
+    /// startup even if it [is_startup](ServiceScope::is_startup). Services
+    /// which depend on it should be deferred as well; otherwise a warning is
+    /// logged at startup and they fail when spun up before it's registered.
+    ///
+    /// Unlike [ServiceAppExt::register_service], this doesn't panic if the
+    /// service can't be registered, e.g. because it would form a dependency
+    /// cycle. The error is logged and the service is left unregistered.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// #[derive(Resource)]
+    /// struct Config;
+    ///
+    /// let mut app = App::new();
+    /// app.register_service_when_resource::<ExampleService, Config>();
+    /// ```
+    fn register_service_when_resource<T: Service + Default, R: Resource>(&mut self) -> &mut Self;
+
+    /// Adds a [Service] to the given [ServiceGroup]. A [GroupUp] event will
+    /// fire once every member of the group is up.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// struct CoreServices;
+    /// impl ServiceGroup for CoreServices {}
+    ///
+    /// let mut app = App::new();
+    /// app.register_service::<ExampleService>()
+    ///     .add_service_to_group::<ExampleService>(CoreServices);
+    /// ```
+    fn add_service_to_group<T: Service>(&mut self, group: impl ServiceGroup) -> &mut Self;
+
+    /// Adds an instance of a [Service] to the application, keyed by a string.
+    /// Instances of the same service share its `build` logic, but each has its
+    /// own [ServiceData] and status. They're controlled with
+    /// [ServiceCommandsExt::spin_service_instance_up] and friends, and report
+    /// status changes through [ServiceUpdated] rather than the service's typed
+    /// events.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// #[derive(Resource, Debug, Default)]
+    /// struct Connection;
+    /// impl Service for Connection {
+    ///     fn build(_: &mut ServiceScope<Self>) {}
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.register_service_instance::<Connection>("primary")
+    ///     .register_service_instance::<Connection>("replica");
+    /// ```
+    /// ## Panics
+    ///
+    /// Instances may only depend on other services. This function panics if
+    /// the service's `build` adds scoped systems, resources, or assets, or if
+    /// cycles are detected in its dependencies.
+    fn register_service_instance<T: Service>(&mut self, key: impl Into<String>) -> &mut Self;
+
+    /// Runs the callback once every plugin has been built, i.e. once all
+    /// [register_service](ServiceAppExt::register_service) calls are done and
+    /// the [DependencyGraph] is complete. This is the place for up-front
+    /// validation and wiring which needs to see every service.
+    ///
+    /// Callbacks run during [App::finish], in the order they were added. App
+    /// runners call this before the first update; if you drive the app
+    /// manually, call [App::finish] yourself.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// let mut app = App::new();
+    /// app.on_graph_built(|app| {
+    ///     let graph = app.world().resource::<DependencyGraph>();
+    ///     info!("{} nodes in the dependency graph", graph.node_count());
+    /// });
+    /// ```
+    fn on_graph_built(&mut self, callback: impl FnOnce(&mut App) + Send + 'static) -> &mut Self;
+
+    /// Mirrors a [Service]'s status into a Bevy [States](bevy_state::state::States) type. Whenever the
+    /// service enters a new status, `map` is called with it, and the returned
+    /// state (if any) is set through [NextState]. This lets you use
+    /// `OnEnter`/`OnExit` schedules and `in_state` with services.
+    ///
+    /// The state must be initialized separately, e.g. with
+    /// [init_state](bevy_state::app::AppExtStates::init_state).
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// #[derive(States, Debug, Default, Clone, PartialEq, Eq, Hash)]
+    /// enum Online {
+    ///     #[default]
+    ///     No,
+    ///     Yes,
+    /// }
+    ///
+    /// let mut app = App::new();
+    /// app.add_plugins(bevy::state::app::StatesPlugin)
+    ///     .init_state::<Online>()
+    ///     .register_service::<ExampleService>()
+    ///     .mirror_service_to_state::<ExampleService, _>(|status| match status {
+    ///         ServiceStatus::Up => Some(Online::Yes),
+    ///         ServiceStatus::Down(_) => Some(Online::No),
+    ///         _ => None,
+    ///     });
+    /// ```
+    fn mirror_service_to_state<T: Service, S: FreelyMutableState>(
+        &mut self,
+        map: impl Fn(&ServiceStatus) -> Option<S> + Send + Sync + 'static,
+    ) -> &mut Self;
+
+    /// Spins every registered service down when the app exits, dependents
+    /// first, so that deinit hooks get a chance to close sockets, flush files,
+    /// etc. Asynchronous deinit hooks are blocked on until they finish.
+    ///
+    /// Shutdown runs in [Last] during the update in which [AppExit] is sent.
+    fn run_service_shutdown_on_exit(&mut self) -> &mut Self;
+
+    /// Adds systems to an already registered service. Useful for plugins which
+    /// extend another plugin's service. The systems are scoped to the service
+    /// like those added in its `build`. Hooks and dependencies can't be
+    /// patched; see [ServiceWorldExt::hot_swap_service].
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// # fn sys_a() {}
+    /// # fn sys_b() {}
+    /// let mut app = App::new();
+    /// app.register_service::<ExampleService>()
+    ///     .patch_service::<ExampleService>(|patch| {
+    ///         patch.add_systems(Update, (sys_a, sys_b).chain());
+    ///     });
+    /// ```
+    /// ## Panics
+    ///
+    /// This function panics if the service has not been registered.
+    fn patch_service<T: Service>(&mut self, patch: impl FnOnce(&mut ServicePatch<T>)) -> &mut Self;
 }
 impl ServiceAppExt for App {
-    fn register_service<T: Service>(&mut self) -> &mut Self {
+    fn register_service<T: Service + Default>(&mut self) -> &mut Self {
         T::register(self);
         self
     }
+
+    fn try_register_service<T: Service + Default>(&mut self) -> Result<(), DepInitErr> {
+        T::try_register(self)
+    }
+
+    fn validate_services(&self) -> Result<(), Vec<DepInitErr>> {
+        if !self.world().contains_resource::<GraphDataCache>() {
+            return Ok(());
+        }
+        crate::deps::validate_services(self.world())
+    }
+
+    fn register_service_with<T: Service>(&mut self, init: impl FnOnce() -> T) -> &mut Self {
+        if !self.world().contains_resource::<T>() {
+            self.insert_resource(init());
+        }
+        crate::service_trait::try_register_service::<T>(self).expect("Dependencies are invalid.");
+        self
+    }
+
+    fn register_service_when_resource<T: Service + Default, R: Resource>(&mut self) -> &mut Self {
+        crate::service_trait::init_service_globals(self);
+        init_deferred_registration(self);
+        let register = |world: &mut World| {
+            if !world.contains_resource::<R>() {
+                return false;
+            }
+            if world.get_service::<T>().is_some() {
+                return true;
+            }
+            world.init_resource::<T>();
+            if let Err(e) = crate::service_trait::try_register_service_in::<T>(world) {
+                error!("Couldn't register service {}: {e}", T::name());
+            }
+            true
+        };
+        self.world_mut()
+            .resource_mut::<DeferredServices>()
+            .0
+            .push(Box::new(register));
+        self
+    }
+
+    fn add_service_to_group<T: Service>(&mut self, group: impl ServiceGroup) -> &mut Self {
+        crate::groups::add_service_to_group::<T, _>(self, group);
+        self
+    }
+
+    fn register_service_instance<T: Service>(&mut self, key: impl Into<String>) -> &mut Self {
+        crate::service_trait::register_instance::<T>(self, key.into());
+        self
+    }
+
+    fn patch_service<T: Service>(&mut self, patch: impl FnOnce(&mut ServicePatch<T>)) -> &mut Self {
+        assert!(
+            self.world().contains_resource::<T>() && self.world().service::<T>().registered(),
+            "Tried to patch service {}, but it isn't registered!",
+            T::name()
+        );
+        let mut scope = ServicePatch(ServiceScope::new(self.world_mut()));
+        patch(&mut scope);
+        let (_, setup) = scope.0.into_parts();
+        setup.apply(self.world_mut());
+        self
+    }
+
+    fn run_service_shutdown_on_exit(&mut self) -> &mut Self {
+        crate::service_trait::init_service_globals(self);
+        self.add_systems(Last, crate::lifecycle::commands::shutdown_on_exit);
+        self
+    }
+
+    fn on_graph_built(&mut self, callback: impl FnOnce(&mut App) + Send + 'static) -> &mut Self {
+        crate::service_trait::init_service_globals(self);
+        self.add_plugins(GraphBuiltPlugin(Mutex::new(Some(Box::new(callback)))));
+        self
+    }
+
+    fn mirror_service_to_state<T: Service, S: FreelyMutableState>(
+        &mut self,
+        map: impl Fn(&ServiceStatus) -> Option<S> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.add_observer(
+            move |trigger: Trigger<EnterServiceState<T>>, mut next: ResMut<NextState<S>>| {
+                if let Some(state) = map(trigger.event()) {
+                    next.set(state);
+                }
+            },
+        )
+    }
+}
+
+/// Runs the [ServiceAppExt::register_service_when_resource] checks. A running
+/// schedule is taken out of the world and put back once it's done, which
+/// would drop any systems a service's build adds to it, so registration runs
+/// in a schedule of its own.
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct RegisterDeferredServices;
+
+/// A deferred registration. Returns true once it's done with, i.e. its
+/// resource exists.
+type DeferredRegistration = Box<dyn FnMut(&mut World) -> bool + Send + Sync>;
+
+/// The registrations still waiting on their resource. See
+/// [ServiceAppExt::register_service_when_resource].
+#[derive(Resource, Default)]
+struct DeferredServices(Vec<DeferredRegistration>);
+
+fn init_deferred_registration(app: &mut App) {
+    if app.world().contains_resource::<DeferredServices>() {
+        return;
+    }
+    let mut order = app.world_mut().resource_mut::<MainScheduleOrder>();
+    order.insert_startup_before(PreStartup, RegisterDeferredServices);
+    order.insert_before(First, RegisterDeferredServices);
+    app.init_resource::<DeferredServices>().add_systems(
+        RegisterDeferredServices,
+        register_deferred_services.run_if(|deferred: Res<DeferredServices>| !deferred.0.is_empty()),
+    );
+}
+
+/// Registers the deferred services whose resource now exists, and drops
+/// them from [DeferredServices].
+fn register_deferred_services(world: &mut World) {
+    let mut pending = core::mem::take(&mut world.resource_mut::<DeferredServices>().0);
+    pending.retain_mut(|register| !register(world));
+    // a service's build may have deferred others
+    let mut deferred = world.resource_mut::<DeferredServices>();
+    pending.append(&mut deferred.0);
+    deferred.0 = pending;
+}
+
+type GraphBuiltCallback = Box<dyn FnOnce(&mut App) + Send>;
+
+/// Runs a [ServiceAppExt::on_graph_built] callback when the app is finished.
+struct GraphBuiltPlugin(Mutex<Option<GraphBuiltCallback>>);
+impl Plugin for GraphBuiltPlugin {
+    fn build(&self, _: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        let callback = self.0.lock().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(callback) = callback {
+            callback(app);
+        }
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
+}
+
+/// Collects [Services](Service) so they can be registered together with a
+/// single [App::add_plugins]. Dependencies are resolved once the app is
+/// finished, so services may be added in any order.
+///
+/// ## Example usage
+/// ```rust
+/// # use q_service::prelude::*;
+/// # use bevy::prelude::*;
+/// # #[derive(Resource, Debug, Default)]
+/// # struct Database;
+/// # impl Service for Database {
+/// #     fn build(_: &mut ServiceScope<Self>) {}
+/// # }
+/// #[derive(Resource, Debug, Default)]
+/// struct Api;
+/// impl Service for Api {
+///     fn build(scope: &mut ServiceScope<Self>) {
+///         scope.add_dep::<Database>();
+///     }
+/// }
+///
+/// let mut app = App::new();
+/// app.add_plugins(ServiceBundle::new().add::<Api>().add::<Database>().build());
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct ServiceBundle(Vec<fn(&mut App)>);
+impl ServiceBundle {
+    /// Creates an empty bundle.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a [Service] to the bundle.
+    pub fn add<T: Service + Default>(mut self) -> Self {
+        self.0.push(|app| {
+            app.register_service::<T>();
+        });
+        self
+    }
+
+    /// Gets a [Plugin] which registers every service in the bundle, in the
+    /// order they were added.
+    pub fn build(self) -> ServiceBundlePlugin {
+        ServiceBundlePlugin(self.0)
+    }
+}
+
+/// Registers the services collected by a [ServiceBundle].
+#[derive(Debug)]
+pub struct ServiceBundlePlugin(Vec<fn(&mut App)>);
+impl Plugin for ServiceBundlePlugin {
+    fn build(&self, app: &mut App) {
+        for register in &self.0 {
+            register(app);
+        }
+    }
+
+    fn is_unique(&self) -> bool {
+        false
+    }
 }
@@ -1,22 +1,134 @@
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::resource::Resource;
-use bevy_platform::collections::HashMap;
+use bevy_platform::collections::{HashMap, HashSet};
 
 use crate::prelude::*;
-use std::{fmt::Debug, hash::Hash};
+use bevy_ecs::error::BevyError;
+use std::{
+    any::Any, cmp::Ordering, error::Error, fmt::Debug, hash::Hash, marker::PhantomData, sync::Arc,
+};
 
 /// Used to specify where and how the service failed.
-#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// Errors are compared, ordered, and hashed by their messages; the underlying
+/// [ErrorSource] is ignored.
+///
+/// With the `serde` feature, errors are serialized by their messages; the
+/// underlying [ErrorSource] is skipped.
+#[derive(thiserror::Error, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ServiceError {
-    /// The service failed all by itself!
+    /// The service failed all by itself! Holds the error message, and the
+    /// underlying error if there is one.
     #[error("{0}")]
-    Own(String),
-    // Not boxing here because IsServiceError is not dyn compatible.
-    /// A dependency failed, propogating to this service.
+    Own(
+        String,
+        #[cfg_attr(feature = "serde", serde(skip))] Option<ErrorSource>,
+    ),
+    /// A dependency failed, propogating to this service. Holds the
+    /// dependency's name and error.
     #[error("Dependency {0} failed with error:\n{1}")]
-    Dependency(String, String),
+    Dependency(String, #[source] Box<ServiceError>),
+}
+impl ServiceError {
+    /// The service failed with the given message.
+    pub fn own(message: impl ToString) -> Self {
+        Self::Own(message.to_string(), None)
+    }
+    /// The service failed with the given error, which can be retrieved with
+    /// [ServiceError::downcast_ref].
+    pub fn from_error<E: Error + Send + Sync + 'static>(error: E) -> Self {
+        BevyError::from(error).into()
+    }
+    /// The dependency with the given name failed with the given error.
+    pub fn dependency(name: impl ToString, error: ServiceError) -> Self {
+        Self::Dependency(name.to_string(), Box::new(error))
+    }
+    /// Gets the error's message. For a dependency failure, this is the
+    /// message of the service which originally failed.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Own(message, _) => message,
+            Self::Dependency(_, error) => error.message(),
+        }
+    }
+    /// Gets the name of the dependency whose failure propagated to this
+    /// service, if any.
+    pub fn dependency_name(&self) -> Option<&str> {
+        match self {
+            Self::Own(..) => None,
+            Self::Dependency(name, _) => Some(name),
+        }
+    }
+    /// Gets the error of the dependency whose failure propagated to this
+    /// service, if any.
+    pub fn dependency_error(&self) -> Option<&ServiceError> {
+        match self {
+            Self::Own(..) => None,
+            Self::Dependency(_, error) => Some(error),
+        }
+    }
+    /// Gets the underlying error if it has the type `E`, following dependency
+    /// failures down to the service which originally failed.
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        match self {
+            Self::Own(_, source) => source.as_ref()?.0.downcast_ref(),
+            Self::Dependency(_, error) => error.downcast_ref(),
+        }
+    }
+}
+impl From<BevyError> for ServiceError {
+    fn from(error: BevyError) -> Self {
+        // BevyError's display ends with a newline
+        let message = error.to_string().trim_end().to_string();
+        Self::Own(message, Some(ErrorSource(Arc::new(error))))
+    }
+}
+impl PartialEq for ServiceError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Own(a, _), Self::Own(b, _)) => a == b,
+            (Self::Dependency(a, a_err), Self::Dependency(b, b_err)) => a == b && a_err == b_err,
+            _ => false,
+        }
+    }
+}
+impl Eq for ServiceError {}
+impl Hash for ServiceError {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Own(message, _) => message.hash(state),
+            Self::Dependency(name, error) => {
+                name.hash(state);
+                error.hash(state);
+            }
+        }
+    }
 }
 
+impl Ord for ServiceError {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Own(a, _), Self::Own(b, _)) => a.cmp(b),
+            (Self::Dependency(a, a_err), Self::Dependency(b, b_err)) => {
+                a.cmp(b).then_with(|| a_err.cmp(b_err))
+            }
+            (Self::Own(..), Self::Dependency(..)) => Ordering::Less,
+            (Self::Dependency(..), Self::Own(..)) => Ordering::Greater,
+        }
+    }
+}
+impl PartialOrd for ServiceError {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The error underlying a [ServiceError::Own]. See [ServiceError::downcast_ref].
+#[derive(Debug, Clone)]
+pub struct ErrorSource(Arc<BevyError>);
+
 // #[derive(Debug, States, Deref)]
 // pub struct ServiceStates<T: Service>(#[deref] ServiceState, PhantomData<T>);
 // impl<T: Service> ServiceStates<T> {
@@ -55,6 +167,7 @@ pub enum ServiceError {
 /// In order to react to changes, use [events](crate::lifecycle::events) or
 /// [service hooks](crate::lifecycle::hooks).
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ServiceStatus {
     /// The service is currently down.
     Down(DownReason),
@@ -64,6 +177,10 @@ pub enum ServiceStatus {
     Init,
     /// The service is up and running.
     Up,
+    /// The service has initialized, but has not run its on_up hook. Spinning
+    /// it up will skip initialization. See
+    /// [ServiceCommandsExt::prewarm_service].
+    Standby,
 }
 impl ServiceStatus {
     /// Self::Down(DownReason::SpunDown)
@@ -106,6 +223,10 @@ impl ServiceStatus {
         matches!(self, ServiceStatus::Up)
     }
     #[allow(missing_docs)]
+    pub fn is_standby(&self) -> bool {
+        matches!(self, ServiceStatus::Standby)
+    }
+    #[allow(missing_docs)]
     pub fn is_failed(&self) -> bool {
         matches!(self, ServiceStatus::Down(DownReason::Failed(_)))
     }
@@ -117,28 +238,67 @@ impl ServiceStatus {
     pub fn is_deinitializing(&self) -> bool {
         matches!(self, ServiceStatus::Deinit(_))
     }
+    /// Is the service initializing or deinitializing?
+    pub fn is_transitioning(&self) -> bool {
+        self.is_initializing() || self.is_deinitializing()
+    }
+    /// How far along the lifecycle the status is, from down to up.
+    fn rank(&self) -> u8 {
+        match self {
+            ServiceStatus::Down(_) => 0,
+            ServiceStatus::Deinit(_) => 1,
+            ServiceStatus::Init => 2,
+            ServiceStatus::Standby => 3,
+            ServiceStatus::Up => 4,
+        }
+    }
+}
+/// Statuses are ordered by how far along the lifecycle they are:
+/// `Down < Deinit < Init < Standby < Up`. Statuses with the same variant are
+/// ordered by their [DownReason].
+impl Ord for ServiceStatus {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank()
+            .cmp(&other.rank())
+            .then_with(|| match (self, other) {
+                (ServiceStatus::Down(a), ServiceStatus::Down(b))
+                | (ServiceStatus::Deinit(a), ServiceStatus::Deinit(b)) => a.cmp(b),
+                _ => Ordering::Equal,
+            })
+    }
+}
+impl PartialOrd for ServiceStatus {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 /// Describes the reason the service is currently down.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+///
+/// Reasons are ordered `Uninitialized < SpunDown < Custom < Failed`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DownReason {
     /// The service hasn't yet been initialized.
     Uninitialized,
+    /// The service succesfully spun down.
+    SpunDown,
+    /// The service succesfully spun down for a domain-specific reason, e.g.
+    /// "Evicted". See [ServiceCommandsExt::spin_service_down_with_reason].
+    Custom(String),
     /// At some point, this service failed. Contains the error, which might be from a dependency.
     /// See [ServiceError] for more details.
     Failed(ServiceError),
-    /// The service succesfully spun down.
-    SpunDown,
 }
 impl DownReason {
     /// The service itself failed. Distinct from [DownReason::dep_failure()]
     pub fn failed(err: impl ToString) -> Self {
-        Self::Failed(ServiceError::Own(err.to_string()))
+        Self::Failed(ServiceError::own(err))
     }
     /// One of the service's dependencies failed. Distint from [DownReason::failed()].
     pub fn dep_failure<Dependency: Service>(err: impl ToString) -> Self {
-        Self::Failed(ServiceError::Dependency(
-            Dependency::name().to_string(),
-            err.to_string(),
+        Self::Failed(ServiceError::dependency(
+            Dependency::name(),
+            ServiceError::own(err),
         ))
     }
 }
@@ -173,6 +333,71 @@ impl GraphDataCache {
     }
 }
 
+/// Every registered service's [NodeId] and name, in the order the services
+/// were first registered. Unlike iterating the [GraphDataCache], this order is
+/// deterministic. See [ServiceWorldExt::registry].
+#[derive(Resource, Deref, Default, Debug)]
+pub struct ServiceRegistry(Vec<(NodeId, String)>);
+impl ServiceRegistry {
+    /// Records a registration. Re-registering a service keeps its original
+    /// position.
+    pub(crate) fn push(&mut self, id: NodeId, name: String) {
+        match self.0.iter_mut().find(|(other, _)| *other == id) {
+            Some(entry) => entry.1 = name,
+            None => self.0.push((id, name)),
+        }
+    }
+
+    /// Forgets a deregistered service.
+    pub(crate) fn remove(&mut self, id: NodeId) {
+        self.0.retain(|(other, _)| *other != id);
+    }
+}
+
+/// Services removed with [ServiceWorldExt::deregister_service]. Their
+/// lifecycle systems can't be removed from the schedules, so they are skipped
+/// instead, and reused if the service is registered again.
+#[derive(Resource, Deref, DerefMut, Default, Debug)]
+pub(crate) struct DeregisteredServices(HashSet<NodeId>);
+
+/// Values shared between a service's hooks, keyed by the service's [NodeId].
+/// Written with [ServiceWorldExt::set_service_context] and handed to the hook
+/// added with [ServiceScope::deinit_with_context].
+#[derive(Resource, Deref, DerefMut, Default, Debug)]
+pub struct ServiceContexts(HashMap<NodeId, Box<dyn Any + Send + Sync>>);
+impl ServiceContexts {
+    /// Gets the context stored for the service, if it has the type `C`.
+    pub fn get_context<C: 'static>(&self, id: NodeId) -> Option<&C> {
+        self.get(&id).and_then(|context| context.downcast_ref())
+    }
+    /// Removes the context stored for the service, returning it if it has the
+    /// type `C`.
+    pub fn take_context<C: 'static>(&mut self, id: NodeId) -> Option<C> {
+        self.remove(&id)
+            .and_then(|context| context.downcast().ok())
+            .map(|context| *context)
+    }
+}
+
+/// Configuration for the service `T`, inserted by
+/// [ServiceScope::init_with_config]. Hooks and systems can read it with
+/// `Res<ServiceConfig<T, C>>`.
+#[derive(Resource, Deref, DerefMut, Debug)]
+pub struct ServiceConfig<T: Service, C: Send + Sync + 'static> {
+    #[deref]
+    config: C,
+    _service: PhantomData<T>,
+}
+impl<T: Service, C: Send + Sync + 'static> ServiceConfig<T, C> {
+    /// Wraps the config for the service `T`.
+    pub fn new(config: C) -> Self {
+        Self {
+            config,
+            _service: PhantomData,
+        }
+    }
+}
+
 /// Gets the name of a type as a string.
 /// Truncates up to the last colon.
 pub fn name_from_type<T>() -> String {
@@ -184,3 +409,44 @@ pub fn name_from_type<T>() -> String {
     }
     base.to_string()
 }
+
+/// A service identifier which, unlike [NodeId], is stable across worlds and
+/// runs, so it can be saved or sent over the network. Derived from the
+/// service's full type path, so it changes if the type is renamed or moved.
+/// Look up the service in a particular world with
+/// [ServiceWorldExt::key_to_node_id].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct ServiceKey(String);
+impl ServiceKey {
+    /// Gets the key of the service `T`.
+    pub fn of<T: Service>() -> Self {
+        Self(std::any::type_name::<T>().to_string())
+    }
+
+    /// Gets the key of the instance of the service `T` with the given key.
+    /// See [ServiceAppExt::register_service_instance].
+    pub fn instance<T: Service>(key: &str) -> Self {
+        Self(format!("{}[{key}]", std::any::type_name::<T>()))
+    }
+
+    /// Creates a key from a string, e.g. one previously stored with
+    /// [ServiceKey::as_str].
+    pub fn from_string(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+
+    /// Gets the key as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+impl std::fmt::Display for ServiceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -1,12 +1,14 @@
-use crate::graph::{DagError, DependencyGraph, NodeId};
+use crate::graph::{DagError, DependencyGraph, NodeId, NodeIdPair};
 use crate::prelude::*;
+use bevy_app::{App, Plugin};
 use bevy_asset::{
-    Asset, AssetServer, Handle, LoadState, RecursiveDependencyLoadState, UntypedAssetId,
+    Asset, AssetPath, AssetServer, Handle, LoadState, RecursiveDependencyLoadState, UntypedAssetId,
 };
+use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::component::ComponentId;
 use bevy_ecs::prelude::*;
 use bevy_ecs::system::SystemId;
-use tracing::*;
+use tracing::{debug, warn};
 
 /// This is the underlying data for an [Asset] dependency. Asset dependencies
 /// are kept alive by storing a strong handle in an entity,
@@ -22,13 +24,26 @@ pub struct AssetData {
     pub status: ServiceStatus,
     /// An entity containing a strong handle to the underyling [Asset].
     pub container: Entity,
+    /// The services currently holding this dependency up. The strong handle
+    /// is only dropped once the last of them goes down.
+    pub owners: Vec<NodeId>,
+    /// Weak assets are loaded and kept alive like any other, but their load
+    /// state never blocks or fails their owners. See
+    /// [ServiceScope::add_weak_asset].
+    pub weak: bool,
 }
 
 /// This is the underyling data for a [Resource] dependency. Resource deps are
 /// literal resources whose lifetimes are equivalent to the service's lifetime.
 /// You can define how the resource is initialized and deinitialized using the
-/// included init and deinit functions, stored here as entities. These may not
-/// be async.
+/// included init and deinit functions, stored here as entities. If the
+/// resource was added with
+/// [add_resource_async_with](ServiceScope::add_resource_async_with), its init
+/// function returns an [AsyncHook] which inserts the resource once it's done.
+///
+/// A resource shared between several services is reference counted: it's
+/// initialized when the first of them spins up, and removed when the last of
+/// them spins down.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 #[allow(missing_docs, reason = "obvious")]
 pub struct ResourceData {
@@ -39,6 +54,12 @@ pub struct ResourceData {
     pub init: Entity,
     /// The deinitialisation function, as an Entity.
     pub deinit: Entity,
+    /// The services currently holding this dependency up.
+    pub owners: Vec<NodeId>,
+    /// Whether the init function is asynchronous.
+    pub is_async: bool,
+    /// The pending initialization task of an async resource, as an Entity.
+    pub task: Option<Entity>,
 }
 
 /// The main abstraction for service dependencies. This includes the underyling
@@ -47,6 +68,10 @@ pub struct ResourceData {
 /// All data for services is stored through this abstraction and placed in the
 /// [GraphDataCache] resource for global access.
 #[allow(missing_docs)]
+#[allow(
+    clippy::large_enum_variant,
+    reason = "most entries in the cache are services"
+)]
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum GraphData {
     /// A Service as a service dependency.
@@ -94,8 +119,21 @@ impl GraphData {
             init,
             deinit,
             status: ServiceStatus::uninit(),
+            owners: vec![],
+            is_async: false,
+            task: None,
         })
     }
+    /// Create a resource dependency which initializes asynchronously.
+    /// The init system must impl `IntoSystem<(), AsyncHook, _>`, and the
+    /// deinit system `IntoSystem<(),(), _>`.
+    pub fn resource_async<R: Resource>(world: &mut World, init: Entity, deinit: Entity) -> Self {
+        let mut data = Self::resource::<R>(world, init, deinit);
+        if let Self::Resource(data) = &mut data {
+            data.is_async = true;
+        }
+        data
+    }
     #[allow(missing_docs)]
     pub fn is_resource(&self) -> bool {
         matches!(self, Self::Resource { .. })
@@ -121,12 +159,21 @@ impl GraphData {
     /// (internal component struct) which will keep the handle alive at least as
     /// long as the service is up.
     pub fn asset<T: Asset, S: Service>(handle: Handle<T>, world: &mut World) -> Self {
-        let entity = world.spawn(KeepHandleAlive::<T>(handle.clone())).id();
+        let entity = world
+            .spawn((
+                KeepHandleAlive::<T>(handle.clone()),
+                ReloadAsset(|server, path| {
+                    let _ = server.load::<T>(path);
+                }),
+            ))
+            .id();
         Self::Asset(AssetData {
             id: handle.untyped().id(),
             name: name_from_type::<T>(),
             container: entity,
             status: ServiceStatus::uninit(),
+            owners: vec![],
+            weak: false,
         })
     }
 
@@ -166,49 +213,116 @@ impl GraphData {
             GraphData::Asset(AssetData { id, .. }) => NodeId::Asset(*id),
         }
     }
+    /// Is this a resource or asset which the given service no longer holds
+    /// up? Shared dependencies stay up until their last owner goes down.
+    pub fn released_by(&self, owner: NodeId) -> bool {
+        match self {
+            GraphData::Service(_) => false,
+            GraphData::Resource(ResourceData { owners, .. })
+            | GraphData::Asset(AssetData { owners, .. }) => !owners.contains(&owner),
+        }
+    }
+    /// Is this a weak asset? See [AssetData::weak].
+    pub fn is_weak(&self) -> bool {
+        matches!(self, GraphData::Asset(AssetData { weak: true, .. }))
+    }
     #[allow(missing_docs)]
     pub fn status(&self) -> ServiceStatus {
+        self.status_ref().clone()
+    }
+    /// Gets the status without cloning it, e.g. for checks run every frame.
+    pub(crate) fn status_ref(&self) -> &ServiceStatus {
         match self {
-            GraphData::Service(ServiceData { status, .. }) => status.clone(),
-            GraphData::Resource(ResourceData { status, .. }) => status.clone(),
-            GraphData::Asset(AssetData { status, .. }) => status.clone(),
+            GraphData::Service(ServiceData { status, .. }) => status,
+            GraphData::Resource(ResourceData { status, .. }) => status,
+            GraphData::Asset(AssetData { status, .. }) => status,
         }
     }
 
-    /// Initializes or deinitializes the dep.
-    /// Called during ServiceData::handle_dep
+    /// Initializes or deinitializes the dep on behalf of the service `owner`.
+    /// Resources and assets are only torn down once their last owner goes down.
+    /// Called during ServiceData::cycle_deps
     pub(crate) fn cycle(
         &mut self,
         world: &mut World,
+        owner: NodeId,
         down_reason: Option<DownReason>,
     ) -> Result<(), ServiceError> {
-        let is_init = down_reason.is_none();
         match self {
             GraphData::Service(service) => cycle_service(world, service, down_reason.clone()),
-            GraphData::Resource(ResourceData { init, deinit, .. }) => {
-                if is_init {
-                    let init: SystemId<(), ()> = SystemId::from_entity(*init);
+            GraphData::Resource(ResourceData {
+                init,
+                deinit,
+                status,
+                owners,
+                is_async,
+                task,
+                ..
+            }) => {
+                if let Some(reason) = down_reason {
+                    owners.retain(|id| *id != owner);
+                    if !owners.is_empty() {
+                        return Ok(());
+                    }
+                    if let Some(task) = task.take()
+                        && let Ok(entity) = world.get_entity_mut(task)
+                    {
+                        entity.despawn();
+                    }
+                    let deinit: SystemId<(), ()> = SystemId::from_entity(*deinit);
                     world
-                        .run_system(init)
+                        .run_system(deinit)
                         .expect("Function signature should match.");
-                    Ok(())
+                    *status = ServiceStatus::Down(reason);
                 } else {
-                    let deinit: SystemId<(), ()> = SystemId::from_entity(*deinit);
+                    if !owners.contains(&owner) {
+                        owners.push(owner);
+                    }
+                    if (status.is_up() || status.is_initializing()) && owners.len() > 1 {
+                        return Ok(());
+                    }
+                    if *is_async {
+                        let init: SystemId<(), AsyncHook> = SystemId::from_entity(*init);
+                        let hook = world
+                            .run_system(init)
+                            .expect("Function signature should match.");
+                        if let Some(old) = task.replace(world.spawn(hook).id()) {
+                            world.despawn(old);
+                        }
+                        *status = ServiceStatus::Init;
+                        return Ok(());
+                    }
+                    let init: SystemId<(), ()> = SystemId::from_entity(*init);
                     world
-                        .run_system(deinit)
+                        .run_system(init)
                         .expect("Function signature should match.");
-                    Ok(())
+                    *status = ServiceStatus::Up;
                 }
+                Ok(())
             }
             GraphData::Asset(AssetData {
-                container, status, ..
+                container,
+                status,
+                owners,
+                ..
             }) => {
                 if let Some(reason) = down_reason {
+                    owners.retain(|id| *id != owner);
+                    if !owners.is_empty() {
+                        return Ok(());
+                    }
+                    if status.is_failed() {
+                        // keep the handle so the load can be retried, see
+                        // ServiceWorldExt::reload_asset_dep
+                        return Ok(());
+                    }
                     // drop container so the strong handle is dropped
                     // NOTE this does not mean the asset is necessarily removed from the world
                     // there might be another strong handle active
                     world.despawn(*container);
                     *status = ServiceStatus::Down(reason);
+                } else if !owners.contains(&owner) {
+                    owners.push(owner);
                 }
                 Ok(())
             }
@@ -222,7 +336,7 @@ impl GraphData {
 pub enum DepInitErr {
     #[error("Service '{0}' failed to initialize with message:\n{1}")]
     Service(String, String),
-    #[error("Dependency '{0}' not found.")]
+    #[error("Dependency '{0}' not found. Did you forget to call `register_service::<{0}>()`?")]
     NotFound(String),
     #[error("Dependency '{0}' depends on itself.")]
     DepLoop(String),
@@ -230,70 +344,264 @@ pub enum DepInitErr {
     DepCycle(#[from] DagError),
 }
 
+/// Error returned by [ServiceWorldExt::deregister_service].
+#[allow(missing_docs)]
+#[derive(thiserror::Error, Debug, Clone)]
+pub enum DeregisterErr {
+    #[error("Service '{0}' is not registered.")]
+    NotRegistered(String),
+    #[error("Service '{0}' can't be deregistered while '{1}' depend(s) on it.")]
+    HasDependents(String, String),
+}
+
 /// Adds a service to the dependency graph. Will fail if cycles are detected.
 /// Returns the topsort of the passed in dependencies.
+/// Cycles and new edges are reported using the names in the cache.
 pub(crate) fn register_deps(
     global_graph: &mut DependencyGraph,
-    parent: NodeId,
+    cache: &GraphDataCache,
+    (parent, parent_name): (NodeId, &str),
     deps: Vec<NodeId>,
 ) -> Result<Vec<NodeId>, DepInitErr> {
-    // NOTE: We're duplicating the dependency heirarchy here.
-    // Could blow up.
-    // Ideally the local graphs are just references to the global graph.
-    add_and_sort(global_graph, parent, deps)?;
-    let topsort = global_graph.subgraph(parent).topsort_graph()?;
-    Ok(topsort)
+    add_and_sort(global_graph, cache, (parent, parent_name), deps)?;
+    Ok(global_graph.reachable_topsort(parent))
 }
 
 fn add_and_sort(
     graph: &mut DependencyGraph,
-    parent: NodeId,
+    cache: &GraphDataCache,
+    (parent, parent_name): (NodeId, &str),
     deps: Vec<NodeId>,
 ) -> Result<(), DepInitErr> {
     graph.add_node(parent);
-    for dep in deps {
-        graph.add_node(dep);
-        graph.add_edge(parent, dep);
-    }
+    let edges = deps
+        .into_iter()
+        .map(|dep| (parent, dep))
+        .filter(|(a, b)| !graph.contains_edge(*a, *b))
+        .collect::<Vec<_>>();
     // see if the graph makes sense...
-    match graph.topsort_graph() {
-        Ok(vec) => {
-            graph.topsort = vec;
+    let res = graph.add_edges(&edges);
+    if res.is_ok() {
+        for dep in edges.iter().map(|(_, dep)| dep) {
+            let dep_name = cache
+                .get(dep)
+                .map(|data| data.name().to_string())
+                .unwrap_or_else(|| format!("{dep:?}"));
+            debug!(
+                ?parent,
+                parent_name,
+                ?dep,
+                dep_name,
+                "Adding dep: {parent_name} -> {dep_name}"
+            );
         }
-        Err(e) => {
-            let err = if let DagError::DependencyLoop(name) = e {
-                DepInitErr::DepLoop(name)
-            } else {
-                e.into()
-            };
-            return Err(err);
+    }
+    res.map_err(|e| {
+        let e = match e {
+            DagError::DependencyCycle(_) => DagError::DependencyCycle(
+                graph.get_dependency_cycles_error_message(&graph.find_cycles(), Some(cache)),
+            ),
+            e => e,
+        };
+        // leave the graph as it was so other services can still register
+        for (a, b) in edges.iter().copied() {
+            graph.remove_edge(a, b);
         }
+        graph
+            .update_topsort()
+            .expect("Graph should be valid once the new edges are removed.");
+        match e {
+            DagError::DependencyLoop(name) => DepInitErr::DepLoop(name),
+            e => e.into(),
+        }
+    })
+}
+
+/// Describes each dependency of a registered service which was never
+/// registered itself, sorted.
+fn unregistered_deps(cache: &GraphDataCache) -> Vec<String> {
+    let mut missing = cache
+        .values()
+        .filter_map(GraphData::as_service)
+        .filter(|service| service.registered())
+        .flat_map(|service| {
+            service
+                .deps()
+                .iter()
+                .filter_map(|id| cache.get_service(*id))
+                .filter(|dep| !dep.registered())
+                .map(|dep| {
+                    format!(
+                        "Service '{}' depends on '{}', which was never registered. Did you forget to call `register_service::<{}>()`?",
+                        service.name(),
+                        dep.name(),
+                        dep.name()
+                    )
+                })
+        })
+        .collect::<Vec<_>>();
+    missing.sort();
+    missing
+}
+
+/// Marks the [DependencyGraph] as validated by [validate_deps].
+#[derive(Resource, Default)]
+pub(crate) struct DepsValidated;
+
+/// Panics if any registered service depends on a service which was never
+/// registered, naming each such pair. Services may be registered in any order,
+/// so this runs once the app is finished building. See [DepValidationPlugin].
+pub(crate) fn validate_deps(world: &mut World) {
+    let missing = unregistered_deps(world.resource::<GraphDataCache>());
+    if !missing.is_empty() {
+        panic!("{}", missing.join("\n"));
+    }
+    world.init_resource::<DepsValidated>();
+}
+
+/// Warns about each dependency which was never registered. Runs once in
+/// [PostStartup](bevy_app::PostStartup), before any service spins up, so that
+/// apps which are updated by hand and never [finished](bevy_app::App::finish)
+/// still hear about missing registrations before the services depending on
+/// them fail. Finished apps have already been checked by [validate_deps].
+pub(crate) fn warn_unregistered_deps(
+    cache: Res<GraphDataCache>,
+    validated: Option<Res<DepsValidated>>,
+) {
+    if validated.is_some() {
+        return;
+    }
+    for message in unregistered_deps(&cache) {
+        warn!("{message}");
+    }
+}
+
+/// The direct deps of each service whose registration was rejected because
+/// they would form a cycle. See [ServiceAppExt::validate_services].
+#[derive(Resource, Deref, DerefMut, Default, Debug)]
+pub(crate) struct RejectedDeps(Vec<(NodeId, Vec<NodeId>)>);
+
+/// Reports every problem with the registered services at once: each cycle or
+/// loop, including those formed by rejected registrations, and each dep which
+/// was never registered. See [ServiceAppExt::validate_services].
+pub(crate) fn validate_services(world: &World) -> Result<(), Vec<DepInitErr>> {
+    let cache = world.resource::<GraphDataCache>();
+    let rejected = world.resource::<RejectedDeps>();
+    let name = |id: &NodeId| {
+        cache
+            .get(id)
+            .map(|data| data.name().to_string())
+            .unwrap_or_else(|| format!("{id:?}"))
+    };
+
+    // rebuild the graph as it would be had every registration succeeded
+    let mut graph = DependencyGraph::default();
+    for NodeIdPair(a, b) in world.resource::<DependencyGraph>().all_edges() {
+        graph.add_edge(a, b);
+    }
+    for (parent, deps) in rejected.iter() {
+        for dep in deps {
+            graph.add_edge(*parent, *dep);
+        }
+    }
+    let mut errors = vec![];
+    if graph.topsort_graph().is_err() {
+        for cycle in graph.find_cycles() {
+            errors.push(match cycle.as_slice() {
+                [node] => DepInitErr::DepLoop(name(node)),
+                _ => DepInitErr::DepCycle(DagError::DependencyCycle(
+                    graph.get_dependency_cycles_error_message(&[cycle], Some(cache)),
+                )),
+            });
+        }
+    }
+
+    let mut missing = graph
+        .all_edges()
+        .map(|NodeIdPair(_, dep)| dep)
+        .filter(|dep| {
+            cache.get_service(*dep).is_some_and(|dep| !dep.registered())
+                && !rejected.iter().any(|(id, _)| id == dep)
+        })
+        .map(|dep| name(&dep))
+        .collect::<Vec<_>>();
+    missing.sort();
+    missing.dedup();
+    errors.extend(missing.into_iter().map(DepInitErr::NotFound));
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Validates the [DependencyGraph] once every plugin has been built.
+pub(crate) struct DepValidationPlugin;
+impl Plugin for DepValidationPlugin {
+    fn build(&self, _: &mut App) {}
+
+    fn finish(&self, app: &mut App) {
+        validate_deps(app.world_mut());
     }
-    Ok(())
 }
 
 /// Contains a strong asset handle. Used to keep the asset alive at least as long as the owning service.
 #[derive(Component)]
 pub struct KeepHandleAlive<T: Asset>(pub Handle<T>);
 
+/// Stored alongside [KeepHandleAlive]. Requests the asset's load again with
+/// its type, which the [AssetServer] only does if the load failed. See
+/// [ServiceWorldExt::reload_asset_dep].
+#[derive(Component)]
+pub(crate) struct ReloadAsset(pub fn(&AssetServer, AssetPath<'static>));
+
 /// System run every pre-update to check service dependency status. Will update
 /// the stored dependency's status.\
-/// NOTE: For now, this only updates Asset dependencies, as Service dependencies
-/// have their own logic, and Resources are not async.
+/// NOTE: This only updates Asset dependencies and async Resource
+/// dependencies, as Service dependencies have their own logic. Assets shared
+/// between services have a single status, which is updated as long as any of
+/// its owners holds it.
 pub(crate) fn update_dep_status<S: Service>(
     service: ServiceRef<S>,
     asset_server: Res<AssetServer>,
     mut cache: ResMut<GraphDataCache>,
+    mut commands: Commands,
+    mut q_tasks: Query<&mut AsyncHook>,
 ) {
-    if service.status.is_down() {
-        // don't reawaken the asset dep
-        return;
-    }
     for dep in service.deps.iter() {
+        if let Some(ResourceData { status, task, .. }) = cache.get_resource_mut(*dep)
+            && let Some(entity) = *task
+        {
+            let Ok(mut hook) = q_tasks.get_mut(entity) else {
+                continue;
+            };
+            let Some(res) = hook.poll() else {
+                continue;
+            };
+            commands.entity(entity).despawn();
+            *task = None;
+            *status = match res {
+                Ok(mut queue) => {
+                    commands.append(&mut queue);
+                    ServiceStatus::Up
+                }
+                // the dependent wraps this in a dependency error, see deps_ok
+                Err(e) => ServiceStatus::Down(DownReason::Failed(e)),
+            };
+            continue;
+        }
         if let Some(AssetData {
-            id, name, status, ..
+            id,
+            name,
+            status,
+            owners,
+            ..
         }) = cache.get_asset_mut(*dep)
         {
+            if owners.is_empty() {
+                // don't reawaken the asset dep
+                continue;
+            }
             *status = update_asset_status(&asset_server, *id, name);
         }
     }
@@ -313,13 +621,13 @@ fn update_asset_status(server: &AssetServer, id: UntypedAssetId, name: &str) ->
         }
         (LoadState::Loaded, RecursiveDependencyLoadState::Loaded) => ServiceStatus::Up,
         (_, RecursiveDependencyLoadState::Failed(asset_load_error)) => {
-            ServiceStatus::Down(DownReason::Failed(ServiceError::Dependency(
-                name.to_string(),
-                asset_load_error.to_string(),
+            ServiceStatus::Down(DownReason::Failed(ServiceError::dependency(
+                name,
+                ServiceError::from_error(asset_load_error),
             )))
         }
         (LoadState::Failed(asset_load_error), _) => ServiceStatus::Down(DownReason::Failed(
-            ServiceError::Own(asset_load_error.to_string()),
+            ServiceError::from_error(asset_load_error),
         )),
         _ => ServiceStatus::Init,
     }
@@ -334,9 +642,9 @@ fn cycle_service(
 ) -> Result<(), ServiceError> {
     // if the dep is not registered, we can't spin it up
     if !service.registered() {
-        return Err(ServiceError::Dependency(
-            service.name().to_string(),
-            "Service has not been registered.".to_string(),
+        return Err(ServiceError::dependency(
+            service.name(),
+            ServiceError::from_error(DepInitErr::NotFound(service.name().to_string())),
         ));
     }
     let status = service.status();
@@ -349,7 +657,8 @@ fn cycle_service(
         if let Some(reason) = down_reason.clone() {
             match reason {
                 DownReason::Failed(error) => service.fail(world, error),
-                DownReason::SpunDown => service.spin_down(world),
+                // custom reasons belong to the dependent
+                DownReason::SpunDown | DownReason::Custom(_) => service.spin_down(world),
                 _ => {
                     unreachable!()
                 }
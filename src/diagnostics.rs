@@ -0,0 +1,66 @@
+use crate::prelude::*;
+use bevy_app::prelude::*;
+use bevy_diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic};
+use bevy_ecs::prelude::*;
+
+/// Adds [Diagnostics](bevy_diagnostic::Diagnostic) counting the registered
+/// services in each status, measured every frame.
+///
+/// Failed services are counted separately from those which are down, and
+/// services on [standby](ServiceStatus::Standby) or deinitializing aren't
+/// counted.
+///
+/// ## Example usage
+/// ```rust
+/// # use q_service::prelude::*;
+/// # use bevy::prelude::*;
+/// let mut app = App::new();
+/// app.add_plugins(ServiceDiagnosticsPlugin);
+/// ```
+#[derive(Default, Debug)]
+pub struct ServiceDiagnosticsPlugin;
+impl ServiceDiagnosticsPlugin {
+    /// The number of services which are up.
+    pub const SERVICES_UP: DiagnosticPath = DiagnosticPath::const_new("services/up");
+    /// The number of services which are initializing.
+    pub const SERVICES_INITIALIZING: DiagnosticPath =
+        DiagnosticPath::const_new("services/initializing");
+    /// The number of services which are down, not counting failed services.
+    pub const SERVICES_DOWN: DiagnosticPath = DiagnosticPath::const_new("services/down");
+    /// The number of services which have failed.
+    pub const SERVICES_FAILED: DiagnosticPath = DiagnosticPath::const_new("services/failed");
+}
+impl Plugin for ServiceDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        crate::service_trait::init_service_globals(app);
+        app.register_diagnostic(Diagnostic::new(Self::SERVICES_UP))
+            .register_diagnostic(Diagnostic::new(Self::SERVICES_INITIALIZING))
+            .register_diagnostic(Diagnostic::new(Self::SERVICES_DOWN))
+            .register_diagnostic(Diagnostic::new(Self::SERVICES_FAILED))
+            .add_systems(Update, measure_services);
+    }
+}
+
+/// Counts the services in the [GraphDataCache] by status.
+fn measure_services(cache: Res<GraphDataCache>, mut diagnostics: Diagnostics) {
+    let (mut up, mut initializing, mut down, mut failed) = (0, 0, 0, 0);
+    for service in cache
+        .values()
+        .filter_map(GraphData::as_service)
+        .filter(|service| service.registered())
+    {
+        match service.status {
+            ServiceStatus::Up => up += 1,
+            ServiceStatus::Init => initializing += 1,
+            ServiceStatus::Down(DownReason::Failed(_)) => failed += 1,
+            ServiceStatus::Down(_) => down += 1,
+            ServiceStatus::Deinit(_) | ServiceStatus::Standby => {}
+        }
+    }
+    diagnostics.add_measurement(&ServiceDiagnosticsPlugin::SERVICES_UP, || up as f64);
+    diagnostics.add_measurement(&ServiceDiagnosticsPlugin::SERVICES_INITIALIZING, || {
+        initializing as f64
+    });
+    diagnostics.add_measurement(&ServiceDiagnosticsPlugin::SERVICES_DOWN, || down as f64);
+    diagnostics.add_measurement(&ServiceDiagnosticsPlugin::SERVICES_FAILED, || failed as f64);
+}
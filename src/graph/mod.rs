@@ -1,6 +1,6 @@
 pub(crate) mod tarjan;
 
-use std::fmt::Debug;
+use std::{cmp::Ordering, fmt::Debug, hash::BuildHasher, sync::Arc};
 
 use bevy_asset::UntypedAssetId;
 use bevy_ecs::{component::ComponentId, resource::Resource};
@@ -12,6 +12,8 @@ use indexmap::IndexMap;
 use smallvec::SmallVec;
 use thiserror::Error;
 
+use crate::data::GraphDataCache;
+
 /// The ID of a service dependency, as stored in the [DependencyGraph].
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum NodeId {
@@ -21,8 +23,16 @@ pub enum NodeId {
     Resource(ComponentId),
     /// NodeId for an Asset. Just an UntypedAssetId.
     Asset(UntypedAssetId),
+    /// NodeId for an instance of a Service, registered with
+    /// [ServiceAppExt::register_service_instance](crate::prelude::ServiceAppExt::register_service_instance).
+    /// Holds the Service's ComponentId and a hash of the instance's key.
+    ServiceInstance(ComponentId, u64),
 }
 impl NodeId {
+    /// Gets the id of the instance of the service with the given ComponentId.
+    pub fn service_instance(id: ComponentId, key: &str) -> Self {
+        NodeId::ServiceInstance(id, FixedHasher.hash_one(key))
+    }
     /// Gets the underlying id for a service.
     pub fn service_id(self) -> Option<ComponentId> {
         if let NodeId::Service(id) = self {
@@ -55,7 +65,7 @@ pub struct NodeIdAndDir(NodeId, Direction);
 
 /// Compact storage of a [`NodeId`] pair.
 #[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
-pub struct NodeIdPair(NodeId, NodeId);
+pub struct NodeIdPair(pub(crate) NodeId, pub(crate) NodeId);
 
 /// Edge direction.
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Ord, Eq, Hash)]
@@ -77,6 +87,16 @@ impl Direction {
         }
     }
 }
+/// Orders nodes which have no ordering constraint between them. See
+/// [DependencyGraph::set_tiebreaker].
+#[derive(Clone)]
+struct Tiebreaker(Arc<dyn Fn(NodeId, NodeId) -> Ordering + Send + Sync>);
+impl Debug for Tiebreaker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Tiebreaker")
+    }
+}
+
 /// A directed acyclic graph structure used to track service dependencies.
 /// Based on [bevy_ecs::schedule::graph]
 #[derive(Default, Debug, Resource)]
@@ -85,9 +105,66 @@ pub struct DependencyGraph {
     edges: HashSet<NodeIdPair, FixedHasher>,
     /// A cached topological ordering of the graph.
     pub(crate) topsort: Vec<NodeId>,
+    tiebreaker: Option<Tiebreaker>,
+    /// Whether nodes or edges have changed since the topsort was cached.
+    dirty: bool,
+    /// How many times the cached topsort has been computed.
+    #[cfg(feature = "testing")]
+    sort_count: usize,
 }
 
 impl DependencyGraph {
+    /// The cached topological ordering of the graph. Dependents are placed
+    /// before their dependencies.
+    pub fn topsort(&self) -> &[NodeId] {
+        &self.topsort
+    }
+
+    /// Sets a comparison used to order nodes which have no ordering constraint
+    /// between them, e.g. two services which don't depend on each other.
+    /// Nodes which compare as [Ordering::Less] are placed earlier in the
+    /// topsort. Re-sorts the cached topsort.
+    pub fn set_tiebreaker(
+        &mut self,
+        tiebreaker: impl Fn(NodeId, NodeId) -> Ordering + Send + Sync + 'static,
+    ) {
+        self.tiebreaker = Some(Tiebreaker(Arc::new(tiebreaker)));
+        self.dirty = true;
+        let _ = self.update_topsort();
+    }
+
+    /// How many times the cached topsort has been computed. The topsort is
+    /// only recomputed when the graph has changed. Requires the `testing`
+    /// feature.
+    #[cfg(feature = "testing")]
+    pub fn sort_count(&self) -> usize {
+        self.sort_count
+    }
+
+    /// Recomputes the cached topsort if the graph has changed since it was
+    /// last computed. On error the cache is left untouched.
+    pub fn update_topsort(&mut self) -> Result<(), DagError> {
+        if !self.dirty {
+            return Ok(());
+        }
+        #[cfg(feature = "testing")]
+        {
+            self.sort_count += 1;
+        }
+        self.topsort = self.topsort_graph()?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Adds a batch of edges, as with [DependencyGraph::add_edge], then
+    /// validates the graph and updates the cached topsort once.
+    pub fn add_edges(&mut self, edges: &[(NodeId, NodeId)]) -> Result<(), DagError> {
+        for (a, b) in edges.iter().copied() {
+            self.add_edge(a, b);
+        }
+        self.update_topsort()
+    }
+
     /// Return the number of nodes in the graph.
     pub fn node_count(&self) -> usize {
         self.nodes.len()
@@ -95,7 +172,10 @@ impl DependencyGraph {
 
     /// Add node `n` to the graph if it doesn't already exist.
     pub fn add_node(&mut self, n: NodeId) {
-        self.nodes.entry(n).or_default();
+        if !self.nodes.contains_key(&n) {
+            self.nodes.insert(n, Vec::new());
+            self.dirty = true;
+        }
     }
 
     #[inline]
@@ -110,6 +190,7 @@ impl DependencyGraph {
         let Some(links) = self.nodes.swap_remove(&n) else {
             return;
         };
+        self.dirty = true;
 
         let links = links.into_iter();
 
@@ -135,8 +216,10 @@ impl DependencyGraph {
     /// For a directed graph, the edge is directed from `a` to `b`.
     ///
     /// Inserts nodes `a` and/or `b` if they aren't already part of the graph.
+    /// Does not update the cached topsort; see [DependencyGraph::add_edges].
     pub fn add_edge(&mut self, a: NodeId, b: NodeId) {
         if self.edges.insert(Self::edge_key(a, b)) {
+            self.dirty = true;
             // insert in the adjacency list if it's a new edge
             self.nodes
                 .entry(a)
@@ -163,7 +246,7 @@ impl DependencyGraph {
         let Some(index) = sus
             .iter()
             .copied()
-            .position(|elt| (elt == NodeIdAndDir(b, dir)))
+            .position(|elt| elt == NodeIdAndDir(b, dir))
         else {
             return false;
         };
@@ -175,7 +258,7 @@ impl DependencyGraph {
     /// Remove edge from `a` to `b` from the graph.
     ///
     /// Return `false` if the edge didn't exist.
-    pub fn _remove_edge(&mut self, a: NodeId, b: NodeId) -> bool {
+    pub fn remove_edge(&mut self, a: NodeId, b: NodeId) -> bool {
         let exist1 = self.remove_single_edge(a, b, Direction::Outgoing);
         let exist2 = if a != b {
             self.remove_single_edge(b, a, Direction::Incoming)
@@ -183,13 +266,14 @@ impl DependencyGraph {
             exist1
         };
         let weight = self.edges.remove(&Self::edge_key(a, b));
+        self.dirty |= weight;
         debug_assert!(exist1 == exist2 && exist1 == weight);
         weight
     }
 
     /// Return `true` if the edge connecting `a` with `b` is contained in the
     /// graph.
-    pub fn _contains_edge(&self, a: NodeId, b: NodeId) -> bool {
+    pub fn contains_edge(&self, a: NodeId, b: NodeId) -> bool {
         self.edges.contains(&Self::edge_key(a, b))
     }
 
@@ -211,6 +295,13 @@ impl DependencyGraph {
             .filter_map(|NodeIdAndDir(n, dir)| (dir == Direction::Outgoing).then_some(n))
     }
 
+    /// Return an iterator of the nodes with an edge ending at `a`, i.e. the
+    /// services which depend on `a`.
+    pub fn dependents(&self, a: NodeId) -> impl DoubleEndedIterator<Item = NodeId> + '_ {
+        self._neighbors_directed(a, Direction::Incoming)
+            .filter(move |n| *n != a)
+    }
+
     /// Return an iterator of all neighbors that have an edge between them and
     /// `a`, in the specified direction.
     /// If the graph's edges are undirected, this is equivalent to
@@ -275,6 +366,28 @@ impl DependencyGraph {
         tarjan::new_tarjan_scc(self)
     }
 
+    /// Finds every simple cycle in the graph, including nodes which depend on
+    /// themselves. Useful for validating wiring before registering services.
+    pub fn find_cycles(&self) -> Vec<Vec<NodeId>> {
+        let mut cycles = self
+            .all_edges()
+            .filter(|NodeIdPair(left, right)| left == right)
+            .map(|NodeIdPair(node, _)| vec![node])
+            .collect::<Vec<_>>();
+        for scc in self.iter_sccs().filter(|scc| scc.len() > 1) {
+            cycles.append(&mut simple_cycles_in_component(self, &scc));
+        }
+        cycles
+    }
+
+    /// Does the graph have no cycles? See [DependencyGraph::find_cycles].
+    pub fn is_acyclic(&self) -> bool {
+        !self
+            .all_edges()
+            .any(|NodeIdPair(left, right)| left == right)
+            && self.iter_sccs().all(|scc| scc.len() == 1)
+    }
+
     /// Tries to topologically sort `graph`.
     ///
     /// If the graph is acyclic, returns [`Ok`] with the list of [`NodeId`] in a
@@ -316,41 +429,91 @@ impl DependencyGraph {
         if sccs_with_cycles.is_empty() {
             // reverse to get topological order
             top_sorted_nodes.reverse();
-            Ok(top_sorted_nodes)
+            match &self.tiebreaker {
+                Some(tiebreaker) => {
+                    Ok(self.break_ties(tiebreaker, &top_sorted_nodes, self.all_edges()))
+                }
+                None => Ok(top_sorted_nodes),
+            }
         } else {
             let mut cycles = Vec::new();
             for scc in &sccs_with_cycles {
                 cycles.append(&mut simple_cycles_in_component(self, scc));
             }
             let error =
-                DagError::DependencyCycle(self.get_dependency_cycles_error_message(&cycles));
+                DagError::DependencyCycle(self.get_dependency_cycles_error_message(&cycles, None));
 
             Err(error)
         }
     }
 
-    fn get_dependency_cycles_error_message(&self, cycles: &[Vec<NodeId>]) -> String {
+    /// Re-orders an acyclic topsort so that, of the nodes whose dependents
+    /// have all been placed, the least according to the tiebreaker comes next.
+    /// `edges` must contain every edge between the given nodes, and no others.
+    fn break_ties(
+        &self,
+        tiebreaker: &Tiebreaker,
+        nodes: &[NodeId],
+        edges: impl Iterator<Item = NodeIdPair>,
+    ) -> Vec<NodeId> {
+        let mut in_degree: HashMap<NodeId, usize> = nodes.iter().map(|node| (*node, 0)).collect();
+        for NodeIdPair(_, b) in edges {
+            *in_degree.entry(b).or_default() += 1;
+        }
+        let mut ready = nodes
+            .iter()
+            .copied()
+            .filter(|node| in_degree[node] == 0)
+            .collect::<Vec<_>>();
+        let mut sorted = Vec::with_capacity(nodes.len());
+        while let Some((index, _)) = ready
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (tiebreaker.0)(**a, **b))
+        {
+            let node = ready.remove(index);
+            sorted.push(node);
+            for neighbor in self.neighbors(node) {
+                let degree = in_degree.entry(neighbor).or_default();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(neighbor);
+                }
+            }
+        }
+        sorted
+    }
+
+    /// Describes each cycle as a chain of dependencies, e.g. `Foo → Bar → Foo`.
+    /// Nodes are named by their entry in the cache, falling back to the
+    /// [NodeId] itself for nodes which have no entry.
+    pub(crate) fn get_dependency_cycles_error_message(
+        &self,
+        cycles: &[Vec<NodeId>],
+        cache: Option<&GraphDataCache>,
+    ) -> String {
         use std::fmt::Write;
-        let mut message = format!("Service has {} before/after cycle(s):\n", cycles.len());
+        let name = |id: &NodeId| {
+            cache
+                .and_then(|cache| cache.get(id))
+                .map(|data| data.name().to_string())
+                .unwrap_or_else(|| format!("{id:?}"))
+        };
+        let mut message = format!("Found {} dependency cycle(s):\n", cycles.len());
         for (i, cycle) in cycles.iter().enumerate() {
-            let mut names = cycle.iter();
-            let first_name = names.next().unwrap();
-            writeln!(
-                message,
-                "cycle {}: `{first_name:?}` must run before itself",
-                i + 1,
-            )
-            .unwrap();
-            writeln!(message, "`{first_name:?}`").unwrap();
-            for name in names.chain(core::iter::once(first_name)) {
-                writeln!(message, " ... which must run before `{name:?}`").unwrap();
-            }
-            writeln!(message).unwrap();
+            let chain = cycle
+                .iter()
+                .chain(cycle.first())
+                .map(name)
+                .collect::<Vec<_>>()
+                .join(" → ");
+            writeln!(message, "cycle {}: {chain}", i + 1).unwrap();
         }
 
         message
     }
 
+    #[cfg(feature = "testing")]
     fn color(&self, subgraph: &mut DependencyGraph, parent: NodeId) {
         self.neighbors(parent).for_each(|neighbor| {
             if subgraph.contains_node(neighbor) {
@@ -362,16 +525,56 @@ impl DependencyGraph {
         })
     }
 
-    pub(crate) fn subgraph(&self, node: NodeId) -> DependencyGraph {
+    /// Copies the nodes reachable from `node` into a new graph, along with the
+    /// edges used to reach them. Prefer [DependencyGraph::reachable_topsort]
+    /// when only the ordering is needed. Requires the `testing` feature.
+    #[cfg(feature = "testing")]
+    pub fn subgraph(&self, node: NodeId) -> DependencyGraph {
         let mut subgraph = DependencyGraph {
-            nodes: IndexMap::default(),
-            edges: HashSet::default(),
-            topsort: Vec::default(),
+            tiebreaker: self.tiebreaker.clone(),
+            ..Default::default()
         };
         subgraph.add_node(node);
         self.color(&mut subgraph, node);
         subgraph
     }
+
+    /// Topologically sorts the nodes reachable from `node`, without copying
+    /// them into a new graph. `node` always comes first, and dependents are
+    /// placed before their dependencies. Assumes the graph is acyclic, which
+    /// [DependencyGraph::add_edges] guarantees.
+    ///
+    /// Gives the same ordering as `self.subgraph(node).topsort_graph()`.
+    pub fn reachable_topsort(&self, node: NodeId) -> Vec<NodeId> {
+        // Reverse postorder of a depth-first search, visiting neighbors in
+        // insertion order, as Tarjan's algorithm does on the subgraph.
+        let mut visited = HashSet::<NodeId>::default();
+        let mut postorder = Vec::new();
+        let mut stack = vec![(node, self.neighbors(node))];
+        visited.insert(node);
+        while let Some((current, neighbors)) = stack.last_mut() {
+            match neighbors.find(|neighbor| !visited.contains(neighbor)) {
+                Some(next) => {
+                    visited.insert(next);
+                    stack.push((next, self.neighbors(next)));
+                }
+                None => {
+                    postorder.push(*current);
+                    stack.pop();
+                }
+            }
+        }
+        postorder.reverse();
+        match &self.tiebreaker {
+            Some(tiebreaker) => {
+                let edges = postorder
+                    .iter()
+                    .flat_map(|a| self.neighbors(*a).map(move |b| Self::edge_key(*a, b)));
+                self.break_ties(tiebreaker, &postorder, edges)
+            }
+            None => postorder,
+        }
+    }
 }
 
 /// Returns the simple cycles in a strongly-connected component of a directed
@@ -3,7 +3,7 @@
 use crate::graph::DependencyGraph;
 
 use super::NodeId;
-use core::num::NonZeroUsize;
+use core::{iter::Peekable, num::NonZeroUsize};
 use smallvec::SmallVec;
 
 /// Create an iterator over *strongly connected components* using Algorithm 3 in
@@ -31,7 +31,7 @@ pub(crate) fn new_tarjan_scc(
         .nodes()
         .map(|node| NodeData {
             root_index: None,
-            neighbors: graph.neighbors(node),
+            neighbors: graph.neighbors(node).peekable(),
         })
         .collect::<Vec<_>>();
 
@@ -52,7 +52,9 @@ pub(crate) fn new_tarjan_scc(
 
 struct NodeData<N: Iterator<Item = NodeId>> {
     root_index: Option<NonZeroUsize>,
-    neighbors: N,
+    /// Peekable so that a neighbor is only consumed once it has been visited,
+    /// and its root index can be compared against.
+    neighbors: Peekable<N>,
 }
 
 /// A state for computing the *strongly connected components* using [Tarjan's
@@ -160,7 +162,7 @@ impl<'graph, A: Iterator<Item = NodeId>, N: Iterator<Item = NodeId>> TarjanScc<'
             self.index += 1;
         }
 
-        while let Some(w) = self.nodes[self.graph.to_index(v)].neighbors.next() {
+        while let Some(&w) = self.nodes[self.graph.to_index(v)].neighbors.peek() {
             // If a neighbor hasn't been visited yet...
             if self.nodes[self.graph.to_index(w)].root_index.is_none() {
                 // Push the current node and the neighbor back onto the
@@ -179,6 +181,7 @@ impl<'graph, A: Iterator<Item = NodeId>, N: Iterator<Item = NodeId>> TarjanScc<'
                     self.nodes[self.graph.to_index(w)].root_index;
                 v_is_local_root = false;
             }
+            self.nodes[self.graph.to_index(v)].neighbors.next();
         }
 
         if !v_is_local_root {
@@ -0,0 +1,125 @@
+use crate::{prelude::*, service_trait::init_service_globals};
+use bevy_app::{App, PostStartup, PreUpdate};
+use bevy_derive::{Deref, DerefMut};
+use bevy_ecs::prelude::*;
+use bevy_platform::{collections::HashMap, prelude::*};
+use core::any::TypeId;
+use std::marker::PhantomData;
+use tracing::debug;
+
+/// A label for a group of [Services](Service). Groups are declared as marker
+/// types, and services are added to them with
+/// [ServiceAppExt::add_service_to_group].
+///
+/// ## Example usage
+/// ```rust
+/// # use q_service::prelude::*;
+/// # use bevy::prelude::*;
+/// # #[derive(Resource, Debug, Default)]
+/// # struct MyService;
+/// # impl Service for MyService {
+/// #     fn build(_: &mut ServiceScope<Self>) {}
+/// # }
+/// struct CoreServices;
+/// impl ServiceGroup for CoreServices {}
+///
+/// let mut app = App::new();
+/// app.register_service::<MyService>()
+///     .add_service_to_group::<MyService>(CoreServices);
+/// ```
+pub trait ServiceGroup: Sized + Send + Sync + 'static {
+    /// Gets the display name for this group.
+    fn name() -> String {
+        name_from_type::<Self>()
+    }
+}
+
+/// The membership of a single [ServiceGroup].
+#[derive(Debug, Clone, Default)]
+pub struct ServiceGroupData {
+    /// The group's display name.
+    pub name: String,
+    /// The group's members, in the order they were added.
+    pub members: Vec<NodeId>,
+    /// Whether every member was up as of the last lifecycle update.
+    up: bool,
+}
+
+/// Stores the members of every [ServiceGroup], keyed by the group's type.
+#[derive(Resource, Deref, DerefMut, Default, Debug)]
+pub struct ServiceGroups(HashMap<TypeId, ServiceGroupData>);
+impl ServiceGroups {
+    /// Gets the members of the group `G`, in the order they were added.
+    pub fn members<G: ServiceGroup>(&self) -> &[NodeId] {
+        self.get(&TypeId::of::<G>())
+            .map(|group| group.members.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Is every member of the group `G` up? Always false for empty groups.
+    pub fn all_up<G: ServiceGroup>(&self, cache: &GraphDataCache) -> bool {
+        let members = self.members::<G>();
+        !members.is_empty()
+            && members
+                .iter()
+                .all(|id| cache.get_service(*id).is_some_and(|s| s.status().is_up()))
+    }
+}
+
+/// Fires once when every member of the [ServiceGroup] `G` has reached
+/// [ServiceStatus::Up]. Will fire again if a member goes down and the group
+/// comes back up.
+#[derive(Event, Debug)]
+pub struct GroupUp<G: ServiceGroup>(PhantomData<G>);
+impl<G: ServiceGroup> Default for GroupUp<G> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+/// Adds the service `T` to the group `G`, setting up the group's event and
+/// bookkeeping the first time the group is seen.
+pub(crate) fn add_service_to_group<T: Service, G: ServiceGroup>(app: &mut App, _group: G) {
+    init_service_globals(app);
+    let id = NodeId::Service(app.world_mut().register_resource::<T>());
+    let mut groups = app.world_mut().resource_mut::<ServiceGroups>();
+    let is_new = !groups.contains_key(&TypeId::of::<G>());
+    let group = groups
+        .entry(TypeId::of::<G>())
+        .or_insert_with(|| ServiceGroupData {
+            name: G::name(),
+            ..Default::default()
+        });
+    if !group.members.contains(&id) {
+        debug!("({}) Adding {} to group", G::name(), T::name());
+        group.members.push(id);
+    }
+    if is_new {
+        app.add_event::<GroupUp<G>>();
+        app.add_systems(
+            PreUpdate,
+            watch_group::<G>.in_set(GlobalLifecycleSystems::After),
+        );
+        app.add_systems(
+            PostStartup,
+            watch_group::<G>.in_set(GlobalLifecycleSystems::After),
+        );
+    }
+}
+
+/// Sends [GroupUp] when the last member of the group comes up.
+fn watch_group<G: ServiceGroup>(
+    mut groups: ResMut<ServiceGroups>,
+    cache: Res<GraphDataCache>,
+    mut writer: EventWriter<GroupUp<G>>,
+) {
+    let all_up = groups.all_up::<G>(&cache);
+    let Some(group) = groups.get_mut(&TypeId::of::<G>()) else {
+        return;
+    };
+    if all_up && !group.up {
+        debug!("({}) All group members are up", group.name);
+        writer.write(GroupUp::default());
+    }
+    group.up = all_up;
+}
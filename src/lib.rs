@@ -7,9 +7,15 @@ pub mod app;
 mod data;
 /// Dependency management.
 pub mod deps;
+/// [Diagnostics](bevy_diagnostic) for service health.
+pub mod diagnostics;
 pub(crate) mod graph;
+/// Groups of services which can be controlled together.
+pub mod groups;
 /// Service lifecycle functions.
 pub mod lifecycle;
+/// [Futures](core::future::Future) which resolve when services come up.
+pub mod ready;
 /// [Conditions](bevy_ecs::schedule::Condition) for service scoping.
 pub mod run_conditions;
 /// The [ServiceScope](crate::prelude::ServiceScope) struct.
@@ -23,17 +29,26 @@ mod spec;
 pub mod system_params;
 /// Asynchronous tasks forked from [q_tasks](https://docs.io/q_tasks)
 pub mod tasks;
+/// Helpers for driving services in tests. Requires the `testing` feature.
+#[cfg(feature = "testing")]
+pub mod testing;
 /// Extensions to [World](bevy_ecs::prelude::World).
 pub mod world;
 
 #[allow(missing_docs)]
 pub mod prelude {
+    #[cfg(feature = "testing")]
+    pub use crate::testing::*;
+    pub use crate::{all_services_up, any_service_up};
     pub use crate::{
         app::*,
         data::*,
         deps::*,
+        diagnostics::*,
         graph::{DependencyGraph, NodeId},
+        groups::*,
         lifecycle::{commands::*, events::*, hooks::*},
+        ready::*,
         run_conditions::*,
         scope::*,
         service_data::*,
@@ -1,51 +1,187 @@
 use crate::prelude::*;
-use bevy_ecs::prelude::*;
+use bevy_app::AppExit;
+use bevy_ecs::{event::EventCursor, prelude::*};
+use bevy_platform::prelude::*;
+use core::any::TypeId;
 use std::marker::PhantomData;
 use tracing::debug;
 
 #[derive(Event, Debug)]
 pub(crate) enum LifecycleCommand<S: Service> {
     SpinUp,
+    SpinUpIfReady,
     SpinDown,
+    SpinDownWithReason(String),
+    ForceDown,
     Restart,
+    Prewarm,
     Fail(ServiceError),
     _Placeholder(PhantomData<S>),
 }
 impl<S: Service> LifecycleCommand<S> {
+    /// Strips the service type from the command.
+    pub(crate) fn kind(&self) -> LifecycleCommandKind {
+        match self {
+            LifecycleCommand::SpinUp => LifecycleCommandKind::SpinUp,
+            LifecycleCommand::SpinUpIfReady => LifecycleCommandKind::SpinUpIfReady,
+            LifecycleCommand::SpinDown => LifecycleCommandKind::SpinDown,
+            LifecycleCommand::SpinDownWithReason(reason) => {
+                LifecycleCommandKind::SpinDownWithReason(reason.clone())
+            }
+            LifecycleCommand::ForceDown => LifecycleCommandKind::ForceDown,
+            LifecycleCommand::Restart => LifecycleCommandKind::Restart,
+            LifecycleCommand::Prewarm => LifecycleCommandKind::Prewarm,
+            LifecycleCommand::Fail(error) => LifecycleCommandKind::Fail(error.clone()),
+            LifecycleCommand::_Placeholder(_) => unreachable!(),
+        }
+    }
+}
+
+/// A lifecycle command without its service type, as stored in the service's
+/// command queue. Each variant corresponds to a method of
+/// [ServiceCommandsExt]. See [ServiceWorldExt::pending_commands].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LifecycleCommandKind {
+    /// See [ServiceCommandsExt::spin_service_up].
+    SpinUp,
+    /// See [ServiceCommandsExt::spin_service_up_if_ready].
+    SpinUpIfReady,
+    /// See [ServiceCommandsExt::spin_service_down].
+    SpinDown,
+    /// See [ServiceCommandsExt::spin_service_down_with_reason].
+    SpinDownWithReason(String),
+    /// See [ServiceCommandsExt::force_service_down].
+    ForceDown,
+    /// See [ServiceCommandsExt::restart_service].
+    Restart,
+    /// See [ServiceCommandsExt::prewarm_service].
+    Prewarm,
+    /// See [ServiceCommandsExt::fail_service].
+    Fail(ServiceError),
+}
+impl LifecycleCommandKind {
     /// Lower number = higher priority, should execute first.
-    pub(crate) fn priority(&self, service_status: ServiceStatus) -> u8 {
+    pub(crate) fn priority(&self, service_status: &ServiceStatus) -> u8 {
         match self {
-            LifecycleCommand::Fail(_) => 0,
-            LifecycleCommand::Restart => 1,
-            LifecycleCommand::SpinUp => {
-                if service_status.is_up() {
+            LifecycleCommandKind::Fail(_) | LifecycleCommandKind::ForceDown => 0,
+            LifecycleCommandKind::Restart => 1,
+            LifecycleCommandKind::SpinUp | LifecycleCommandKind::SpinUpIfReady => {
+                if service_status.is_up() { 3 } else { 2 }
+            }
+            LifecycleCommandKind::SpinDown | LifecycleCommandKind::SpinDownWithReason(_) => {
+                if service_status.is_down() {
                     3
                 } else {
                     2
                 }
             }
-            LifecycleCommand::SpinDown => {
+            LifecycleCommandKind::Prewarm => {
                 if service_status.is_down() {
-                    3
-                } else {
                     2
+                } else {
+                    3
                 }
             }
-            LifecycleCommand::_Placeholder(_) => unreachable!(),
         }
     }
 }
 
+/// The services targeted by a [BatchLifecycleCommand].
+#[derive(Debug, Clone)]
+pub(crate) enum BatchTarget {
+    /// Every registered service.
+    All,
+    /// The given services.
+    Services(Vec<NodeId>),
+    /// The members of the [ServiceGroup] with the given type.
+    Group(TypeId),
+}
+impl BatchTarget {
+    /// Resolves the target into service ids, ordered as in the cached topsort.
+    fn resolve(self, world: &World) -> Vec<NodeId> {
+        let order = world.resource::<DependencyGraph>().topsort();
+        let ids = match self {
+            BatchTarget::All => return order.to_vec(),
+            BatchTarget::Services(ids) => ids,
+            BatchTarget::Group(group) => world
+                .resource::<ServiceGroups>()
+                .get(&group)
+                .map(|group| group.members.clone())
+                .unwrap_or_default(),
+        };
+        order
+            .iter()
+            .copied()
+            .filter(|id| ids.contains(id))
+            .collect()
+    }
+}
+
+/// Lifecycle commands which target several services at once.
+#[derive(Event, Debug, Clone)]
+pub(crate) enum BatchLifecycleCommand {
+    SpinUp(BatchTarget),
+    SpinDown(BatchTarget),
+    Restart(BatchTarget),
+    Fail(BatchTarget, ServiceError),
+}
+
 /// Extensions for Commands to allow moving along the service lifecycle.
 pub trait ServiceCommandsExt {
     /// Queue the service to be spun up. Will warn and do nothing if the service is already up.
     fn spin_service_up<S: Service>(&mut self);
+    /// Queue the service to be spun up, but only if all of its service
+    /// dependencies are already up. Unlike [ServiceCommandsExt::spin_service_up],
+    /// this will not spin up any dependencies. Does nothing otherwise.
+    fn spin_service_up_if_ready<S: Service>(&mut self);
     /// Queue the service to be spun down. Will warn and do nothing if the service is already down.
     fn spin_service_down<S: Service>(&mut self);
+    /// Queue the service to be spun down with a domain-specific reason, e.g.
+    /// "Evicted". The service ends up [DownReason::Custom], and its on_down
+    /// hook receives the reason. Will warn and do nothing if the service is
+    /// already down.
+    fn spin_service_down_with_reason<S: Service>(&mut self, reason: impl Into<String>);
+    /// Queue the service to be brought down immediately, without running its
+    /// deinit or on_down hooks. Pending tasks are cancelled and dependencies
+    /// are left as they are. Useful for fast shutdown.
+    fn force_service_down<S: Service>(&mut self);
     /// Queue the service to be spun up, forcibly.
     fn restart_service<S: Service>(&mut self);
     /// Queues the service to fail with the given error. Will forcibly spin down the service.
     fn fail_service<S: Service>(&mut self, reason: ServiceError);
+    /// Queue the service to be initialized without running its on_up hook,
+    /// leaving it in [ServiceStatus::Standby]. A later spin up will skip
+    /// initialization. Will warn and do nothing if the service isn't down.
+    fn prewarm_service<S: Service>(&mut self);
+    /// Queue several services to be spun down. Dependents are spun down
+    /// before their dependencies. Services which are already down are skipped.
+    fn spin_services_down(&mut self, ids: &[NodeId]);
+    /// Queue every registered service to be spun down, dependents first. See
+    /// [ServiceCommandsExt::spin_services_down].
+    fn spin_all_services_down(&mut self);
+    /// Queue every member of the group to be spun up. Dependencies are spun up
+    /// before their dependents. Services which are already up or initializing
+    /// are skipped.
+    fn spin_group_up<G: ServiceGroup>(&mut self, group: G);
+    /// Queue every member of the group to be spun down. See
+    /// [ServiceCommandsExt::spin_services_down].
+    fn spin_group_down<G: ServiceGroup>(&mut self, group: G);
+    /// Queue an instance of the service to be spun up. See
+    /// [ServiceAppExt::register_service_instance]. Will do nothing if the
+    /// instance is already up or initializing.
+    fn spin_service_instance_up<S: Service>(&mut self, key: impl Into<String>);
+    /// Queue an instance of the service to be spun down. Will do nothing if the
+    /// instance is already down or deinitializing.
+    fn spin_service_instance_down<S: Service>(&mut self, key: impl Into<String>);
+    /// Queue an instance of the service to be spun up, forcibly. See
+    /// [ServiceCommandsExt::restart_service].
+    fn restart_service_instance<S: Service>(&mut self, key: impl Into<String>);
+    /// Queue an instance of the service to fail with the given error. See
+    /// [ServiceCommandsExt::fail_service].
+    fn fail_service_instance<S: Service>(&mut self, key: impl Into<String>, reason: ServiceError);
+    /// Queue a value to be stored for the service's hooks to share. See
+    /// [ServiceWorldExt::set_service_context].
+    fn set_service_context<S: Service, C: Send + Sync + 'static>(&mut self, context: C);
 }
 impl<'w, 's> ServiceCommandsExt for Commands<'w, 's> {
     fn spin_service_up<S: Service>(&mut self) {
@@ -53,11 +189,26 @@ impl<'w, 's> ServiceCommandsExt for Commands<'w, 's> {
         self.send_event(LifecycleCommand::SpinUp::<S>);
     }
 
+    fn spin_service_up_if_ready<S: Service>(&mut self) {
+        debug!("spin_service_up_if_ready");
+        self.send_event(LifecycleCommand::SpinUpIfReady::<S>);
+    }
+
     fn spin_service_down<S: Service>(&mut self) {
         debug!("spin_service_up");
         self.send_event(LifecycleCommand::SpinDown::<S>);
     }
 
+    fn spin_service_down_with_reason<S: Service>(&mut self, reason: impl Into<String>) {
+        debug!("spin_service_down_with_reason");
+        self.send_event(LifecycleCommand::SpinDownWithReason::<S>(reason.into()));
+    }
+
+    fn force_service_down<S: Service>(&mut self) {
+        debug!("force_service_down");
+        self.send_event(LifecycleCommand::ForceDown::<S>);
+    }
+
     fn restart_service<S: Service>(&mut self) {
         debug!("spin_service_up");
         self.send_event(LifecycleCommand::Restart::<S>);
@@ -67,41 +218,254 @@ impl<'w, 's> ServiceCommandsExt for Commands<'w, 's> {
         debug!("spin_service_up");
         self.send_event(LifecycleCommand::Fail::<S>(reason));
     }
+
+    fn prewarm_service<S: Service>(&mut self) {
+        debug!("prewarm_service");
+        self.send_event(LifecycleCommand::Prewarm::<S>);
+    }
+
+    fn spin_services_down(&mut self, ids: &[NodeId]) {
+        debug!("spin_services_down");
+        self.send_event(BatchLifecycleCommand::SpinDown(BatchTarget::Services(
+            ids.to_vec(),
+        )));
+    }
+
+    fn spin_all_services_down(&mut self) {
+        debug!("spin_all_services_down");
+        self.send_event(BatchLifecycleCommand::SpinDown(BatchTarget::All));
+    }
+
+    fn spin_group_up<G: ServiceGroup>(&mut self, _group: G) {
+        debug!("spin_group_up");
+        self.send_event(BatchLifecycleCommand::SpinUp(BatchTarget::Group(
+            TypeId::of::<G>(),
+        )));
+    }
+
+    fn spin_group_down<G: ServiceGroup>(&mut self, _group: G) {
+        debug!("spin_group_down");
+        self.send_event(BatchLifecycleCommand::SpinDown(BatchTarget::Group(
+            TypeId::of::<G>(),
+        )));
+    }
+
+    fn spin_service_instance_up<S: Service>(&mut self, key: impl Into<String>) {
+        debug!("spin_service_instance_up");
+        send_instance_command::<S>(self, key.into(), BatchLifecycleCommand::SpinUp);
+    }
+
+    fn spin_service_instance_down<S: Service>(&mut self, key: impl Into<String>) {
+        debug!("spin_service_instance_down");
+        send_instance_command::<S>(self, key.into(), BatchLifecycleCommand::SpinDown);
+    }
+
+    fn restart_service_instance<S: Service>(&mut self, key: impl Into<String>) {
+        debug!("restart_service_instance");
+        send_instance_command::<S>(self, key.into(), BatchLifecycleCommand::Restart);
+    }
+
+    fn fail_service_instance<S: Service>(&mut self, key: impl Into<String>, reason: ServiceError) {
+        debug!("fail_service_instance");
+        send_instance_command::<S>(self, key.into(), move |target| {
+            BatchLifecycleCommand::Fail(target, reason)
+        });
+    }
+
+    fn set_service_context<S: Service, C: Send + Sync + 'static>(&mut self, context: C) {
+        self.queue(move |world: &mut World| world.set_service_context::<S, C>(context));
+    }
+}
+
+/// Queues a [BatchLifecycleCommand] targeting a single instance of the service.
+fn send_instance_command<S: Service>(
+    commands: &mut Commands,
+    key: String,
+    command: impl FnOnce(BatchTarget) -> BatchLifecycleCommand + Send + 'static,
+) {
+    commands.queue(move |world: &mut World| {
+        let id = NodeId::service_instance(world.register_resource::<S>(), &key);
+        world.send_event(command(BatchTarget::Services(vec![id])));
+    });
 }
 
-/// Executes any queued up service lifecycle commands.
+/// Executes the highest priority of the service's queued lifecycle commands.
+/// The rest stay queued and are executed on later updates, one per update.
 #[tracing::instrument(skip_all)]
 pub(crate) fn watch_service_commands<S: Service>(
-    mut reader: EventReader<LifecycleCommand<S>>,
+    mut events: ResMut<Events<LifecycleCommand<S>>>,
     mut commands: Commands,
-    service: ServiceRef<S>,
+    mut service: ServiceMut<S>,
 ) {
+    // Commands are drained so that each is handled exactly once, even though
+    // this system runs in both PostStartup and PreUpdate.
+    // Only repeats are collapsed, so e.g. up, down, up still ends up.
+    for event in events.drain() {
+        let command = event.kind();
+        if service.commands.last() != Some(&command) {
+            service.commands.push(command);
+        }
+    }
     let status = service.status();
-    if let Some(event) = reader.read().min_by(|a, b| {
-        let order = a.priority(status.clone()).cmp(&b.priority(status.clone()));
-        debug!("{a:?}.cmp({b:?}) = {order:?}");
-        order
-    }) {
-        debug!("({}) Got event {:?}", S::name(), event);
-        match event {
-            LifecycleCommand::SpinUp => commands.queue(|world: &mut World| {
+    // ties go to the command which was queued first
+    let next = service
+        .commands
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, command)| command.priority(&status))
+        .map(|(index, _)| index);
+    if let Some(index) = next {
+        let command = service.commands.remove(index);
+        debug!("({}) Got command {:?}", service.name(), command);
+        match command {
+            LifecycleCommandKind::SpinUp => commands.queue(|world: &mut World| {
                 world.service_scope::<S, ()>(|world, service| service.spin_up(world));
             }),
-            LifecycleCommand::SpinDown => commands.queue(|world: &mut World| {
-                world.service_scope::<S, ()>(|world, service| service.spin_down(world));
+            LifecycleCommandKind::SpinUpIfReady => commands.queue(|world: &mut World| {
+                world.service_scope::<S, ()>(|world, service| service.spin_up_if_ready(world));
             }),
-            LifecycleCommand::Restart => commands.queue(|world: &mut World| {
-                world.service_scope::<S, ()>(|world, service| service.restart(world));
+            LifecycleCommandKind::SpinDown => commands.queue(|world: &mut World| {
+                world.service_scope::<S, ()>(|world, service| service.spin_down(world));
             }),
-            LifecycleCommand::Fail(error) => {
-                let error = error.clone();
+            LifecycleCommandKind::SpinDownWithReason(reason) => {
                 commands.queue(move |world: &mut World| {
                     world.service_scope::<S, ()>(|world, service| {
-                        service.fail(world, error.clone())
+                        service.spin_down_with_reason(world, reason.clone())
                     });
                 })
             }
-            _ => unreachable!(),
+            LifecycleCommandKind::ForceDown => commands.queue(|world: &mut World| {
+                world.service_scope::<S, ()>(|world, service| service.force_down(world));
+            }),
+            LifecycleCommandKind::Restart => commands.queue(|world: &mut World| {
+                world.service_scope::<S, ()>(|world, service| service.restart(world));
+            }),
+            LifecycleCommandKind::Prewarm => commands.queue(|world: &mut World| {
+                world.service_scope::<S, ()>(|world, service| service.prewarm(world));
+            }),
+            LifecycleCommandKind::Fail(error) => commands.queue(move |world: &mut World| {
+                world.service_scope::<S, ()>(|world, service| service.fail(world, error.clone()));
+            }),
+        }
+    }
+}
+
+/// Executes any queued up batch lifecycle commands. Runs before any individual
+/// service's lifecycle systems.
+#[tracing::instrument(skip_all)]
+pub(crate) fn watch_batch_commands(world: &mut World) {
+    // Apply commands queued directly on the world so they're handled this frame.
+    world.flush();
+    let events = world
+        .resource_mut::<Events<BatchLifecycleCommand>>()
+        .drain()
+        .collect::<Vec<_>>();
+    for event in events {
+        debug!("Got event {event:?}");
+        match event {
+            BatchLifecycleCommand::SpinUp(target) => {
+                // The cached topsort places dependents before their
+                // dependencies, so walk it backwards.
+                for id in target.resolve(world).into_iter().rev() {
+                    let Some(service) = world.service_by_id(id) else {
+                        continue;
+                    };
+                    let status = service.status();
+                    if !service.registered() || status.is_up() || status.is_initializing() {
+                        continue;
+                    }
+                    world.service_scope_by_id(id, |world, service| service.spin_up(world));
+                }
+            }
+            BatchLifecycleCommand::SpinDown(target) => {
+                // The cached topsort places dependents before their dependencies.
+                for id in target.resolve(world) {
+                    let Some(service) = world.service_by_id(id) else {
+                        continue;
+                    };
+                    let status = service.status();
+                    if !service.registered() || status.is_down() || status.is_deinitializing() {
+                        continue;
+                    }
+                    world.service_scope_by_id(id, |world, service| service.spin_down(world));
+                }
+            }
+            BatchLifecycleCommand::Restart(target) => {
+                // restarting brings dependencies up first, as with SpinUp
+                for id in target.resolve(world).into_iter().rev() {
+                    if world.service_by_id(id).is_some_and(ServiceData::registered) {
+                        world.service_scope_by_id(id, |world, service| service.restart(world));
+                    }
+                }
+            }
+            BatchLifecycleCommand::Fail(target, error) => {
+                for id in target.resolve(world) {
+                    let Some(service) = world.service_by_id(id) else {
+                        continue;
+                    };
+                    if !service.registered() || service.status().is_failed() {
+                        continue;
+                    }
+                    world.service_scope_by_id(id, |world, service| {
+                        service.fail(world, error.clone())
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Spins every registered service down, dependents first, then blocks until
+/// all of their asynchronous deinitialization has finished.
+pub(crate) fn shutdown_services(world: &mut World) {
+    debug!("Shutting down all services");
+    let order = BatchTarget::All.resolve(world);
+    let services = order
+        .into_iter()
+        .filter(|id| world.service_by_id(*id).is_some_and(|s| s.registered()))
+        .collect::<Vec<_>>();
+    for id in services.iter().copied() {
+        let status = world.service_by_id(id).unwrap().status();
+        if status.is_down() || status.is_deinitializing() {
+            continue;
+        }
+        world.service_scope_by_id(id, |world, service| service.spin_down(world));
+    }
+    settle_deinit(world, &services);
+}
+
+/// Drives the given deinitializing services down, blocking on their tasks.
+/// Services wait on their deps, and on_down hooks may spawn new tasks, so this
+/// keeps going until nothing changes and no tasks are left.
+pub(crate) fn settle_deinit(world: &mut World, services: &[NodeId]) {
+    loop {
+        let mut changed = false;
+        for id in services.iter().copied() {
+            if !world
+                .service_by_id(id)
+                .unwrap()
+                .status()
+                .is_deinitializing()
+            {
+                continue;
+            }
+            changed |= world.service_scope_by_id(id, |world, service| {
+                let status = service.status();
+                service.finish_tasks(world);
+                service.update_async_state(world);
+                service.status() != status || !service.tasks.is_empty()
+            });
+        }
+        if !changed {
+            break;
         }
     }
 }
+
+/// Runs [shutdown_services] when the app exits. See
+/// [ServiceAppExt::run_service_shutdown_on_exit].
+pub(crate) fn shutdown_on_exit(world: &mut World, mut cursor: Local<EventCursor<AppExit>>) {
+    if cursor.read(world.resource::<Events<AppExit>>()).count() > 0 {
+        shutdown_services(world);
+    }
+}
@@ -70,14 +70,98 @@ hooks!(
     (
         Down,
         in = In<DownReason>,
-        out = (),
-        "Runs when the [Service] changes state to Down. Must be synchronous."
+        out = DownResult,
+        "Runs when the [Service] is about to change state to Down. May return an [AsyncHook], which the service will wait on before going down."
+    ),
+    (
+        Transition,
+        in = In<(ServiceStatus, ServiceStatus)>,
+        out = bool,
+        "Guards the [Service]'s transitions. Receives the current and next status, and returns whether the transition may happen."
     ),
 );
 
 /// The result returned from the Init hook.
 pub type InitResult = Result<Option<AsyncHook>, BevyError>;
+/// The result returned from an Init hook added with
+/// [ServiceScope::init_with_context]: the context to keep until deinit, and an
+/// optional task.
+pub type InitContextResult<C> = Result<(C, Option<AsyncHook>), BevyError>;
 /// The result returned from the Deinit hook.
 pub type DeinitResult = Result<Option<AsyncHook>, BevyError>;
 /// The result retunred from the Up hook.
 pub type UpResult = Result<(), BevyError>;
+/// The result returned from the Down hook.
+pub type DownResult = Option<AsyncHook>;
+
+/// Output types accepted from on_down hooks: `()` for synchronous hooks, or a
+/// [DownResult] for hooks which may need to wait on an [AsyncHook].
+pub trait IntoDownResult: Send + Sync + 'static {
+    #[allow(missing_docs)]
+    fn into_down_result(self) -> DownResult;
+}
+impl IntoDownResult for () {
+    fn into_down_result(self) -> DownResult {
+        None
+    }
+}
+impl IntoDownResult for DownResult {
+    fn into_down_result(self) -> DownResult {
+        self
+    }
+}
+
+/// Builds an init hook which reads a clone of the resource `R` and passes it to
+/// `system`. Fails the service if `R` doesn't exist when it initializes. Pass
+/// the result to [ServiceScope::init_with].
+/// ```rust
+/// # use q_service::prelude::*;
+/// # use bevy_ecs::prelude::*;
+/// # #[derive(Resource, Debug, Default)]
+/// # struct MyService;
+/// #[derive(Resource, Clone)]
+/// struct Config {
+///     retries: u32,
+/// }
+///
+/// fn my_init(config: In<Config>) -> InitResult {
+///     // connect, retrying up to config.retries times
+///     Ok(None)
+/// }
+/// # impl Service for MyService {
+/// # fn build(scope: &mut ServiceScope<Self>) {
+/// scope
+///     .init_with(init_from_resource(my_init))
+///     .deinit_with(deinit_remove_resource::<Config>());
+/// # }
+/// # }
+/// ```
+pub fn init_from_resource<R: Resource + Clone, M>(
+    system: impl IntoSystem<In<R>, InitResult, M>,
+) -> impl System<In = (), Out = InitResult> {
+    let mut system = IntoSystem::into_system(system);
+    let mut initialized = false;
+    IntoSystem::into_system(move |world: &mut World| -> InitResult {
+        let Some(config) = world.get_resource::<R>().cloned() else {
+            return Err(ServiceError::own(format!(
+                "resource {} does not exist",
+                name_from_type::<R>()
+            ))
+            .into());
+        };
+        if !std::mem::replace(&mut initialized, true) {
+            system.initialize(world);
+        }
+        system.run(config, world)
+    })
+}
+
+/// Builds a deinit hook which removes the resource `R`, e.g. state derived from
+/// the service's config during init. Pass the result to
+/// [ServiceScope::deinit_with]. See [init_from_resource].
+pub fn deinit_remove_resource<R: Resource>() -> impl System<In = (), Out = DeinitResult> {
+    IntoSystem::into_system(|world: &mut World| -> DeinitResult {
+        world.remove_resource::<R>();
+        Ok(None)
+    })
+}
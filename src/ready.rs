@@ -0,0 +1,108 @@
+use core::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+};
+use std::sync::{Arc, Mutex};
+
+use bevy_ecs::prelude::*;
+use bevy_platform::collections::HashMap;
+
+use crate::prelude::*;
+
+/// State shared between a [ServiceReadyNotifier] and its [ServiceReady].
+#[derive(Default)]
+struct ReadyState {
+    /// Set once the notifier has resolved. True if the service came up.
+    ready: Option<bool>,
+    waker: Option<Waker>,
+}
+
+/// The sending half of a one-shot readiness channel. Resolves its
+/// [ServiceReady] when notified or cancelled, or with `false` when dropped
+/// unnotified, e.g. when the world is dropped before the service comes up.
+pub struct ServiceReadyNotifier(Arc<Mutex<ReadyState>>);
+impl ServiceReadyNotifier {
+    /// Creates a connected notifier and future.
+    pub fn channel() -> (Self, ServiceReady) {
+        let state = Arc::new(Mutex::new(ReadyState::default()));
+        (Self(state.clone()), ServiceReady(state))
+    }
+
+    /// Resolves the future with `true`.
+    pub fn notify(self) {
+        self.resolve(true);
+    }
+
+    /// Resolves the future with `false`.
+    pub fn cancel(self) {
+        self.resolve(false);
+    }
+
+    fn resolve(&self, ready: bool) {
+        let mut state = self.0.lock().unwrap();
+        if state.ready.is_none() {
+            state.ready = Some(ready);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        }
+    }
+}
+impl Drop for ServiceReadyNotifier {
+    fn drop(&mut self) {
+        self.resolve(false);
+    }
+}
+
+/// A [Future] which resolves once a service comes up. Outputs `true` if the
+/// service came up, or `false` if it went down first, e.g. because it failed
+/// or was forced down, or if the world was dropped. Does not need a Bevy
+/// executor, so it can be awaited from any async code. See
+/// [ServiceWorldExt::service_ready].
+pub struct ServiceReady(Arc<Mutex<ReadyState>>);
+impl ServiceReady {
+    /// Has the future resolved?
+    pub fn is_resolved(&self) -> bool {
+        self.0.lock().unwrap().ready.is_some()
+    }
+}
+impl Future for ServiceReady {
+    type Output = bool;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0.lock().unwrap();
+        match state.ready {
+            Some(ready) => Poll::Ready(ready),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// The notifiers waiting on each service. Resolved and cleared when the
+/// service comes up or goes down.
+#[derive(Resource, Default)]
+pub(crate) struct ServiceReadyNotifiers(pub(crate) HashMap<NodeId, Vec<ServiceReadyNotifier>>);
+
+/// Notifies the waiters of every service which came up this frame, and
+/// cancels those of every service which went down instead.
+pub(crate) fn notify_ready(
+    mut events: EventReader<ServiceUpdated>,
+    mut notifiers: ResMut<ServiceReadyNotifiers>,
+) {
+    for event in events.read() {
+        let resolve = if event.new_status.is_up() {
+            ServiceReadyNotifier::notify
+        } else if event.new_status.is_down() {
+            ServiceReadyNotifier::cancel
+        } else {
+            continue;
+        };
+        if let Some(waiting) = notifiers.0.remove(&event.id) {
+            waiting.into_iter().for_each(resolve);
+        }
+    }
+}
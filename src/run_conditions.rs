@@ -1,5 +1,6 @@
 use crate::prelude::*;
 use bevy_ecs::prelude::*;
+use core::time::Duration;
 
 /// Run condition which checks if the given service has the given state.
 pub fn service_has_status<T>(target_status: ServiceStatus) -> impl Condition<()>
@@ -9,6 +10,48 @@ where
     IntoSystem::into_system(move |service: ServiceRef<T>| service.status() == target_status)
 }
 
+/// Run condition which checks if the service with the given id has the given
+/// state, for when the service's type isn't known at compile time. False if
+/// there is no such service. See [ServiceById].
+pub fn service_has_status_id(id: NodeId, target_status: ServiceStatus) -> impl Condition<()> {
+    IntoSystem::into_system(move |cache: Option<Res<GraphDataCache>>| {
+        cache.is_some_and(|cache| {
+            cache
+                .get_service(id)
+                .is_some_and(|service| service.status() == target_status)
+        })
+    })
+}
+
+/// Run condition. Is the service down for the given [DownReason]?
+/// [DownReason::Failed] matches any [ServiceError]; to match a particular
+/// error, see [service_failed_with_error].
+pub fn service_down_because<T>(reason: DownReason) -> impl Condition<()>
+where
+    T: Service,
+{
+    IntoSystem::into_system(move |service: ServiceRef<T>| match service.status() {
+        ServiceStatus::Down(DownReason::Failed(_)) => matches!(reason, DownReason::Failed(_)),
+        ServiceStatus::Down(ref r) => r == &reason,
+        _ => false,
+    })
+}
+
+/// Run condition. Has the service never been started? True only while the
+/// service is [DownReason::Uninitialized]; once it has been spun up, it will
+/// never be uninitialized again. Useful for one-time bootstrap systems.
+pub fn service_never_started<T>() -> impl Condition<()>
+where
+    T: Service,
+{
+    IntoSystem::into_system(move |service: ServiceRef<T>| {
+        matches!(
+            service.status(),
+            ServiceStatus::Down(DownReason::Uninitialized)
+        )
+    })
+}
+
 macro_rules! run_conditions {
     ($(( $state:ident, $doc:tt )),*) => {
         $crate::paste::paste! {
@@ -32,6 +75,7 @@ macro_rules! run_conditions {
 run_conditions!(
     (Up, "Run condition. Is the service up?"),
     (Down, "Run condition. Is the service down?"),
+    (Standby, "Run condition. Is the service on standby?"),
     (
         Initializing,
         "Run condition. Is the service initializing? Note: If the service
@@ -43,9 +87,93 @@ run_conditions!(
         "Run condition. Is the service deinitializing? Note: If the service
         deinitializes synchronously, or if deinit takes less than a frame, then
         this will never fire."
+    ),
+    (
+        Transitioning,
+        "Run condition. Is the service initializing or deinitializing? See
+        [ServiceData::pending_tasks] to tell whether it is waiting on async
+        work."
     )
 );
 
+/// Run condition. Is every service with the given id up? Reads the
+/// [GraphDataCache] once, rather than once per service. True for an empty
+/// list. See [all_services_up](crate::all_services_up) to name the services by
+/// type.
+pub fn services_up(ids: impl Into<Vec<NodeId>>) -> impl Condition<()> {
+    let ids = ids.into();
+    IntoSystem::into_system(move |cache: Option<Res<GraphDataCache>>| {
+        cache.is_some_and(|cache| {
+            ids.iter().all(|id| {
+                cache
+                    .get_service(*id)
+                    .is_some_and(|service| service.status().is_up())
+            })
+        })
+    })
+}
+
+/// Run condition. Are all of the given services up? Reads the
+/// [GraphDataCache] once, rather than once per service as chaining
+/// [service_up] would.
+///
+/// ## Example usage
+/// ```rust
+/// # use q_service::prelude::*;
+/// # use bevy::prelude::*;
+/// # #[derive(Resource, Debug, Default)]
+/// # struct Database;
+/// # impl Service for Database {
+/// #     fn build(_: &mut ServiceScope<Self>) {}
+/// # }
+/// # #[derive(Resource, Debug, Default)]
+/// # struct Network;
+/// # impl Service for Network {
+/// #     fn build(_: &mut ServiceScope<Self>) {}
+/// # }
+/// fn sync() {}
+///
+/// let mut app = App::new();
+/// app.add_systems(Update, sync.run_if(all_services_up!(Database, Network)));
+/// ```
+#[macro_export]
+macro_rules! all_services_up {
+    ($($service:ty),+ $(,)?) => {
+        $crate::services_up_with!(all, $($service),+)
+    };
+}
+
+/// Run condition. Is at least one of the given services up? Reads the
+/// [GraphDataCache] once. See [all_services_up](crate::all_services_up).
+#[macro_export]
+macro_rules! any_service_up {
+    ($($service:ty),+ $(,)?) => {
+        $crate::services_up_with!(any, $($service),+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! services_up_with {
+    ($fold:ident, $($service:ty),+) => {
+        $crate::bevy_ecs::system::IntoSystem::into_system(
+            |cache: Option<$crate::bevy_ecs::system::Res<$crate::prelude::GraphDataCache>>,
+             components: &$crate::bevy_ecs::component::Components| {
+                let Some(cache) = cache else {
+                    return false;
+                };
+                let up = |id: Option<$crate::bevy_ecs::component::ComponentId>| {
+                    id.and_then(|id| cache.get_service($crate::prelude::NodeId::Service(id)))
+                        .is_some_and(|service| service.status().is_up())
+                };
+                [$(up(components.resource_id::<$service>())),+]
+                    .into_iter()
+                    .$fold(|up| up)
+            },
+        )
+    };
+}
+
 /// Run condition. Has the service failed? Will fire on any [ServiceError].
 pub fn service_failed<T>() -> impl Condition<()>
 where
@@ -66,3 +194,56 @@ where
         _ => false,
     })
 }
+
+/// Run condition. Has any of the service's dependencies failed, or is it
+/// failing? Unlike [service_failed], this fires as soon as the dependency
+/// fails, even if the service itself is still up.
+pub fn service_dep_failed<T>() -> impl Condition<()>
+where
+    T: Service,
+{
+    IntoSystem::into_system(move |service: ServiceRef<T>, cache: Res<GraphDataCache>| {
+        service.deps.iter().any(|dep| {
+            cache
+                .get(dep)
+                .is_some_and(|dep| dep.status().is_failed() || dep.status().is_failing())
+        })
+    })
+}
+
+/// Run condition. Did the service change status since the condition last ran?
+/// True for a single update after each transition, even if the service went
+/// through several statuses in that frame.
+pub fn service_status_changed<T>() -> impl Condition<()>
+where
+    T: Service,
+{
+    IntoSystem::into_system(move |mut reader: EventReader<ServiceStateChange<T>>| {
+        reader.read().count() > 0
+    })
+}
+
+/// Run condition. Has the service been in its current status for longer than
+/// the given duration? Useful for recovering services which are stuck
+/// initializing.
+pub fn service_in_status_longer_than<T>(duration: Duration) -> impl Condition<()>
+where
+    T: Service,
+{
+    IntoSystem::into_system(move |service: ServiceRef<T>| service.time_in_status() > duration)
+}
+
+/// Run condition. Is every member of the [ServiceGroup] up? Always false for
+/// empty groups.
+pub fn group_up<G>() -> impl Condition<()>
+where
+    G: ServiceGroup,
+{
+    IntoSystem::into_system(
+        move |groups: Option<Res<ServiceGroups>>, cache: Option<Res<GraphDataCache>>| {
+            groups
+                .zip(cache)
+                .is_some_and(|(groups, cache)| groups.all_up::<G>(&cache))
+        },
+    )
+}
@@ -1,22 +1,72 @@
 use crate::{prelude::*, spec::ServiceSpec};
-use bevy_app::prelude::*;
-use bevy_asset::{Asset, AssetPath, DirectAssetAccessExt};
-use bevy_ecs::{prelude::*, schedule::ScheduleLabel, system::ScheduleSystem};
+use bevy_asset::{Asset, AssetPath, AssetServer, DirectAssetAccessExt, Handle, meta::Settings};
+use bevy_ecs::{prelude::*, schedule::ScheduleLabel, system::ScheduleSystem, world::CommandQueue};
+use core::time::Duration;
+use tracing::warn;
+
+/// A change to the world made while building a service. See [ScopeSetup].
+type SetupStep = Box<dyn FnOnce(&mut World)>;
+
+/// The changes to the world made while building a service. They're only
+/// applied once the service has been registered, so a service whose
+/// dependencies are rejected leaves nothing behind.
+#[derive(Default)]
+pub(crate) struct ScopeSetup {
+    /// Scoped systems and observers.
+    systems: Vec<SetupStep>,
+    /// Dependency data and config resources.
+    data: Vec<SetupStep>,
+}
+impl ScopeSetup {
+    /// Drops the scoped systems and observers, e.g. because they're still
+    /// around from an earlier registration.
+    pub(crate) fn without_systems(mut self) -> Self {
+        self.systems.clear();
+        self
+    }
+    pub(crate) fn apply(self, world: &mut World) {
+        for step in self.data.into_iter().chain(self.systems) {
+            step(world);
+        }
+    }
+}
 
 /// Used to scope systems, resources, and assets to a service.
 pub struct ServiceScope<'a, T: Service> {
-    app: &'a mut App,
+    world: &'a mut World,
     spec: ServiceSpec<T>,
+    setup: ScopeSetup,
+    /// See [ServiceWorldExt::hot_swap_service].
+    hot_swap: bool,
 }
 impl<'a, T: Service> ServiceScope<'a, T> {
-    pub(crate) fn new(app: &'a mut App) -> Self {
+    pub(crate) fn new(world: &'a mut World) -> Self {
         Self {
-            app,
+            world,
             spec: ServiceSpec::default(),
+            setup: ScopeSetup::default(),
+            hot_swap: false,
         }
     }
-    pub(crate) fn into_spec(self) -> ServiceSpec<T> {
-        self.spec
+    pub(crate) fn new_hot_swap(world: &'a mut World) -> Self {
+        Self {
+            hot_swap: true,
+            ..Self::new(world)
+        }
+    }
+    pub(crate) fn into_parts(self) -> (ServiceSpec<T>, ScopeSetup) {
+        (self.spec, self.setup)
+    }
+    /// Systems can't be removed from a schedule, so a hot swap mustn't add
+    /// any. Returns true, after warning, if this scope is a hot swap.
+    fn reject_in_hot_swap(&self, what: &str) -> bool {
+        if self.hot_swap {
+            warn!(
+                "Tried to add {what} while hot swapping service {}. Ignoring them.",
+                T::name()
+            );
+        }
+        self.hot_swap
     }
     /// Adds systems to this service.
     /// Will automatically scope these systems so that they run only if the service is up.
@@ -25,116 +75,409 @@ impl<'a, T: Service> ServiceScope<'a, T> {
         schedule: impl ScheduleLabel + Clone,
         systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
     ) -> &mut Self {
-        self.app
-            .add_systems(schedule.clone(), systems.in_set(T::system_set()));
-        self.app
-            .configure_sets(schedule, T::system_set().run_if(service_up::<T>()));
+        if self.reject_in_hot_swap("systems") {
+            return self;
+        }
+        let systems = systems.in_set(T::system_set());
+        self.setup.systems.push(Box::new(move |world| {
+            world
+                .resource_mut::<Schedules>()
+                .add_systems(schedule.clone(), systems)
+                .configure_sets(schedule, T::system_set().run_if(service_up::<T>()));
+        }));
+        self.spec.has_systems = true;
+        self
+    }
+
+    /// Adds systems to this service which run only while the service has the
+    /// given status, e.g. cleanup systems which should run while it's
+    /// deinitializing. Each status gets its own
+    /// [system set](Service::system_set_when), so systems added for different
+    /// statuses don't affect each other.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// fn show_loading_screen() {}
+    /// scope.add_systems_when(Update, show_loading_screen, ServiceStatus::Init);
+    /// # }
+    /// # }
+    /// ```
+    pub fn add_systems_when<M>(
+        &mut self,
+        schedule: impl ScheduleLabel + Clone,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+        status: ServiceStatus,
+    ) -> &mut Self {
+        if self.reject_in_hot_swap("systems") {
+            return self;
+        }
+        let set = T::system_set_when(status.clone());
+        let systems = systems.in_set(set.clone());
+        self.setup.systems.push(Box::new(move |world| {
+            world
+                .resource_mut::<Schedules>()
+                .add_systems(schedule.clone(), systems)
+                .configure_sets(schedule, set.run_if(service_has_status::<T>(status)));
+        }));
+        self.spec.has_systems = true;
         self
     }
 
+    /// Adds systems which run every frame while the service is initializing,
+    /// e.g. to poll an external readiness endpoint. Shorthand for
+    /// [add_systems_when](ServiceScope::add_systems_when) with
+    /// [ServiceStatus::Init]. These can be combined with systems added through
+    /// [add_systems](ServiceScope::add_systems).
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// fn poll_endpoint() {}
+    /// fn serve() {}
+    /// scope
+    ///     .add_init_systems(Update, poll_endpoint)
+    ///     .add_systems(Update, serve);
+    /// # }
+    /// # }
+    /// ```
+    pub fn add_init_systems<M>(
+        &mut self,
+        schedule: impl ScheduleLabel + Clone,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self {
+        self.add_systems_when(schedule, systems, ServiceStatus::Init)
+    }
+
     /// Adds an initialization function to the service.
     /// The init hook may return a task to be polled. If so, the service
     /// will remain in the Initializing state until the task finishes.
     ///
     /// # Example usage
     /// ```rust
-    /// # let app = App::new();
-    /// # let scope = ServiceScope::new(&mut app);
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
     /// fn my_default_init() -> InitResult {
     ///     Ok(None)
     /// }
-    /// spec.with_init(my_default_init);
+    /// scope.init_with(my_default_init);
     ///
     /// fn my_async_init() -> InitResult {
-    ///     let task = AsyncHook::async_compute_task(async |_| {
+    ///     let task = AsyncHook::async_compute(async |_| {
     ///         // do something async here
     ///         Ok(())
     ///     });
     ///     Ok(Some(task))
     /// }
-    /// spec.with_init(my_async_init);
+    /// scope.init_with(my_async_init);
+    /// # }
+    /// # }
     /// ```
     pub fn init_with<M>(&mut self, system: impl IntoInitHook<T, M>) -> &mut Self {
         self.spec.on_init = Some(InitHook::new(system));
         self
     }
 
+    /// Adds an initialization function which receives a copy of `config`.
+    /// The config is stored in a [ServiceConfig] resource, so the service's
+    /// other hooks and systems can read it too.
+    ///
+    /// # Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// #[derive(Clone)]
+    /// struct Retries(u32);
+    ///
+    /// fn my_init(retries: In<Retries>) -> InitResult {
+    ///     // connect, retrying up to retries.0 times
+    ///     Ok(None)
+    /// }
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// scope.init_with_config(Retries(3), my_init);
+    /// # }
+    /// # }
+    /// ```
+    pub fn init_with_config<C: Clone + Send + Sync + 'static, M>(
+        &mut self,
+        config: C,
+        system: impl IntoSystem<In<C>, InitResult, M>,
+    ) -> &mut Self {
+        self.setup.data.push(Box::new(|world| {
+            world.insert_resource(ServiceConfig::<T, C>::new(config));
+        }));
+        let get_config = |config: Res<ServiceConfig<T, C>>| (**config).clone();
+        self.spec.on_init = Some(InitHook::new(get_config.pipe(system)));
+        self
+    }
+
+    /// Adds an initialization function which returns a context for the
+    /// service's other hooks to share, e.g. a connection it opened. The context
+    /// is handed to the hook added with
+    /// [deinit_with_context](ServiceScope::deinit_with_context), and is dropped
+    /// once deinit runs, including when init fails.
+    ///
+    /// # Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// struct Connection(u32);
+    ///
+    /// fn my_init() -> InitContextResult<Connection> {
+    ///     // open the connection here
+    ///     Ok((Connection(0), None))
+    /// }
+    ///
+    /// fn my_deinit(connection: In<Option<Connection>>) -> DeinitResult {
+    ///     if let Some(connection) = connection.0 {
+    ///         // close the connection here
+    ///     }
+    ///     Ok(None)
+    /// }
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// scope
+    ///     .init_with_context(my_init)
+    ///     .deinit_with_context(my_deinit);
+    /// # }
+    /// # }
+    /// ```
+    pub fn init_with_context<C: Send + Sync + 'static, M>(
+        &mut self,
+        system: impl IntoSystem<(), InitContextResult<C>, M>,
+    ) -> &mut Self {
+        let id = NodeId::Service(self.world.register_resource::<T>());
+        let store_context = move |result: In<InitContextResult<C>>,
+                                  mut contexts: ResMut<ServiceContexts>|
+              -> InitResult {
+            let (context, task) = result.0?;
+            contexts.insert(id, Box::new(context));
+            Ok(task)
+        };
+        self.spec.on_init = Some(InitHook::new(system.pipe(store_context)));
+        self
+    }
+
     /// Adds a deinitialization function to the service.
     /// The deinit hook may return a task to be polled. If so, the service
     /// will remain in the Deinitializing state until the task finishes.
     ///
     /// # Example usage
     /// ```rust
-    /// # let app = App::new();
-    /// # let scope = ServiceScope::new(&mut app);
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
     /// fn my_default_deinit() -> DeinitResult {
     ///     Ok(None)
     /// }
-    /// spec.with_deinit(my_default_init);
+    /// scope.deinit_with(my_default_deinit);
     ///
     /// fn my_async_deinit() -> DeinitResult {
-    ///     let task = AsyncHook::async_compute_task(async |_| {
+    ///     let task = AsyncHook::async_compute(async |_| {
     ///         // do something async here
     ///         Ok(())
     ///     });
     ///     Ok(Some(task))
     /// }
-    /// spec.with_deinit(my_async_init);
+    /// scope.deinit_with(my_async_deinit);
+    /// # }
+    /// # }
     /// ```
     pub fn deinit_with<M>(&mut self, system: impl IntoDeinitHook<T, M>) -> &mut Self {
         self.spec.on_deinit = Some(DeinitHook::new(system));
         self
     }
 
+    /// Adds a deinitialization function which receives the context stored by
+    /// the service's other hooks, e.g. a connection opened during init. The
+    /// context is removed when deinit runs, and is `None` if it was never set
+    /// or has a different type. See [ServiceScope::init_with_context] and
+    /// [ServiceWorldExt::set_service_context].
+    ///
+    /// # Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::{ecs::world::CommandQueue, prelude::*};
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// struct Connection(u32);
+    ///
+    /// fn my_init() -> InitResult {
+    ///     let task = AsyncHook::io(async |queue: &mut CommandQueue| {
+    ///         // open the connection here
+    ///         queue.push(|world: &mut World| {
+    ///             world.set_service_context::<MyService, _>(Connection(0));
+    ///         });
+    ///         Ok(())
+    ///     });
+    ///     Ok(Some(task))
+    /// }
+    ///
+    /// fn my_deinit(connection: In<Option<Connection>>) -> DeinitResult {
+    ///     if let Some(connection) = connection.0 {
+    ///         // close the connection here
+    ///     }
+    ///     Ok(None)
+    /// }
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// scope.init_with(my_init).deinit_with_context(my_deinit);
+    /// # }
+    /// # }
+    /// ```
+    pub fn deinit_with_context<C: Send + Sync + 'static, M>(
+        &mut self,
+        system: impl IntoSystem<In<Option<C>>, DeinitResult, M>,
+    ) -> &mut Self {
+        let id = NodeId::Service(self.world.register_resource::<T>());
+        let take_context =
+            move |mut contexts: ResMut<ServiceContexts>| contexts.take_context::<C>(id);
+        self.spec.on_deinit = Some(DeinitHook::new(take_context.pipe(system)));
+        self
+    }
+
     /// Adds a hook which will run when the service is up.
     ///
     /// ## Example usage
     /// ```rust
-    /// # let app = App::new();
-    /// # let scope = ServiceScope::new(&mut app);
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
     /// fn my_up_hook() -> UpResult {
     ///     Ok(())
     /// }
-    /// spec.on_up(my_up_hook);
+    /// scope.on_up(my_up_hook);
+    /// # }
+    /// # }
     /// ```
     pub fn on_up<M>(&mut self, system: impl IntoUpHook<T, M>) -> &mut Self {
         self.spec.on_up = Some(UpHook::new(system));
         self
     }
 
-    /// Adds a hook which will run when the service is down.
+    /// Adds a hook which will run when the service is going down. The hook may
+    /// return `()`, or a [DownResult] if it needs to do some asynchronous work,
+    /// e.g. flushing buffers. The service will stay in
+    /// [ServiceStatus::Deinit] until the returned task finishes.
     ///
     /// ## Example usage
     /// ```rust
-    /// # let app = App::new();
-    /// # let scope = ServiceScope::new(&mut app);
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
     /// fn my_down_hook(reason: In<DownReason>) {
-    ///     match reason {
+    ///     match reason.0 {
     ///         DownReason::Uninitialized => todo!(),
     ///         DownReason::Failed(service_error_kind) => todo!(),
     ///         DownReason::SpunDown => todo!(),
+    ///         DownReason::Custom(reason) => todo!(),
     ///     }
     /// }
-    /// spec.on_up(my_down_hook);
+    /// scope.on_down(my_down_hook);
+    /// # }
+    /// # }
     /// ```
-    pub fn on_down<M>(&mut self, system: impl IntoDownHook<T, M>) -> &mut Self {
-        self.spec.on_down = Some(DownHook::new(system));
+    pub fn on_down<O: IntoDownResult, M>(
+        &mut self,
+        system: impl IntoSystem<In<DownReason>, O, M>,
+    ) -> &mut Self {
+        self.spec.on_down = Some(DownHook::new(system.map(O::into_down_result)));
+        self
+    }
+
+    /// Adds a guard which can veto the service's transitions. The guard is
+    /// consulted whenever the service is about to start spinning up or down,
+    /// and receives the current and next status. If it returns false, the
+    /// transition is logged and skipped. Failures bypass the guard, so a
+    /// failing service always goes down.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// #[derive(Resource)]
+    /// struct Locked(bool);
+    /// fn my_guard(In((_from, to)): In<(ServiceStatus, ServiceStatus)>, locked: Res<Locked>) -> bool {
+    ///     // don't spin down while locked
+    ///     !(to.is_deinitializing() && locked.0)
+    /// }
+    /// scope.on_transition(my_guard);
+    /// # }
+    /// # }
+    /// ```
+    pub fn on_transition<M>(&mut self, system: impl IntoTransitionHook<T, M>) -> &mut Self {
+        self.spec.on_transition = Some(TransitionHook::new(system));
+        self
+    }
+
+    /// Guards the service's spin-down, e.g. while it's in the middle of a
+    /// transaction. While the condition returns false, spinning the service
+    /// down is deferred to a later frame rather than dropped, and it goes down
+    /// once the condition passes. Failures and [ServiceCommandsExt::force_service_down]
+    /// ignore the guard.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// #[derive(Resource)]
+    /// struct Transaction(bool);
+    /// scope.can_spin_down(|transaction: Res<Transaction>| !transaction.0);
+    /// # }
+    /// # }
+    /// ```
+    pub fn can_spin_down<M>(&mut self, condition: impl Condition<M>) -> &mut Self {
+        self.spec.can_spin_down = Some(Box::new(IntoSystem::into_system(condition)));
         self
     }
 
     /// Adds the given service as a dependency.
-    /// Make sure this dependency is also registered, or you'll run into errors!
+    /// Make sure this dependency is also registered, or this service will fail
+    /// to spin up with a [DepInitErr::NotFound] error! The dependency's
+    /// lifecycle systems, which drive its async hooks, are only added by
+    /// registering it, so it is never spun up before then.
     pub fn add_dep<S: Service>(&mut self) -> &mut Self {
-        self.app.init_resource::<S>();
-        let cid = self
-            .app
-            .world()
-            .resource_id::<S>()
-            .expect("Resource id should exist");
+        let cid = self.world.register_resource::<S>();
         let id = NodeId::Service(cid);
+        // unregistered placeholder, which names the dep in cycle errors
         let data = ServiceData::new::<S>(cid);
-        self.app
-            .world_mut()
+        self.world
             .resource_mut::<GraphDataCache>()
             .entry(id)
             .or_insert(GraphData::Service(data));
@@ -142,6 +485,132 @@ impl<'a, T: Service> ServiceScope<'a, T> {
         self
     }
 
+    /// Runs the given system whenever this service enters the given status.
+    /// The system runs from an observer on [EnterServiceState], so it runs
+    /// once per transition regardless of the schedule. The status must match
+    /// exactly, e.g. `Down(SpunDown)` won't match a service which failed.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// fn play_ready_sound() {}
+    /// scope.on_enter(ServiceStatus::Up, play_ready_sound);
+    /// # }
+    /// # }
+    /// ```
+    pub fn on_enter<M>(
+        &mut self,
+        status: ServiceStatus,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self {
+        if self.reject_in_hot_swap("observers") {
+            return self;
+        }
+        let system = IntoSystem::into_system(system);
+        self.setup.systems.push(Box::new(move |world| {
+            let id = world.register_system(system);
+            world.add_observer(
+                move |trigger: Trigger<EnterServiceState<T>>, mut commands: Commands| {
+                    if **trigger.event() == status {
+                        commands.run_system(id);
+                    }
+                },
+            );
+        }));
+        self
+    }
+
+    /// Runs the given system whenever this service leaves the given status.
+    /// See [ServiceScope::on_enter].
+    pub fn on_exit<M>(
+        &mut self,
+        status: ServiceStatus,
+        system: impl IntoSystem<(), (), M> + 'static,
+    ) -> &mut Self {
+        if self.reject_in_hot_swap("observers") {
+            return self;
+        }
+        let system = IntoSystem::into_system(system);
+        self.setup.systems.push(Box::new(move |world| {
+            let id = world.register_system(system);
+            world.add_observer(
+                move |trigger: Trigger<ExitServiceState<T>>, mut commands: Commands| {
+                    if **trigger.event() == status {
+                        commands.run_system(id);
+                    }
+                },
+            );
+        }));
+        self
+    }
+
+    /// Restarts this service whenever the given dependency comes back up after
+    /// having been up before, e.g. so that state cached from a connection is
+    /// rebuilt once it reconnects. The dependency must also be added with
+    /// [ServiceScope::add_dep]. The service is only restarted while it's up,
+    /// and at most once per frame.
+    pub fn restart_on_dep_change<D: Service>(&mut self) -> &mut Self {
+        if self.reject_in_hot_swap("observers") {
+            return self;
+        }
+        self.setup.systems.push(Box::new(|world| {
+            world.add_observer(
+                |trigger: Trigger<ServiceStateChange<D>>,
+                 service: ServiceRef<T>,
+                 mut was_up: Local<bool>,
+                 mut commands: Commands| {
+                    let (old, new) = &trigger.event().0;
+                    if !new.is_up() || old.is_up() {
+                        return;
+                    }
+                    if std::mem::replace(&mut *was_up, true) && service.status().is_up() {
+                        // back-to-back restarts are collapsed into one queued command
+                        commands.restart_service::<T>();
+                    }
+                },
+            );
+        }));
+        self
+    }
+
+    /// Adds the given service as a dependency, as with [ServiceScope::add_dep],
+    /// but only if the condition holds when this service first spins up.
+    /// Otherwise, the dependency is ignored for the rest of the app's run.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct Window;
+    /// # impl Service for Window {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct Renderer;
+    /// #[derive(Resource)]
+    /// struct Headless(bool);
+    ///
+    /// # impl Service for Renderer {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// scope.add_dep_if::<Window, _>(|headless: Res<Headless>| !headless.0);
+    /// # }
+    /// # }
+    /// ```
+    pub fn add_dep_if<S: Service, M>(&mut self, condition: impl Condition<M>) -> &mut Self {
+        self.add_dep::<S>();
+        let id = *self.spec.deps.last().unwrap();
+        self.spec
+            .dep_conditions
+            .push((id, Box::new(IntoSystem::into_system(condition))));
+        self
+    }
+
     /// Adds a resource to this service, initializing with its Default value.
     /// The resource will be instantiated when the service is spun up, and
     /// removed when the service is spun down.
@@ -152,43 +621,206 @@ impl<'a, T: Service> ServiceScope<'a, T> {
 
     /// Adds a resource to this service with a custom default value.
     /// The resource will be instantiated when the service is spun up, and
-    /// removed when the service is spun down.
+    /// removed when the service is spun down. If several services share the
+    /// resource, it is only removed once all of them are down.
     pub fn add_resource_with<R: Resource, M>(
         &mut self,
         default: impl IntoSystem<(), R, M> + 'static,
     ) -> &mut Self {
-        let world = self.app.world_mut();
         let init_sys = default.pipe(|input: In<R>, mut commands: Commands| {
             commands.insert_resource(input.0);
         });
-        let init = world.register_system(init_sys).entity();
-        let deinit = world
-            .register_system(|mut commands: Commands| {
-                commands.remove_resource::<R>();
-            })
-            .entity();
+        let init_sys = IntoSystem::into_system(init_sys);
+        self.insert_resource::<R>(move |world| {
+            let init = world.register_system(init_sys).entity();
+            let deinit = world
+                .register_system(|mut commands: Commands| {
+                    commands.remove_resource::<R>();
+                })
+                .entity();
+            GraphData::resource::<R>(world, init, deinit)
+        })
+    }
+
+    /// Adds a resource to this service which is created by an async function.
+    /// The function runs on the [AsyncComputeTaskPool](bevy_tasks::AsyncComputeTaskPool)
+    /// whenever the service spins up, and the service stays initializing until
+    /// the resource is ready. If the function fails, so does the service. The
+    /// resource is removed when the service is spun down.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyService;
+    /// #[derive(Resource)]
+    /// struct Config(String);
+    /// # impl Service for MyService {
+    /// # fn build(scope: &mut ServiceScope<Self>) {
+    /// scope.add_resource_async_with(async || {
+    ///     // e.g. read the config from disk
+    ///     Ok(Config("...".into()))
+    /// });
+    /// # }
+    /// # }
+    /// ```
+    pub fn add_resource_async_with<R: Resource>(
+        &mut self,
+        init: impl AsyncFn() -> Result<R, BevyError> + Clone + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.insert_resource::<R>(move |world| {
+            let init = world
+                .register_system(move || {
+                    let init = init.clone();
+                    AsyncHook::async_compute(async move |queue: &mut CommandQueue| {
+                        let resource = init().await?;
+                        queue.push(move |world: &mut World| world.insert_resource(resource));
+                        Ok(())
+                    })
+                })
+                .entity();
+            let deinit = world
+                .register_system(|mut commands: Commands| {
+                    commands.remove_resource::<R>();
+                })
+                .entity();
+            GraphData::resource_async::<R>(world, init, deinit)
+        })
+    }
+
+    /// Services which add the same resource share its data, so the first
+    /// one's systems, status and owners are kept. `data` only runs for the
+    /// first.
+    fn insert_resource<R: Resource>(
+        &mut self,
+        data: impl FnOnce(&mut World) -> GraphData + 'static,
+    ) -> &mut Self {
         // registers resource without inserting it into the world
-        let id = world.register_resource::<R>();
-        let data = GraphData::resource::<R>(world, init, deinit);
-        world
-            .resource_mut::<GraphDataCache>()
-            .insert(NodeId::Resource(id), data);
-        self.spec.deps.push(NodeId::Resource(id));
+        let id = NodeId::Resource(self.world.register_resource::<R>());
+        self.setup.data.push(Box::new(move |world| {
+            if world
+                .resource::<GraphDataCache>()
+                .get_resource(id)
+                .is_none()
+            {
+                let data = data(world);
+                world.resource_mut::<GraphDataCache>().insert(id, data);
+            }
+        }));
+        self.spec.deps.push(id);
         self
     }
 
     /// Adds an asset to the service. The asset will be load a strong handle
     /// into an entity which will stay alive as long as the service is up. So,
     /// the asset added here will live _at least_ as long as the service.
+    /// Services which add the same asset share a single strong handle, which
+    /// is dropped once all of them are down.
     pub fn add_asset<A: Asset>(&mut self, path: impl Into<AssetPath<'a>>) -> &mut Self {
-        let world = self.app.world_mut();
-        let handle = world.load_asset::<A>(path.into());
-        let id = handle.id().untyped();
-        let data = GraphData::asset::<A, T>(handle, world);
-        world
-            .resource_mut::<GraphDataCache>()
-            .insert(NodeId::Asset(id), data);
-        self.spec.deps.push(NodeId::Asset(id));
+        let handle = self.world.load_asset::<A>(path);
+        self.insert_asset(handle, false)
+    }
+
+    /// Adds an asset to the service, as with [ServiceScope::add_asset], but
+    /// loads it with the given loader settings, e.g. an image's sampler.
+    /// See [AssetServer::load_with_settings](bevy_asset::AssetServer::load_with_settings).
+    pub fn add_asset_with_settings<A: Asset, S: Settings>(
+        &mut self,
+        path: impl Into<AssetPath<'a>>,
+        settings: impl Fn(&mut S) + Send + Sync + 'static,
+    ) -> &mut Self {
+        let handle = self.world.load_asset_with_settings::<A, S>(path, settings);
+        self.insert_asset(handle, false)
+    }
+
+    /// Adds every asset in the given folder to the service, as with
+    /// [ServiceScope::add_asset]. The service will initialize until the whole
+    /// folder has loaded, and fail if any of its assets fail to load. Files
+    /// without a registered loader are skipped. See
+    /// [AssetServer::load_folder].
+    pub fn add_asset_folder(&mut self, path: impl Into<AssetPath<'a>>) -> &mut Self {
+        let handle = self.world.resource::<AssetServer>().load_folder(path);
+        self.insert_asset(handle, false)
+    }
+
+    /// Adds an asset to the service, as with [ServiceScope::add_asset], but its
+    /// load state never keeps the service from coming up, and a failed load
+    /// won't fail the service. If another service adds the same asset with
+    /// [ServiceScope::add_asset], it is no longer weak.
+    pub fn add_weak_asset<A: Asset>(&mut self, path: impl Into<AssetPath<'a>>) -> &mut Self {
+        let handle = self.world.load_asset::<A>(path);
+        self.insert_asset(handle, true)
+    }
+
+    fn insert_asset<A: Asset>(&mut self, handle: Handle<A>, weak: bool) -> &mut Self {
+        let id = NodeId::Asset(handle.id().untyped());
+        self.setup.data.push(Box::new(move |world| {
+            if let Some(asset) = world.resource_mut::<GraphDataCache>().get_asset_mut(id) {
+                asset.weak &= weak;
+            } else {
+                let mut data = GraphData::asset::<A, T>(handle, world);
+                if let GraphData::Asset(asset) = &mut data {
+                    asset.weak = weak;
+                }
+                world.resource_mut::<GraphDataCache>().insert(id, data);
+            }
+        }));
+        self.spec.deps.push(id);
+        self
+    }
+
+    /// Attaches a tag to the service, e.g. for tooling which groups services.
+    /// See [ServiceWorldExt::services_with_tag].
+    pub fn with_tag(&mut self, tag: impl Into<String>) -> &mut Self {
+        self.spec.tags.insert(tag.into());
+        self
+    }
+
+    /// Overrides the service's display name, which defaults to [Service::name].
+    /// Useful when services in different modules share a type name.
+    pub fn with_name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.spec.name = Some(name.into());
+        self
+    }
+
+    /// Logs a warning once the service has spent the given number of frames
+    /// initializing or deinitializing, naming the dependency it's waiting on.
+    /// Defaults to [DEFAULT_STUCK_WARNING_FRAMES].
+    pub fn stuck_warning_after(&mut self, frames: u32) -> &mut Self {
+        self.spec.stuck_warning_after = Some(frames);
+        self
+    }
+
+    /// Polls the service's async tasks at most once per `interval`, instead of
+    /// every frame. Trades latency for CPU time, which suits long IO tasks:
+    /// a finished task is noticed up to `interval` late.
+    pub fn task_poll_interval(&mut self, interval: Duration) -> &mut Self {
+        self.spec.task_poll_interval = Some(interval);
+        self
+    }
+
+    /// When the service fails, spin down its dependencies instead of failing
+    /// them along with it. Dependencies which another live service depends on
+    /// are left as they are. Defaults to false.
+    pub fn stop_deps_on_failure(&mut self, val: bool) -> &mut Self {
+        self.spec.stop_deps_on_failure = val;
+        self
+    }
+
+    /// Catches panics in the service's hooks instead of letting them bring
+    /// down the app. A panicking init, up, or deinit hook fails the service
+    /// with a "hook panicked" [ServiceError], as does a panicking on_down
+    /// hook. Panicking guards and dependency conditions are logged and
+    /// treated as if they had allowed the transition or kept the dependency.
+    ///
+    /// Defaults to false, as Bevy systems and resources make no promises
+    /// about their state after a panic: a system which panics is lost, and
+    /// won't run again. A service whose hook was lost fails each time it
+    /// tries to run it. Only enable this if your hooks leave the world
+    /// usable when they panic.
+    pub fn catch_hook_panics(&mut self, val: bool) -> &mut Self {
+        self.spec.catch_hook_panics = val;
         self
     }
 
@@ -198,4 +830,52 @@ impl<'a, T: Service> ServiceScope<'a, T> {
         self.spec.is_startup = val;
         self
     }
+
+    /// Spins the service up at startup, as with [ServiceScope::is_startup],
+    /// ordered against the other startup services with a priority. Lower
+    /// numbers spin up first. Dependencies already spin up before their
+    /// dependents, so this only orders services which don't depend on each
+    /// other, e.g. independent roots. Prioritized services spin up in
+    /// [Startup](bevy_app::Startup), before the other startup services.
+    pub fn startup_priority(&mut self, priority: i32) -> &mut Self {
+        self.spec.is_startup = true;
+        self.spec.startup_priority = Some(priority);
+        self
+    }
+}
+
+/// A restricted [ServiceScope] which can only add systems to an already
+/// registered service. See [ServiceAppExt::patch_service].
+pub struct ServicePatch<'a, T: Service>(pub(crate) ServiceScope<'a, T>);
+impl<T: Service> ServicePatch<'_, T> {
+    /// See [ServiceScope::add_systems].
+    pub fn add_systems<M>(
+        &mut self,
+        schedule: impl ScheduleLabel + Clone,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self {
+        self.0.add_systems(schedule, systems);
+        self
+    }
+
+    /// See [ServiceScope::add_systems_when].
+    pub fn add_systems_when<M>(
+        &mut self,
+        schedule: impl ScheduleLabel + Clone,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+        status: ServiceStatus,
+    ) -> &mut Self {
+        self.0.add_systems_when(schedule, systems, status);
+        self
+    }
+
+    /// See [ServiceScope::add_init_systems].
+    pub fn add_init_systems<M>(
+        &mut self,
+        schedule: impl ScheduleLabel + Clone,
+        systems: impl IntoScheduleConfigs<ScheduleSystem, M>,
+    ) -> &mut Self {
+        self.0.add_init_systems(schedule, systems);
+        self
+    }
 }
@@ -1,11 +1,24 @@
 use crate::{
-    deps::{GraphData, register_deps},
+    deps::{GraphData, RejectedDeps, register_deps},
     graph::DependencyGraph,
     prelude::*,
+    scope::ScopeSetup,
     spec::ServiceSpec,
 };
-use bevy_ecs::{component::ComponentId, prelude::*, system::SystemId};
-use bevy_platform::prelude::*;
+use bevy_derive::Deref;
+use bevy_ecs::{
+    component::{ComponentId, Tick},
+    prelude::*,
+    system::SystemId,
+    world::CommandQueue,
+};
+use bevy_platform::{collections::HashSet, prelude::*, time::Instant};
+use bevy_tasks::tick_global_task_pools_on_main_thread;
+use core::time::Duration;
+use std::{
+    collections::BTreeSet,
+    panic::{AssertUnwindSafe, catch_unwind},
+};
 use tracing::{debug, error, warn};
 
 /// The inner Service data structure.
@@ -16,17 +29,73 @@ use tracing::{debug, error, warn};
 pub struct ServiceData {
     pub name: String,
     pub id: NodeId,
+    /// See [ServiceKey].
+    key: ServiceKey,
     pub status: ServiceStatus,
     event_queue: Vec<ServiceUpdated>,
     registered: bool,
+    /// When the service entered its current status.
+    changed_at: Option<Instant>,
+    /// The world's change tick when the service entered its current status.
+    last_status_change: Tick,
+    /// Time spent [ServiceStatus::Up], not counting the current stretch.
+    uptime: Duration,
+    /// Whether the service should stop at [ServiceStatus::Standby] once
+    /// initialized.
+    prewarming: bool,
+    /// The progress reported by the service's init tasks, as [f32] bits so
+    /// that ServiceData stays [Eq] and [Hash].
+    init_progress: Option<u32>,
+    /// Tags attached with [ServiceScope::with_tag].
+    tags: BTreeSet<String>,
+    /// How many frames the service has spent in its current transition.
+    stuck_frames: u32,
+    /// See [ServiceScope::stuck_warning_after].
+    stuck_warning_after: u32,
+    /// See [ServiceScope::task_poll_interval].
+    task_poll_interval: Option<Duration>,
+    /// When the service's tasks were last polled.
+    last_task_poll: Option<Instant>,
+    /// How many times the service's tasks have been polled.
+    task_polls: u32,
     /// Service dependencies, stored in topsorted order.
     pub(crate) deps: Vec<NodeId>,
     pub(crate) tasks: Vec<Entity>,
+    /// Lifecycle commands waiting to be executed.
+    pub(crate) commands: Vec<LifecycleCommandKind>,
     // SystemIds are Entities + a marker. Can't store the marker so we just have to store the Entity.
     pub(crate) on_init: Option<Entity>,
     pub(crate) on_deinit: Option<Entity>,
     pub(crate) on_up: Option<Entity>,
     pub(crate) on_down: Option<Entity>,
+    pub(crate) on_transition: Option<Entity>,
+    /// Conditions for deps added with [ServiceScope::add_dep_if], as system
+    /// entities.
+    dep_conditions: Box<[(NodeId, Entity)]>,
+    /// Deps which aren't needed this session. Decided when the service first
+    /// spins up.
+    skipped_deps: Option<Box<[NodeId]>>,
+    /// See [ServiceScope::stop_deps_on_failure].
+    stop_deps_on_failure: bool,
+    /// See [ServiceScope::catch_hook_panics].
+    catch_hook_panics: bool,
+    /// The error from the last hook run, if it panicked or couldn't run. See
+    /// [ServiceScope::catch_hook_panics].
+    hook_error: Option<ServiceError>,
+    /// Hooks which panicked. Bevy drops a system which panics, so these can't
+    /// run again.
+    lost_hooks: Vec<Entity>,
+    /// See [ServiceScope::can_spin_down].
+    can_spin_down: Option<Entity>,
+    /// Whether a spin-down is currently deferred by the guard, so the warning
+    /// is only logged once.
+    spin_down_deferred: bool,
+    /// Deps left up for their other dependents when this service failed. See
+    /// [ServiceScope::stop_deps_on_failure].
+    retained_deps: Vec<NodeId>,
+    /// Whether the on_down hook has run during the current deinit, so that it
+    /// isn't run again once its task finishes.
+    on_down_ran: bool,
 }
 
 impl ServiceData {
@@ -38,51 +107,197 @@ impl ServiceData {
             on_deinit: Default::default(),
             on_up: Default::default(),
             on_down: Default::default(),
+            on_transition: Default::default(),
+            dep_conditions: Box::default(),
+            skipped_deps: None,
+            stop_deps_on_failure: false,
+            catch_hook_panics: false,
+            hook_error: None,
+            lost_hooks: vec![],
+            can_spin_down: None,
+            spin_down_deferred: false,
+            retained_deps: Vec::new(),
+            on_down_ran: false,
             deps: Vec::new(),
             id: NodeId::Service(id),
+            key: ServiceKey::of::<T>(),
             tasks: Vec::new(),
+            commands: Vec::new(),
             name: T::name().to_string(),
             registered: false,
             event_queue: Vec::new(),
+            changed_at: None,
+            last_status_change: Tick::default(),
+            uptime: Duration::ZERO,
+            prewarming: false,
+            init_progress: None,
+            tags: BTreeSet::new(),
+            stuck_frames: 0,
+            stuck_warning_after: DEFAULT_STUCK_WARNING_FRAMES,
+            task_poll_interval: None,
+            last_task_poll: None,
+            task_polls: 0,
         }
     }
     /// Inputs: World, ID of the wrapper resource.
-    pub(crate) fn register<T: Service>(world: &mut World, spec: ServiceSpec<T>) {
-        let on_init = spec
-            .on_init
-            .map(|hook| world.register_boxed_system(hook.0).entity());
-        let on_deinit = spec
-            .on_deinit
-            .map(|hook| world.register_boxed_system(hook.0).entity());
-        let on_up = spec
-            .on_up
-            .map(|hook| world.register_boxed_system(hook.0).entity());
-        let on_down = spec
-            .on_down
-            .map(|hook| world.register_boxed_system(hook.0).entity());
+    pub(crate) fn register<T: Service>(
+        world: &mut World,
+        spec: ServiceSpec<T>,
+    ) -> Result<(), DepInitErr> {
+        let cid = world.resource_id::<T>().unwrap();
+        let this = Self {
+            name: spec.name.clone().unwrap_or_else(T::name),
+            ..Self::new::<T>(cid)
+        };
+        Self::register_as(world, spec, this)
+    }
 
+    /// Registers an instance of the service `T` under the given key. See
+    /// [ServiceAppExt::register_service_instance].
+    pub(crate) fn register_instance<T: Service>(
+        world: &mut World,
+        spec: ServiceSpec<T>,
+        key: &str,
+    ) -> Result<(), DepInitErr> {
         let cid = world.resource_id::<T>().unwrap();
-        let id = NodeId::Service(cid);
-        // insert self into dependency tree.
-        let this = Self::new::<T>(cid).clone();
-        let mut deps = {
-            let mut graph = world.resource_mut::<DependencyGraph>();
-            register_deps(&mut graph, this.id, spec.deps).expect("Dependencies are invalid.")
+        let this = Self {
+            id: NodeId::service_instance(cid, key),
+            key: ServiceKey::instance::<T>(key),
+            name: format!("{}[{key}]", spec.name.as_deref().unwrap_or(&T::name())),
+            ..Self::new::<T>(cid)
         };
+        Self::register_as(world, spec, this)
+    }
+
+    fn register_as<T: Service>(
+        world: &mut World,
+        mut spec: ServiceSpec<T>,
+        this: Self,
+    ) -> Result<(), DepInitErr> {
+        let id = this.id;
+        // insert self into dependency tree.
+        let direct_deps = core::mem::take(&mut spec.deps);
+        let deps = world.resource_scope(|world, mut graph: Mut<DependencyGraph>| {
+            let cache = world.resource::<GraphDataCache>();
+            register_deps(
+                &mut graph,
+                cache,
+                (this.id, &this.name),
+                direct_deps.clone(),
+            )
+        });
+        // remember rejected deps for ServiceAppExt::validate_services
+        let mut rejected = world.resource_mut::<RejectedDeps>();
+        rejected.retain(|(other, _)| *other != id);
+        if deps.is_err() {
+            rejected.push((id, direct_deps));
+        }
+        let mut deps = deps?;
         // remove self from topsort
         assert_eq!(id, deps.remove(0));
-        let this = Self {
-            on_init,
-            on_deinit,
-            on_up,
-            on_down,
+
+        let mut this = Self {
             deps,
             registered: true,
+            changed_at: Some(Instant::now()),
+            last_status_change: world.read_change_tick(),
             ..this
         };
+        this.install_spec(world, spec);
         world
             .resource_mut::<GraphDataCache>()
             .insert(id, GraphData::Service(this));
+        Ok(())
+    }
+
+    /// Registers the hooks from `spec` and takes on its settings. The spec's
+    /// deps are left to the caller.
+    fn install_spec<T: Service>(&mut self, world: &mut World, spec: ServiceSpec<T>) {
+        self.on_init = spec
+            .on_init
+            .map(|hook| world.register_boxed_system(hook.0).entity());
+        self.on_deinit = spec
+            .on_deinit
+            .map(|hook| world.register_boxed_system(hook.0).entity());
+        self.on_up = spec
+            .on_up
+            .map(|hook| world.register_boxed_system(hook.0).entity());
+        self.on_down = spec
+            .on_down
+            .map(|hook| world.register_boxed_system(hook.0).entity());
+        self.on_transition = spec
+            .on_transition
+            .map(|hook| world.register_boxed_system(hook.0).entity());
+        self.dep_conditions = spec
+            .dep_conditions
+            .into_iter()
+            .map(|(id, condition)| (id, world.register_boxed_system(condition).entity()))
+            .collect();
+        self.can_spin_down = spec
+            .can_spin_down
+            .map(|condition| world.register_boxed_system(condition).entity());
+        self.lost_hooks.clear();
+        self.tags = spec.tags;
+        self.stuck_warning_after = spec
+            .stuck_warning_after
+            .unwrap_or(DEFAULT_STUCK_WARNING_FRAMES);
+        self.task_poll_interval = spec.task_poll_interval;
+        self.stop_deps_on_failure = spec.stop_deps_on_failure;
+        self.catch_hook_panics = spec.catch_hook_panics;
+    }
+
+    /// Installs the hooks and settings from `spec` without changing the
+    /// service's status. Does nothing if the spec's direct dependencies
+    /// differ from the current ones.
+    pub(crate) fn hot_swap<T: Service>(world: &mut World, spec: ServiceSpec<T>, setup: ScopeSetup) {
+        let id = world.service::<T>().id();
+        let old_deps = world
+            .resource::<DependencyGraph>()
+            .neighbors(id)
+            .collect::<HashSet<_>>();
+        let compatible = spec.deps.iter().collect::<HashSet<_>>() == old_deps.iter().collect();
+        if !compatible {
+            warn!(
+                "Tried to hot swap service {}, but its dependencies changed. Keeping the old hooks.",
+                T::name()
+            );
+            return;
+        }
+        // the deps' data is already cached, so this only replaces config
+        setup.apply(world);
+        let mut spec = Some(spec);
+        world.service_scope::<T, ()>(|world, service| {
+            debug!("({}) Hot swapping hooks", service.name());
+            for hook in service.hooks() {
+                world.despawn(hook);
+            }
+            if let Some(spec) = spec.take() {
+                service.install_spec(world, spec);
+            }
+        });
+    }
+
+    /// The hook systems owned by the service.
+    fn hooks(&self) -> impl Iterator<Item = Entity> + '_ {
+        [
+            self.on_init,
+            self.on_deinit,
+            self.on_up,
+            self.on_down,
+            self.on_transition,
+            self.can_spin_down,
+        ]
+        .into_iter()
+        .flatten()
+        .chain(self.dep_conditions.iter().map(|(_, entity)| *entity))
+    }
+
+    /// Despawns the systems and tasks owned by the service. Used when the
+    /// service is deregistered.
+    pub(crate) fn despawn_systems(&self, world: &mut World) {
+        for entity in self.hooks().chain(self.tasks.iter().copied()) {
+            world.despawn(entity);
+        }
     }
 
     // Getters, setters ///////////////////////////////////////////////////////
@@ -93,24 +308,152 @@ impl ServiceData {
         &self.deps
     }
 
+    /// Gets this service's resource dependencies, as added with
+    /// [ServiceScope::add_resource] and friends.
+    pub fn resource_deps<'a>(
+        &'a self,
+        cache: &'a GraphDataCache,
+    ) -> impl Iterator<Item = &'a ResourceData> + 'a {
+        self.deps
+            .iter()
+            .filter(|dep| matches!(dep, NodeId::Resource(_)))
+            .filter_map(|dep| cache.get_resource(*dep))
+    }
+
+    /// Is the resource `R` one of this service's dependencies, up, and present
+    /// in the world?
+    pub fn is_resource_ready<R: Resource>(&self, world: &World) -> bool {
+        let Some(id) = world.components().resource_id::<R>() else {
+            return false;
+        };
+        self.resource_deps(world.resource::<GraphDataCache>())
+            .any(|dep| dep.id == id && dep.status.is_up())
+            && world.contains_resource::<R>()
+    }
+
+    /// Gets the tags attached to this service with [ServiceScope::with_tag].
+    pub fn tags(&self) -> &BTreeSet<String> {
+        &self.tags
+    }
+
+    /// Does this service have the given tag?
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+
     /// Gets this service's status, owned.
     pub fn status(&self) -> ServiceStatus {
         self.status.clone()
     }
 
     /// Sets the current status and queues up a broadcast event.
-    fn set_status(&mut self, status: ServiceStatus) {
+    fn set_status(&mut self, world: &World, status: ServiceStatus) {
         self.event_queue.push(ServiceUpdated {
             old_status: self.status.clone(),
             new_status: status.clone(),
             id: self.id,
+            name: self.name().to_string(),
         });
         debug!(
             "({}) NEW STATUS: {:?} -> {status:?}",
             self.name(),
             self.status,
         );
+        if self.status.is_up() {
+            self.uptime += self.time_in_status();
+        }
         self.status = status;
+        self.changed_at = Some(Instant::now());
+        self.last_status_change = world.read_change_tick();
+        self.init_progress = None;
+        self.stuck_frames = 0;
+    }
+
+    /// Gets the progress, from 0.0 to 1.0, reported by the service's init
+    /// tasks. Returns `None` unless the service is initializing with a task
+    /// created by [AsyncHook::progress_task].
+    pub fn init_progress(&self) -> Option<f32> {
+        self.init_progress.map(f32::from_bits)
+    }
+
+    /// How many times the service's async tasks have been polled. See
+    /// [ServiceScope::task_poll_interval].
+    pub fn task_polls(&self) -> u32 {
+        self.task_polls
+    }
+
+    pub(crate) fn set_init_progress(&mut self, progress: f32) {
+        self.init_progress = Some(progress.to_bits());
+    }
+
+    /// Gets the [Instant] at which the service entered its current status.
+    /// Returns `None` if the service has not been registered.
+    pub fn changed_at(&self) -> Option<Instant> {
+        self.changed_at
+    }
+
+    /// Gets the world's change [Tick] at which the service entered its
+    /// current status. Systems which cache per-service data can store it and
+    /// check [ServiceRef::changed_since] to know when to invalidate.
+    pub fn last_status_change(&self) -> Tick {
+        self.last_status_change
+    }
+
+    /// Has the service been initializing or deinitializing for longer than
+    /// allowed? See [ServiceScope::stuck_warning_after].
+    pub fn is_stuck(&self) -> bool {
+        self.status.is_transitioning() && self.stuck_frames >= self.stuck_warning_after
+    }
+
+    /// Gets the number of async tasks the service is waiting on while it
+    /// initializes or deinitializes.
+    pub fn pending_tasks(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Gets the task pool of each async task the service is waiting on, e.g.
+    /// to find out why it's stuck initializing. See [AsyncHook::pool].
+    pub fn pending_task_pools(&self, world: &World) -> Vec<TaskPoolKind> {
+        self.tasks
+            .iter()
+            .filter_map(|entity| world.get::<AsyncHook>(*entity))
+            .map(AsyncHook::pool)
+            .collect()
+    }
+
+    /// Gets how long the service has been in its current status.
+    /// Returns [Duration::ZERO] if the service has not been registered.
+    pub fn time_in_status(&self) -> Duration {
+        self.changed_at
+            .map(|instant| instant.elapsed())
+            .unwrap_or_default()
+    }
+
+    /// Gets how long the service has spent [ServiceStatus::Up] in total,
+    /// across every time it has been spun up.
+    pub fn total_uptime(&self) -> Duration {
+        if self.status.is_up() {
+            self.uptime + self.time_in_status()
+        } else {
+            self.uptime
+        }
+    }
+
+    /// Is the service's state machine healthy? A service is live unless it
+    /// has failed or is in the process of failing. Services which are
+    /// initializing or spun down are still live.
+    pub fn is_live(&self) -> bool {
+        !self.status.is_failed() && !self.status.is_failing()
+    }
+
+    /// Is the service ready to do work? A service is ready when it and all of
+    /// its dependencies are up.
+    pub fn is_ready(&self, cache: &GraphDataCache) -> bool {
+        self.status.is_up()
+            && self
+                .deps
+                .iter()
+                .all(|dep| cache.get(dep).is_some_and(|dep| dep.status().is_up()))
     }
 
     /// Gets this service's name.
@@ -118,6 +461,11 @@ impl ServiceData {
         &self.name
     }
 
+    /// Gets this service's [ServiceKey], which is stable across runs.
+    pub fn key(&self) -> &ServiceKey {
+        &self.key
+    }
+
     /// Gets the ID of this ServiceData's [Service] resource.
     pub fn id(&self) -> NodeId {
         self.id
@@ -134,20 +482,94 @@ impl ServiceData {
     /// Spins the service up, automatically running its initialization and on_up
     /// hooks. Will do nothing if the service is already up. See
     /// [hooks](crate::lifecycle::hooks) for more details.
+    ///
+    /// If the service and its dependencies are all synchronous, the service is
+    /// up by the time this returns. Otherwise it stays initializing until its
+    /// tasks and dependencies finish on later updates.
     pub fn spin_up(&mut self, world: &mut World) {
         self.initialize(world, false);
     }
+    /// Spins the service up like [ServiceData::spin_up], but only if all of its
+    /// service dependencies are already up. Its dependencies will not be spun
+    /// up. Resources and assets are still loaded as usual. Will do nothing if
+    /// a dependency isn't up.
+    pub fn spin_up_if_ready(&mut self, world: &mut World) {
+        let cache = world.resource::<GraphDataCache>();
+        let waiting = self
+            .deps
+            .iter()
+            .filter_map(|id| cache.get(id))
+            .find(|dep| dep.is_service() && !dep.status().is_up());
+        if let Some(dep) = waiting {
+            debug!(
+                "({}) Not spinning up, dependency {} is {:?}",
+                self.name,
+                dep.name(),
+                dep.status()
+            );
+            return;
+        }
+        self.spin_up(world);
+    }
     /// Forcibly spins the service up, automatically running its initialization
     /// and on_up hooks. See [hooks](crate::lifecycle::hooks) for more details.
+    ///
+    /// If the service is already initializing, e.g. from an earlier restart,
+    /// the restart is coalesced into the pending initialization rather than
+    /// running the init hook again. A prewarming service will come up once
+    /// it's initialized. Spinning the service down cancels the pending
+    /// initialization as usual.
     pub fn restart(&mut self, world: &mut World) {
+        if self.status().is_initializing() {
+            debug!("({}) Already initializing, coalescing restart", self.name());
+            self.prewarming = false;
+            return;
+        }
         self.initialize(world, true);
     }
+    /// Initializes the service without running its on_up hook, leaving it in
+    /// [ServiceStatus::Standby]. Will do nothing if the service isn't down.
+    pub fn prewarm(&mut self, world: &mut World) {
+        if !self.status().is_down() {
+            warn!("Tried to prewarm service {}, but it isn't down!", self.name,);
+            return;
+        }
+        self.prewarming = true;
+        self.initialize(world, false);
+    }
     /// Spins the service down, automatically running its deinitialization and
     /// on_down hooks. Will do nothing if the service is already down for any
-    /// reason. See [hooks](crate::lifecycle::hooks) for more details.
+    /// reason; in particular, spinning down a service that was never
+    /// initialized emits no [ServiceUpdated] events. If the service is
+    /// initializing, its pending init tasks are cancelled first. See
+    /// [hooks](crate::lifecycle::hooks) for more details.
     pub fn spin_down(&mut self, world: &mut World) {
         self.deinit(world, DownReason::SpunDown);
     }
+    /// Spins the service down like [ServiceData::spin_down], but records the
+    /// given reason as [DownReason::Custom]. The on_down hook receives the
+    /// custom reason. Dependencies are spun down as usual.
+    pub fn spin_down_with_reason(&mut self, world: &mut World, reason: impl Into<String>) {
+        self.deinit(world, DownReason::Custom(reason.into()));
+    }
+    /// Brings the service straight to [DownReason::SpunDown] without running
+    /// its deinitialization or on_down hooks. Pending tasks are cancelled and
+    /// dependencies are left as they are. Will do nothing if the service is
+    /// already down.
+    pub fn force_down(&mut self, world: &mut World) {
+        if self.status().is_down() {
+            warn!(
+                "Tried to force service {} down, but it was already down!",
+                self.name
+            );
+            return;
+        }
+        debug!("({}) Forcing down", self.name());
+        self.prewarming = false;
+        self.cancel_tasks(world);
+        self.drop_context(world);
+        self.set_status(world, ServiceStatus::Down(DownReason::SpunDown));
+    }
     /// Fails the service with the given error. Will run the deinitialization
     /// and on_down hooks. If the deinit hook fails during this process, the
     /// service will forcibly shut down.
@@ -157,7 +579,7 @@ impl ServiceData {
 
     // Lifecycle ///////////////////////////////////////////////////////////////
 
-    #[tracing::instrument(skip_all, fields(force))]
+    #[tracing::instrument(skip_all, fields(service = %self.name, force))]
     fn initialize(&mut self, world: &mut World, force: bool) {
         debug!("({}) Initializing...", self.name());
         if self.status().is_up() && !force {
@@ -167,8 +589,31 @@ impl ServiceData {
             );
             return;
         }
+        self.check_dep_conditions(world);
+        if !force {
+            let deps_up = self.deps_ok(ServiceStatus::Up, world.resource::<GraphDataCache>());
+            if self.status().is_standby() && matches!(deps_up, Ok(true)) {
+                debug!("({}) Waking from standby", self.name());
+                if !self.transition_allowed(world, &ServiceStatus::Up) {
+                    return;
+                }
+                return self.on_up(world);
+            }
+            if self.status().is_initializing() && self.prewarming {
+                debug!("({}) Prewarming service will come up", self.name());
+                self.prewarming = false;
+                return;
+            }
+        }
+        if !self.transition_allowed(world, &ServiceStatus::Init) {
+            return;
+        }
+        if force {
+            self.prewarming = false;
+        }
 
-        self.set_status(ServiceStatus::Init);
+        self.set_status(world, ServiceStatus::Init);
+        self.retained_deps.clear();
 
         if let Err(e) = self.cycle_deps(world, None) {
             debug!("({}) deps failed!", self.name());
@@ -176,7 +621,9 @@ impl ServiceData {
         }
 
         debug!("({}) deps ok", self.name());
-        let res: InitResult = self.run_hook(world, self.on_init).unwrap_or(Ok(None));
+        let res: InitResult = self
+            .run_hook(world, self.on_init)
+            .unwrap_or_else(|| self.hook_error_or(Ok(None)));
         match res {
             Ok(Some(task)) => {
                 debug!("({}) hook is async", self.name());
@@ -188,7 +635,7 @@ impl ServiceData {
                 match self.deps_ok(ServiceStatus::Up, world.resource::<GraphDataCache>()) {
                     Ok(true) => {
                         debug!("({}) deps all done", self.name());
-                        self.on_up(world);
+                        self.on_initialized(world);
                     }
                     Ok(false) => {}
                     Err(e) => {
@@ -198,28 +645,39 @@ impl ServiceData {
             }
             Err(e) => {
                 debug!("({}) hook failed", self.name());
-                self.on_failure(world, ServiceError::Own(e.to_string()), false);
+                self.on_failure(world, e.into(), false);
             }
         }
         debug!("({}) ... Done Initializing!", self.name());
     }
 
+    /// Should only be run when initialization and all deps are finished.
+    fn on_initialized(&mut self, world: &mut World) {
+        if std::mem::take(&mut self.prewarming) {
+            self.set_status(world, ServiceStatus::Standby);
+        } else {
+            self.on_up(world);
+        }
+    }
+
     /// Should only be run when all deps are finished.
-    #[tracing::instrument(skip_all)]
+    #[tracing::instrument(skip_all, fields(service = %self.name))]
     fn on_up(&mut self, world: &mut World) {
-        let res: UpResult = self.run_hook(world, self.on_up).unwrap_or(Ok(()));
+        let res: UpResult = self
+            .run_hook(world, self.on_up)
+            .unwrap_or_else(|| self.hook_error_or(Ok(())));
         if let Err(error) = res {
-            let error = ServiceError::Own(error.to_string());
+            let error = error.into();
             self.on_failure(world, error, false);
         } else {
-            self.set_status(ServiceStatus::Up);
+            self.set_status(world, ServiceStatus::Up);
         }
     }
 
-    #[tracing::instrument(skip_all, fields(reason))]
+    #[tracing::instrument(skip_all, fields(service = %self.name, reason))]
     fn deinit(&mut self, world: &mut World, reason: DownReason) {
-        debug!("({}) Deinitializing... ({reason:?})", self.name());
         let is_failure = matches!(reason, DownReason::Failed(_));
+        // Bail before touching any state so redundant calls stay silent.
         if !is_failure && self.status().is_down() || is_failure && self.status().is_failed() {
             warn!(
                 "Tried to spin down service {}, but it was already down!",
@@ -227,14 +685,33 @@ impl ServiceData {
             );
             return;
         }
+        debug!("({}) Deinitializing... ({reason:?})", self.name());
 
-        self.set_status(ServiceStatus::Deinit(reason.clone()));
-        if let Err(e) = self.cycle_deps(world, Some(reason.clone())) {
+        if !is_failure && !self.spin_down_allowed(world, &reason) {
+            return;
+        }
+        if !self.transition_allowed(world, &ServiceStatus::Deinit(reason.clone())) {
+            return;
+        }
+        self.prewarming = false;
+        if self.status().is_initializing() {
+            self.cancel_tasks(world);
+        }
+        self.set_status(world, ServiceStatus::Deinit(reason.clone()));
+        self.on_down_ran = false;
+        let res = match reason {
+            DownReason::Failed(_) if self.stop_deps_on_failure => self.stop_unneeded_deps(world),
+            _ => self.cycle_deps(world, Some(reason.clone())),
+        };
+        if let Err(e) = res {
             debug!("({}) cycle_deps failed!", self.name());
             return self.on_failure(world, e, true);
         }
 
-        let res: DeinitResult = self.run_hook(world, self.on_deinit).unwrap_or(Ok(None));
+        let res: DeinitResult = self
+            .run_hook(world, self.on_deinit)
+            .unwrap_or_else(|| self.hook_error_or(Ok(None)));
+        self.drop_context(world);
         match res {
             Ok(Some(res)) => {
                 debug!("({}) hook is async", self.name());
@@ -259,36 +736,249 @@ impl ServiceData {
             },
             Err(e) => {
                 debug!("({}) hook failed", self.name());
-                self.on_failure(world, ServiceError::Own(e.to_string()), true)
+                self.on_failure(world, e.into(), true)
             }
         }
         debug!("({}) ... Done Deinitializing!", self.name());
     }
 
-    /// Should only be run when all deps are finished.
-    #[tracing::instrument(skip_all, fields(reason))]
-    fn on_down(&mut self, world: &mut World, reason: DownReason) {
-        self.run_hook_with::<In<DownReason>, ()>(world, self.on_down, reason.clone())
-            .unwrap_or_default();
-        self.set_status(ServiceStatus::Down(reason));
+    /// Should only be run when all deps are finished. If the hook returns a
+    /// task, the service stays deinitializing until it finishes.
+    #[tracing::instrument(skip_all, fields(service = %self.name, reason))]
+    fn on_down(&mut self, world: &mut World, mut reason: DownReason) {
+        self.on_down_ran = true;
+        let res: DownResult = self
+            .run_hook_with::<In<DownReason>, DownResult>(world, self.on_down, reason.clone())
+            .flatten();
+        if let Some(error) = self.hook_error.take() {
+            reason = DownReason::Failed(error);
+        }
+        if let Some(task) = res {
+            debug!("({}) on_down hook is async", self.name());
+            let id = world.spawn(task).id();
+            self.tasks.push(id);
+            return;
+        }
+        self.set_status(world, ServiceStatus::Down(reason));
     }
 
     /// Handles errors. If `is_warning`, the service's state will not change.
     /// ## Status
     /// if force { * => Down } else { * => Deinit }
-    #[tracing::instrument(skip_all, fields(error, force))]
+    #[tracing::instrument(skip_all, fields(service = %self.name, error, force))]
     fn on_failure(&mut self, world: &mut World, error: ServiceError, force: bool) {
         error!("{error}");
+        if matches!(error, ServiceError::Dependency(..))
+            && let Some(origin) = self.cascade_origin(world.resource::<GraphDataCache>())
+        {
+            world.send_event(ServiceCascadeFailure {
+                origin,
+                affected: self.id,
+            });
+        }
         if !force {
             let reason = DownReason::Failed(error);
             self.deinit(world, reason);
         } else {
-            self.set_status(ServiceStatus::failed(error));
+            self.drop_context(world);
+            self.set_status(world, ServiceStatus::failed(error));
+        }
+    }
+
+    /// Drops the context shared by the service's hooks, if the deinit hook
+    /// didn't take it. See [ServiceScope::init_with_context].
+    fn drop_context(&self, world: &mut World) {
+        if let Some(mut contexts) = world.get_resource_mut::<ServiceContexts>() {
+            contexts.remove(&self.id);
+        }
+    }
+
+    /// Finishes an asynchronous transition once its tasks and deps are done,
+    /// running the on_up or on_down hook if it was skipped while waiting.
+    pub(crate) fn update_async_state(&mut self, world: &mut World) {
+        let goal = match self.status() {
+            ServiceStatus::Deinit(r) => ServiceStatus::Down(r),
+            ServiceStatus::Init => ServiceStatus::Up,
+            _ => return,
+        };
+        match self.deps_ok(goal.clone(), world.resource::<GraphDataCache>()) {
+            Ok(true) if !self.tasks.is_empty() => {}
+            Ok(true) => match goal {
+                ServiceStatus::Down(reason) if !self.on_down_ran => self.on_down(world, reason),
+                ServiceStatus::Up => self.on_initialized(world),
+                goal => self.set_status(world, goal),
+            },
+            Err(e) => self.fail(world, e),
+            _ => {}
+        }
+    }
+
+    /// Should the service's tasks be polled this frame? Records the poll if
+    /// so. See [ServiceScope::task_poll_interval].
+    pub(crate) fn task_poll_due(&mut self) -> bool {
+        if let Some(interval) = self.task_poll_interval
+            && self
+                .last_task_poll
+                .is_some_and(|last| last.elapsed() < interval)
+        {
+            return false;
+        }
+        self.last_task_poll = Some(Instant::now());
+        self.task_polls += 1;
+        true
+    }
+
+    /// Polls the service's async tasks once. Finished tasks are despawned,
+    /// then the commands of those which succeeded are applied, and a failure
+    /// fails the service.
+    #[tracing::instrument(skip_all, fields(service = %self.name))]
+    pub(crate) fn poll_tasks(&mut self, world: &mut World) {
+        if self.tasks.is_empty() || !self.task_poll_due() {
+            return;
+        }
+        let status = self.status();
+        if !status.is_initializing() && !status.is_deinitializing() {
+            warn!(
+                "Non-empty task queue for service {} despite having status {status:?}",
+                self.name()
+            );
+        }
+        let mut finished = vec![];
+        for entity in std::mem::take(&mut self.tasks) {
+            let Some(mut hook) = world.get_mut::<AsyncHook>(entity) else {
+                continue;
+            };
+            match hook.poll() {
+                Some(res) => finished.push((entity, res)),
+                None => self.tasks.push(entity),
+            }
+        }
+        for (entity, res) in finished {
+            debug!("({}) Finished task", self.name());
+            self.finish_task(world, entity, res);
+        }
+    }
+
+    /// Despawns a finished task, then applies its commands or fails the
+    /// service with its error.
+    fn finish_task(
+        &mut self,
+        world: &mut World,
+        entity: Entity,
+        res: Result<CommandQueue, ServiceError>,
+    ) {
+        if let Ok(entity) = world.get_entity_mut(entity) {
+            entity.despawn();
+        }
+        match res {
+            Ok(mut queue) => queue.apply(world),
+            Err(e) => self.fail(world, e),
+        }
+    }
+
+    /// Does the service have lifecycle work to do? True while it has queued
+    /// commands or tasks, is transitioning, has status changes to broadcast,
+    /// or has asset deps to track.
+    fn has_pending_work(&self) -> bool {
+        let status = self.status();
+        !self.commands.is_empty()
+            || !self.tasks.is_empty()
+            || !self.event_queue.is_empty()
+            || status.is_initializing()
+            || status.is_deinitializing()
+            // asset deps are tracked by update_dep_status while the service is live
+            || (!status.is_down() && self.deps.iter().any(|dep| dep.asset_id().is_some()))
+    }
+
+    /// Runs the lifecycle of a service instance: polls its tasks, finishes any
+    /// asynchronous transition, and broadcasts its status changes as
+    /// [ServiceUpdated] events.
+    pub(crate) fn update_instance(&mut self, world: &mut World) {
+        self.poll_tasks(world);
+        self.update_async_state(world);
+        world.send_event_batch(self.event_queue.drain(..));
+    }
+
+    /// Blocks on the service's async tasks until they have all finished. Must
+    /// be called on the main thread, as hooks run on its local executor.
+    pub(crate) fn finish_tasks(&mut self, world: &mut World) {
+        for entity in std::mem::take(&mut self.tasks) {
+            let Some(mut hook) = world.entity_mut(entity).take::<AsyncHook>() else {
+                continue;
+            };
+            let res = loop {
+                if let Some(res) = hook.poll() {
+                    break res;
+                }
+                tick_global_task_pools_on_main_thread();
+            };
+            self.finish_task(world, entity, res);
+        }
+    }
+
+    /// Drops the service's pending tasks by despawning their entities.
+    fn cancel_tasks(&mut self, world: &mut World) {
+        for entity in std::mem::take(&mut self.tasks) {
+            debug!("({}) Cancelling task {entity}", self.name());
+            if let Ok(entity) = world.get_entity_mut(entity) {
+                entity.despawn();
+            }
         }
     }
 
     // Helpers ////////////////////////////////////////////////////////////////
 
+    /// Asks the service's transition guard whether it may start moving to
+    /// `to`. Failures always go through, so a guard can't keep a failing
+    /// service from going down.
+    fn transition_allowed(&mut self, world: &mut World, to: &ServiceStatus) -> bool {
+        if to.is_failing() || to.is_failed() {
+            return true;
+        }
+        let from = self.status();
+        let allowed = self
+            .run_hook_with::<In<(ServiceStatus, ServiceStatus)>, bool>(
+                world,
+                self.on_transition,
+                (from.clone(), to.clone()),
+            )
+            .unwrap_or(true);
+        if !allowed {
+            warn!(
+                "({}) Transition {from:?} -> {to:?} was rejected by the guard",
+                self.name
+            );
+        }
+        allowed
+    }
+
+    /// Asks the service's spin-down guard whether it may go down. If not, the
+    /// spin-down is queued again for a later frame. See
+    /// [ServiceScope::can_spin_down].
+    fn spin_down_allowed(&mut self, world: &mut World, reason: &DownReason) -> bool {
+        if self
+            .run_hook::<bool>(world, self.can_spin_down)
+            .unwrap_or(true)
+        {
+            self.spin_down_deferred = false;
+            return true;
+        }
+        if !std::mem::replace(&mut self.spin_down_deferred, true) {
+            warn!(
+                "({}) Spin-down was deferred by the service's guard",
+                self.name
+            );
+        }
+        let command = match reason {
+            DownReason::Custom(reason) => LifecycleCommandKind::SpinDownWithReason(reason.clone()),
+            _ => LifecycleCommandKind::SpinDown,
+        };
+        if !self.commands.contains(&command) {
+            self.commands.push(command);
+        }
+        false
+    }
+
     fn run_hook<O: 'static>(&mut self, world: &mut World, hook: Option<Entity>) -> Option<O> {
         self.run_hook_with::<(), O>(world, hook, ())
     }
@@ -299,10 +989,100 @@ impl ServiceData {
         hook: Option<Entity>,
         input: I::Inner<'_>,
     ) -> Option<O> {
-        hook.map(|hook| {
-            let id = SystemId::<I, O>::from_entity(hook);
-            world.run_system_with(id, input).expect("Valid system")
-        })
+        self.hook_error = None;
+        let hook = hook?;
+        if self.lost_hooks.contains(&hook) {
+            self.hook_error = Some(ServiceError::own(
+                "hook was lost when it panicked on a previous run",
+            ));
+            return None;
+        }
+        let id = SystemId::<I, O>::from_entity(hook);
+        let res = if self.catch_hook_panics {
+            match catch_unwind(AssertUnwindSafe(|| world.run_system_with(id, input))) {
+                Ok(res) => res,
+                Err(payload) => {
+                    let message = payload
+                        .downcast_ref::<&str>()
+                        .map(ToString::to_string)
+                        .or_else(|| payload.downcast_ref::<String>().cloned())
+                        .unwrap_or_default();
+                    error!("({}) Hook panicked: {message}", self.name);
+                    self.lost_hooks.push(hook);
+                    self.hook_error = Some(ServiceError::own(format!("hook panicked: {message}")));
+                    return None;
+                }
+            }
+        } else {
+            world.run_system_with(id, input)
+        };
+        // e.g. the hook's params failed validation
+        match res {
+            Ok(res) => Some(res),
+            Err(e) => {
+                error!("({}) Couldn't run hook: {e}", self.name);
+                self.hook_error = Some(ServiceError::own(format!("couldn't run hook: {e}")));
+                None
+            }
+        }
+    }
+
+    /// Takes the error from the hook which just panicked or couldn't run, or
+    /// returns `default` if it ran. See [ServiceScope::catch_hook_panics].
+    fn hook_error_or<T>(&mut self, default: Result<T, BevyError>) -> Result<T, BevyError> {
+        match self.hook_error.take() {
+            Some(error) => Err(error.into()),
+            None => default,
+        }
+    }
+
+    /// Decides which deps are skipped this session, the first time the service
+    /// spins up. See [ServiceScope::add_dep_if].
+    fn check_dep_conditions(&mut self, world: &mut World) {
+        if self.skipped_deps.is_some() {
+            return;
+        }
+        let mut skipped = vec![];
+        for (dep, condition) in self.dep_conditions.clone() {
+            if !self
+                .run_hook::<bool>(world, Some(condition))
+                .unwrap_or(true)
+            {
+                debug!("({}) Skipping dependency {dep:?}", self.name);
+                skipped.push(dep);
+            }
+        }
+        if !skipped.is_empty() {
+            // deps which are only needed by skipped deps are skipped as well
+            let graph = world.resource::<DependencyGraph>();
+            let mut needed = HashSet::<NodeId>::default();
+            let mut stack = vec![self.id];
+            while let Some(id) = stack.pop() {
+                for next in graph.neighbors(id) {
+                    if id == self.id && skipped.contains(&next) {
+                        continue;
+                    }
+                    if needed.insert(next) {
+                        stack.push(next);
+                    }
+                }
+            }
+            skipped = self
+                .deps
+                .iter()
+                .copied()
+                .filter(|id| !needed.contains(id))
+                .collect();
+        }
+        self.skipped_deps = Some(skipped.into());
+    }
+
+    /// Is the dep needed this session? See [ServiceScope::add_dep_if].
+    fn dep_enforced(&self, id: &NodeId) -> bool {
+        !self
+            .skipped_deps
+            .as_ref()
+            .is_some_and(|skipped| skipped.contains(id))
     }
 
     /// Pass without down_reason to spin up.
@@ -322,14 +1102,28 @@ impl ServiceData {
             self.deps.len(),
         );
 
-        for id in self.deps.iter_mut() {
-            if let Some(mut dep) = world.resource_mut::<GraphDataCache>().remove(&*id) {
-                dep.cycle(world, down_reason.clone())?;
+        for id in self.deps.iter() {
+            if !self.dep_enforced(id) {
+                continue;
+            }
+            if let Some(mut dep) = world.resource_mut::<GraphDataCache>().remove(id) {
+                debug!(
+                    parent = ?self.id,
+                    parent_name = self.name,
+                    dep = ?id,
+                    dep_name = dep.name(),
+                    "({}) {} dep {}",
+                    self.name,
+                    if down_reason.is_none() { "Initializing" } else { "Deinitializing" },
+                    dep.name(),
+                );
+                let res = dep.cycle(world, self.id, down_reason.clone());
                 world.resource_mut::<GraphDataCache>().insert(*id, dep);
+                res?;
             } else {
-                return Err(ServiceError::Dependency(
+                return Err(ServiceError::dependency(
                     format!("{id:?}"),
-                    "Dependency not found in cache.".into(),
+                    ServiceError::own("Dependency not found in cache."),
                 ));
             }
         }
@@ -337,30 +1131,158 @@ impl ServiceData {
         Ok(())
     }
 
-    fn deps_ok(&self, goal: ServiceStatus, cache: &GraphDataCache) -> Result<bool, ServiceError> {
-        let err = self.deps.iter().find_map(|dep| {
-            let status = cache.get(dep)?.status();
-            let name = cache.get(dep)?.name();
-            match status {
-                ServiceStatus::Deinit(DownReason::Failed(e))
-                | ServiceStatus::Down(DownReason::Failed(e)) => Some((name, e)),
-                _ => None,
+    /// Spins down the deps which no other live service depends on, and
+    /// retains the rest. Used instead of [cycle_deps](Self::cycle_deps) when
+    /// the service fails. See [ServiceScope::stop_deps_on_failure].
+    fn stop_unneeded_deps(&mut self, world: &mut World) -> Result<(), ServiceError> {
+        debug!("({}) Stopping unneeded deps.", self.name);
+        self.retained_deps.clear();
+        for id in self.deps.clone() {
+            if !self.dep_enforced(&id) {
+                continue;
             }
-        });
-        if let Some((name, e)) = err {
-            return Err(ServiceError::Dependency(name.to_string(), e.to_string()));
+            // resources and assets already count their owners
+            let needed = id.service_id().is_some() && {
+                let cache = world.resource::<GraphDataCache>();
+                world
+                    .resource::<DependencyGraph>()
+                    .dependents(id)
+                    .filter(|other| *other != self.id)
+                    .filter_map(|other| cache.get(&other))
+                    .any(|other| {
+                        let status = other.status();
+                        !status.is_down() && !status.is_deinitializing()
+                    })
+            };
+            if needed {
+                debug!(
+                    "({}) Leaving {id:?} up for its other dependents.",
+                    self.name
+                );
+                self.retained_deps.push(id);
+                continue;
+            }
+            let Some(mut dep) = world.resource_mut::<GraphDataCache>().remove(&id) else {
+                continue;
+            };
+            let res = dep.cycle(world, self.id, Some(DownReason::SpunDown));
+            world.resource_mut::<GraphDataCache>().insert(id, dep);
+            res?;
         }
+        Ok(())
+    }
+
+    /// Gets the id, name, and status of each of this service's direct
+    /// dependencies. Useful for finding out what's holding a service up.
+    pub fn deps_status(&self, cache: &GraphDataCache) -> Vec<(NodeId, String, ServiceStatus)> {
+        self.deps
+            .iter()
+            .filter_map(|id| {
+                let dep = cache.get(id)?;
+                Some((*id, dep.name().to_string(), dep.status()))
+            })
+            .collect()
+    }
+
+    fn deps_ok(&self, goal: ServiceStatus, cache: &GraphDataCache) -> Result<bool, ServiceError> {
         debug!("Checking deps... goal={goal:?}");
-        let res = self.deps.iter().all(|dep| {
-            let dep = cache.get(dep).unwrap();
-            debug!("({:?}) {:?}", dep.name(), dep.status());
-            dep.status() == goal
-        });
+        // weak assets and skipped deps never block or fail the service
+        let enforced = self
+            .deps
+            .iter()
+            .filter(|id| self.dep_enforced(id))
+            .filter_map(|id| cache.get(id))
+            .filter(|dep| !dep.is_weak());
+        // a failed dep fails the service even if others haven't reached the goal
+        let mut res = true;
+        for dep in enforced {
+            if let ServiceStatus::Deinit(DownReason::Failed(e))
+            | ServiceStatus::Down(DownReason::Failed(e)) = dep.status_ref()
+            {
+                return Err(ServiceError::dependency(dep.name(), e.clone()));
+            }
+            res = res && self.dep_reached(dep, &goal);
+        }
         debug!("... Done! res={res:?}");
         Ok(res)
     }
+
+    /// Finds the failed dependency which the others' failures cascaded from,
+    /// i.e. one with no failed dependencies of its own.
+    fn cascade_origin(&self, cache: &GraphDataCache) -> Option<NodeId> {
+        let failed = |id: &NodeId| {
+            cache
+                .get(id)
+                .is_some_and(|dep| dep.status().is_failed() || dep.status().is_failing())
+        };
+        self.deps
+            .iter()
+            .copied()
+            .filter(|id| self.dep_enforced(id) && !cache.get(id).is_some_and(GraphData::is_weak))
+            .filter(failed)
+            .find(|id| {
+                cache
+                    .get(id)
+                    .and_then(GraphData::as_service)
+                    .is_none_or(|dep| !dep.deps.iter().any(failed))
+            })
+    }
+
+    /// Has the dependency reached the goal status? See [deps_ok](Self::deps_ok).
+    fn dep_reached(&self, dep: &GraphData, goal: &ServiceStatus) -> bool {
+        // deps only need to reach the goal, see ServiceStatus's ordering. Any
+        // down dep counts, whatever its reason.
+        let reached = if goal.is_down() {
+            dep.status_ref().is_down()
+        } else {
+            dep.status_ref() >= goal
+        };
+        // shared deps stay up for their other owners
+        reached
+            || goal.is_down()
+                && (dep.released_by(self.id) || self.retained_deps.contains(&dep.id()))
+    }
+
+    /// Counts the frames the service has spent transitioning, and warns once
+    /// it has been stuck for longer than allowed. See
+    /// [ServiceScope::stuck_warning_after].
+    pub(crate) fn check_stuck(&mut self, cache: &GraphDataCache) {
+        let goal = match self.status() {
+            ServiceStatus::Deinit(r) => ServiceStatus::Down(r),
+            ServiceStatus::Init => ServiceStatus::Up,
+            _ => return,
+        };
+        self.stuck_frames = self.stuck_frames.saturating_add(1);
+        if self.stuck_frames != self.stuck_warning_after {
+            return;
+        }
+        let blocker = self.deps.iter().filter_map(|id| cache.get(id)).find(|dep| {
+            !dep.is_weak() && self.dep_enforced(&dep.id()) && !self.dep_reached(dep, &goal)
+        });
+        match blocker {
+            Some(dep) => warn!(
+                "Service {} has been {:?} for {} frames. It's waiting on dependency {}, which is {:?}.",
+                self.name,
+                self.status,
+                self.stuck_frames,
+                dep.name(),
+                dep.status()
+            ),
+            None => warn!(
+                "Service {} has been {:?} for {} frames. It's waiting on {} task(s).",
+                self.name,
+                self.status,
+                self.stuck_frames,
+                self.tasks.len()
+            ),
+        }
+    }
 }
 
+/// How many frames a service may spend initializing or deinitializing before
+/// a warning is logged, unless set with [ServiceScope::stuck_warning_after].
+pub const DEFAULT_STUCK_WARNING_FRAMES: u32 = 600;
+
 /// Fires when a service is updated. Use this when you only have the service's ID.
 #[derive(Event, Clone, PartialEq, Eq, Hash)]
 pub struct ServiceUpdated {
@@ -370,48 +1292,145 @@ pub struct ServiceUpdated {
     pub new_status: ServiceStatus,
     #[allow(missing_docs)]
     pub id: NodeId,
+    /// The display name of the service.
+    pub name: String,
 }
 impl std::fmt::Debug for ServiceUpdated {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
-            "ServiceUpdated ({:?}) {:?} -> {:?}",
-            self.id, self.old_status, self.new_status
+            "ServiceUpdated ({} {:?}) {:?} -> {:?}",
+            self.name, self.id, self.old_status, self.new_status
         ))
     }
 }
 
+/// Fires for each service which fails because one of its dependencies failed,
+/// e.g. to show how far a failure spread. Use [ServiceFailed] to react to the
+/// failure itself.
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ServiceCascadeFailure {
+    /// The dependency which failed first.
+    pub origin: NodeId,
+    /// The service which failed because of it.
+    pub affected: NodeId,
+}
+
+/// Fires once every registered service has settled, i.e. is up or down with
+/// no lifecycle commands or tasks pending. Fires again if a service goes
+/// through an asynchronous transition and everything settles once more.
+#[derive(Event, Debug, Clone, Default)]
+pub struct AllServicesSettled;
+
+/// Fires alongside [AllServicesSettled] if any of the settled services have
+/// failed. Holds the ids of the failed services.
+#[derive(Event, Debug, Clone)]
+pub struct SomeServicesFailed(pub Vec<NodeId>);
+
+/// Whether every registered service had settled as of the last lifecycle
+/// update. See [AllServicesSettled].
+#[derive(Resource, Deref, Debug, Default)]
+pub struct ServicesSettled(bool);
+
+/// Sends [AllServicesSettled] when the last registered service settles.
+pub(crate) fn watch_settled(
+    cache: Res<GraphDataCache>,
+    mut settled: ResMut<ServicesSettled>,
+    mut all_settled: EventWriter<AllServicesSettled>,
+    mut some_failed: EventWriter<SomeServicesFailed>,
+) {
+    let mut services = cache
+        .values()
+        .filter_map(GraphData::as_service)
+        .filter(|service| service.registered())
+        .peekable();
+    let is_settled = services.peek().is_some()
+        && services.all(|service| {
+            let status = service.status();
+            (status.is_up() || status.is_down())
+                && service.commands.is_empty()
+                && service.tasks.is_empty()
+        });
+    if is_settled && !settled.0 {
+        debug!("All services settled");
+        all_settled.write(AllServicesSettled);
+        let failed = cache
+            .values()
+            .filter_map(GraphData::as_service)
+            .filter(|service| service.registered() && service.status().is_failed())
+            .map(ServiceData::id)
+            .collect::<Vec<_>>();
+        if !failed.is_empty() {
+            some_failed.write(SomeServicesFailed(failed));
+        }
+    }
+    settled.0 = is_settled;
+}
+
 /// Run every pre-update to check on service dependencies and transition state if needed.
 /// SERVICE STATUS SHOULD NOT BE CHANGED FROM OUTSIDE THE SERVICE!
 pub(crate) fn update_async_state<S: Service>(world: &mut World) {
-    let goal = match world.service_mut::<S>().status() {
-        ServiceStatus::Deinit(r) => ServiceStatus::Down(r),
-        ServiceStatus::Init => ServiceStatus::Up,
-        _ => return,
-    };
+    let status = world.service::<S>().status();
+    if !status.is_initializing() && !status.is_deinitializing() {
+        return;
+    }
+    world.service_scope::<S, _>(|world, service| service.update_async_state(world))
+}
 
+/// Warns when the service is stuck transitioning. See
+/// [ServiceScope::stuck_warning_after].
+pub(crate) fn warn_if_stuck<S: Service>(world: &mut World) {
+    if !world.service::<S>().status().is_transitioning() {
+        return;
+    }
     world.service_scope::<S, _>(|world, service| {
-        match service.deps_ok(goal.clone(), world.resource::<GraphDataCache>()) {
-            Ok(true) => {
-                if service.tasks.is_empty() {
-                    service.set_status(goal.clone());
-                }
-            }
-            Err(e) => service.fail(world, e),
-            _ => {}
-        }
+        service.check_stuck(world.resource::<GraphDataCache>())
+    })
+}
+
+/// Runs the lifecycle of every instance of the service `S`. See
+/// [ServiceAppExt::register_service_instance].
+pub(crate) fn update_service_instances<S: Service>(world: &mut World) {
+    let cid = world.resource_id::<S>().unwrap();
+    let instances = world
+        .resource::<GraphDataCache>()
+        .keys()
+        .copied()
+        .filter(|id| matches!(id, NodeId::ServiceInstance(c, _) if *c == cid))
+        .collect::<Vec<_>>();
+    for id in instances {
+        world.service_scope_by_id(id, |world, service| service.update_instance(world));
+    }
+}
+
+/// Run condition for the service's [LifecycleSystems]. Idle services, with no
+/// queued commands, tasks, pending transitions, or unbroadcast status changes,
+/// skip the whole lifecycle chain.
+pub(crate) fn lifecycle_pending<S: Service>(
+    service: ServiceRef<S>,
+    commands: Res<Events<LifecycleCommand<S>>>,
+) -> bool {
+    !commands.is_empty() || service.has_pending_work()
+}
+
+/// Run condition for [update_service_instances]. Skips the update while every
+/// instance of the service is idle. See [lifecycle_pending].
+pub(crate) fn instances_pending<S: Service>(world: &World) -> bool {
+    let Some(cid) = world.resource_id::<S>() else {
+        return false;
+    };
+    world.resource::<GraphDataCache>().iter().any(|(id, data)| {
+        matches!(id, NodeId::ServiceInstance(c, _) if *c == cid)
+            && data.as_service().is_some_and(ServiceData::has_pending_work)
     })
 }
 
 /// Broadcasts events which have been placed in the service's event queue by status updates.
 pub(crate) fn broadcast_new_state<S: Service>(mut service: ServiceMut<S>, mut commands: Commands) {
     for event in service.event_queue.drain(..) {
-        // broadcast event
-        // debug!(
-        //     "({}) Broadcasting status update: {:?} -> {:?}",
-        //     S::name(),
-        //     event.old_status,
-        //     event.new_status
-        // );
+        debug!(
+            "({}) Broadcasting status update: {:?} -> {:?}",
+            event.name, event.old_status, event.new_status
+        );
         commands.send_event(event.clone());
         let ServiceUpdated {
             old_status,
@@ -2,13 +2,16 @@ use std::marker::PhantomData;
 
 use crate::prelude::*;
 use bevy_app::{App, PostStartup, PreUpdate, Startup};
-use bevy_ecs::{component::ComponentId, prelude::*};
+use bevy_ecs::schedule::ScheduleLabel;
+use bevy_ecs::{component::ComponentId, event::EventRegistry, prelude::*};
 use tracing::{debug, warn};
 
 macro_rules! register_parameterized_events {
-    ($app:ident, $($name:ident $(,)?)* ) => {
+    ($world:ident, $service:ident, $($name:ident $(,)?)* ) => {
         $(
-            $app.add_event::<$name<Self>>();
+            if !$world.contains_resource::<Events<$name<$service>>>() {
+                EventRegistry::register_event::<$name<$service>>($world);
+            }
         )*
     }
 }
@@ -16,7 +19,7 @@ macro_rules! register_parameterized_events {
 /// A trait for resources which wrap [ServiceData] instances. You can think of
 /// services as a kind of dynamic plugin which can be spun up or down at
 /// runtime. See the [top-level docs](crate) for more details.
-pub trait Service: Resource + Sized + std::fmt::Debug + Default {
+pub trait Service: Resource + Sized + std::fmt::Debug {
     /// Registers systems and service behavior using a [ServiceScope]. The
     /// services will only run if the system is up. Service dependencies will be
     /// automatically spun up and down with the parent service. Resources and
@@ -30,13 +33,23 @@ pub trait Service: Resource + Sized + std::fmt::Debug + Default {
     /// ```rust
     /// # use q_service::prelude::*;
     /// # use bevy::prelude::*;
-    /// # service!(ExampleService);
-    /// #
-    /// # fn main() {
-    /// # let mut app = App::new();
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct MyDep;
+    /// # impl Service for MyDep {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// # #[derive(Resource, Default)]
+    /// # struct MyResource;
+    /// # #[derive(Asset, TypePath)]
+    /// # struct MyAsset;
     /// # fn sys_a() {}
     /// # fn sys_b() {}
-    /// fn build(scope: ServiceScope<ExampleService>) {
+    /// # #[allow(non_upper_case_globals)]
+    /// # const asset_path: &str = "my/asset.txt";
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// fn build(scope: &mut ServiceScope<ExampleService>) {
     ///     scope.add_dep::<MyDep>();
     ///     scope.add_systems(Update, (sys_a, sys_b).chain());
     ///     scope.add_resource::<MyResource>();
@@ -46,93 +59,39 @@ pub trait Service: Resource + Sized + std::fmt::Debug + Default {
     /// ```
     fn build(scope: &mut ServiceScope<Self>);
 
-    /// Gets the display name for this service.
+    /// Gets the default display name for this service. This can be overridden
+    /// with [ServiceScope::with_name], in which case [ServiceData::name] holds
+    /// the overridden name.
     fn name() -> String {
         name_from_type::<Self>()
     }
 
     /// Creates and instantiates the service wrapper,
     /// inserting it as a resource in the world.
-    #[tracing::instrument(skip_all)]
-    fn register(app: &mut App) {
-        debug!("({}) Registering...", Self::name(),);
-
-        // no dupes
-        if let Some(t) = app.world().get_resource::<Self>()
-            && t.data(app.world()).registered()
-        {
-            warn!("Overriding already registered service {}", Self::name());
-        }
-        register_parameterized_events!(
-            app,
-            // set state
-            LifecycleCommand,
-            // react to state
-            EnterServiceState,
-            ExitServiceState,
-            ServiceStateChange,
-            ServiceInitializing,
-            ServiceDeinitializing,
-            ServiceUp,
-            ServiceDown,
-        );
-        app.add_event::<ServiceUpdated>();
+    ///
+    /// ## Panics
+    ///
+    /// Panics if the service's dependencies are invalid. See
+    /// [Service::try_register].
+    fn register(app: &mut App)
+    where
+        Self: Default,
+    {
+        Self::try_register(app).expect("Dependencies are invalid.");
+    }
 
-        // ensure dependencies
-        app.init_resource::<DependencyGraph>();
-        app.init_resource::<GraphDataCache>();
+    /// Creates and instantiates the service wrapper, inserting it as a resource
+    /// in the world. Returns an error, leaving the service unregistered and
+    /// without adding any of its systems, if its dependencies would form a
+    /// cycle. Registering a service twice does nothing, so a running service
+    /// keeps its state; use [ServiceWorldExt::hot_swap_service] to replace
+    /// its hooks.
+    fn try_register(app: &mut App) -> Result<(), DepInitErr>
+    where
+        Self: Default,
+    {
         app.init_resource::<Self>();
-
-        let id = app.world().resource_id::<Self>().unwrap();
-        let system_set = LifecycleSystems(id);
-        let set = (
-            || debug!("({}) Running PostUpdate Service Lifecycle", Self::name()),
-            watch_service_commands::<Self>,
-            poll_tasks::<Self>,
-            update_dep_status::<Self>,
-            update_async_state::<Self>,
-            broadcast_new_state::<Self>,
-        )
-            .chain()
-            .in_set(system_set);
-        app.add_systems(PreUpdate, set);
-
-        let set = (
-            || debug!("({}) Running PostStartup Service Lifecycle", Self::name()),
-            watch_service_commands::<Self>,
-            poll_tasks::<Self>,
-            update_dep_status::<Self>,
-            update_async_state::<Self>,
-            broadcast_new_state::<Self>,
-        )
-            .chain()
-            .in_set(system_set);
-        app.add_systems(PostStartup, set);
-
-        // make spec
-        let mut scope = ServiceScope::new(app);
-        Self::build(&mut scope);
-        let spec = scope.into_spec();
-
-        // run dep lifecycles in order to keep status propogation stable
-        for dep in spec.deps.iter() {
-            if let NodeId::Service(id) = dep {
-                app.configure_sets(PreUpdate, system_set.after(LifecycleSystems(*id)));
-                app.configure_sets(PostStartup, system_set.after(LifecycleSystems(*id)));
-            }
-        }
-
-        if spec.is_startup {
-            app.add_systems(Startup, move |mut commands: Commands| {
-                commands.spin_service_up::<Self>();
-            });
-        }
-
-        // Instantiate service and cache it
-        // If this already exists it will be overwritten. This is what we want,
-        // when we delcare a service wrapper we're defining the canoncial implementation.
-        ServiceData::register::<Self>(app.world_mut(), spec);
-        debug!("({}) ...Done!", Self::name(),);
+        try_register_service::<Self>(app)
     }
 
     /// Fetches the underlying service data.
@@ -154,6 +113,12 @@ pub trait Service: Resource + Sized + std::fmt::Debug + Default {
     fn system_set() -> ServiceSystems<Self> {
         ServiceSystems::<Self>(PhantomData)
     }
+
+    /// Returns the [SystemSet] for systems which only run while this service
+    /// has the given status. See [ServiceScope::add_systems_when].
+    fn system_set_when(status: ServiceStatus) -> ServiceStatusSystems<Self> {
+        ServiceStatusSystems::<Self>(status, PhantomData)
+    }
 }
 
 /// A [SystemSet] associated to a specific [Service]. Sytems in this set will
@@ -189,7 +154,357 @@ impl<T: Service> std::hash::Hash for ServiceSystems<T> {
     }
 }
 
+/// A [SystemSet] associated to a specific [Service] and [ServiceStatus].
+/// Systems in this set will only run while the service has that status.
+#[derive(SystemSet)]
+pub struct ServiceStatusSystems<T: Service>(ServiceStatus, PhantomData<T>);
+
+impl<T: Service> std::fmt::Debug for ServiceStatusSystems<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("ServiceStatusSystems")
+            .field(&self.0)
+            .field(&self.1)
+            .finish()
+    }
+}
+
+impl<T: Service> Clone for ServiceStatusSystems<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone(), PhantomData)
+    }
+}
+
+impl<T: Service> PartialEq for ServiceStatusSystems<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Service> Eq for ServiceStatusSystems<T> {}
+
+impl<T: Service> std::hash::Hash for ServiceStatusSystems<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
 /// A [SystemSet] associated to a specific [Service]. Sytems in this set will
 /// only run when the service is up.
 #[derive(SystemSet, Debug, Hash, Eq, PartialEq, Clone, Copy)]
 pub struct LifecycleSystems(ComponentId);
+
+/// Broadcasts a specific [Service]'s status changes. Ordered after the sets of
+/// the service's dependencies.
+#[derive(SystemSet, Debug, Hash, Eq, PartialEq, Clone, Copy)]
+pub(crate) struct BroadcastSystems(ComponentId);
+
+/// Crate-wide [SystemSets](SystemSet) which order every service's
+/// [LifecycleSystems] relative to global bookkeeping. Configured in both
+/// [PreUpdate] and [PostStartup].
+#[derive(SystemSet, Debug, Hash, Eq, PartialEq, Clone, Copy)]
+pub enum GlobalLifecycleSystems {
+    /// Runs before any service's lifecycle systems.
+    Before,
+    /// Contains every service's [LifecycleSystems].
+    Services,
+    /// Broadcasts every service's status changes. A service's events are
+    /// always sent after those of its dependencies.
+    Broadcast,
+    /// Runs once every service has updated and broadcast its state.
+    After,
+}
+
+/// Registers an instance of the service `T` under the given key. See
+/// [ServiceAppExt::register_service_instance].
+pub(crate) fn register_instance<T: Service>(app: &mut App, key: String) {
+    debug!("({}[{key}]) Registering instance...", T::name());
+    init_service_globals(app);
+    let cid = app.world_mut().register_resource::<T>();
+    let id = NodeId::service_instance(cid, &key);
+    if app.world().service_by_id(id).is_some() {
+        warn!(
+            "Overriding already registered instance {}[{key}]",
+            T::name()
+        );
+    }
+    let is_first = !app
+        .world()
+        .resource::<GraphDataCache>()
+        .keys()
+        .any(|id| matches!(id, NodeId::ServiceInstance(c, _) if *c == cid));
+
+    let mut scope = ServiceScope::new(app.world_mut());
+    T::build(&mut scope);
+    let (spec, setup) = scope.into_parts();
+    if spec.has_systems || spec.deps.iter().any(|dep| dep.service_id().is_none()) {
+        panic!(
+            "Service {} can't be registered as an instance because it has scoped systems, resources, or assets.",
+            T::name()
+        );
+    }
+    let is_startup = spec.is_startup;
+    ServiceData::register_instance::<T>(app.world_mut(), spec, &key)
+        .expect("Dependencies are invalid.");
+    setup.apply(app.world_mut());
+
+    if is_first {
+        for schedule in [PreUpdate.intern(), PostStartup.intern()] {
+            app.add_systems(
+                schedule,
+                update_service_instances::<T>
+                    .run_if(instances_pending::<T>)
+                    .in_set(GlobalLifecycleSystems::Services),
+            );
+        }
+    }
+    if is_startup {
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands.spin_service_instance_up::<T>(key.clone());
+        });
+    }
+}
+
+/// Registers the service, whose resource may or may not have been inserted
+/// yet. See [Service::try_register].
+pub(crate) fn try_register_service<T: Service>(app: &mut App) -> Result<(), DepInitErr> {
+    init_service_globals(app);
+    try_register_service_in::<T>(app.world_mut())
+}
+
+/// Registers the service, as with [try_register_service], in a world whose
+/// service globals have already been initialized.
+#[tracing::instrument(name = "try_register", skip_all)]
+pub(crate) fn try_register_service_in<T: Service>(world: &mut World) -> Result<(), DepInitErr> {
+    debug!("({}) Registering...", T::name(),);
+
+    // no dupes
+    if world
+        .resource_id::<T>()
+        .and_then(|id| world.service_by_id(NodeId::Service(id)))
+        .is_some_and(ServiceData::registered)
+    {
+        warn!("Service {} is already registered, skipping", T::name());
+        return Ok(());
+    }
+    let id = world.register_resource::<T>();
+
+    // make spec
+    let mut scope = ServiceScope::new(world);
+    T::build(&mut scope);
+    let (spec, setup) = scope.into_parts();
+    let deps = spec.deps.clone();
+    let is_startup = spec.is_startup;
+    let startup_priority = spec.startup_priority;
+
+    // Instantiate service and cache it. Nothing is added to the world until
+    // the deps are known to be valid, so a rejected service leaves nothing
+    // behind.
+    ServiceData::register::<T>(world, spec)?;
+    // a deregistered service's systems are still in the schedules, and run
+    // again once it's back
+    let node = NodeId::Service(id);
+    let reregistering = world.resource_mut::<DeregisteredServices>().remove(&node);
+    match reregistering {
+        true => setup.without_systems().apply(world),
+        false => setup.apply(world),
+    }
+    let name = world.service::<T>().name().to_string();
+    world.resource_mut::<ServiceRegistry>().push(node, name);
+
+    register_parameterized_events!(
+        world,
+        T,
+        // set state
+        LifecycleCommand,
+        // react to state
+        EnterServiceState,
+        ExitServiceState,
+        ServiceStateChange,
+        ServiceInitializing,
+        ServiceDeinitializing,
+        ServiceUp,
+        ServiceDown,
+    );
+    if !reregistering {
+        add_lifecycle_systems::<T>(world, id);
+    }
+
+    // run dep lifecycles in order to keep status propogation stable
+    let system_set = LifecycleSystems(id);
+    let broadcast_set = BroadcastSystems(id);
+    let mut schedules = world.resource_mut::<Schedules>();
+    for dep in deps.iter() {
+        if let NodeId::Service(id) = dep {
+            for schedule in [PreUpdate.intern(), PostStartup.intern()] {
+                schedules.configure_sets(schedule, system_set.after(LifecycleSystems(*id)));
+                // dependencies broadcast their status changes first
+                schedules.configure_sets(schedule, broadcast_set.after(BroadcastSystems(*id)));
+            }
+        }
+    }
+
+    if let Some(priority) = startup_priority {
+        add_prioritized_startup::<T>(world, id, priority);
+    } else if is_startup {
+        schedules.add_systems(Startup, move |mut commands: Commands| {
+            commands.spin_service_up::<T>();
+        });
+    }
+
+    debug!("({}) ...Done!", world.service::<T>().name());
+    Ok(())
+}
+
+/// Adds the lifecycle systems which drive the service `T`, gated by
+/// [lifecycle_pending].
+fn add_lifecycle_systems<T: Service>(world: &mut World, id: ComponentId) {
+    let system_set = LifecycleSystems(id);
+    let broadcast_set = BroadcastSystems(id);
+    let mut schedules = world.resource_mut::<Schedules>();
+    schedules.configure_sets(
+        PreUpdate,
+        system_set
+            .in_set(GlobalLifecycleSystems::Services)
+            .run_if(lifecycle_pending::<T>),
+    );
+    schedules.configure_sets(
+        PostStartup,
+        system_set
+            .in_set(GlobalLifecycleSystems::Services)
+            .run_if(lifecycle_pending::<T>),
+    );
+    let set = (
+        || debug!("({}) Running PostUpdate Service Lifecycle", T::name()),
+        watch_service_commands::<T>,
+        update_init_progress::<T>,
+        poll_tasks::<T>,
+        update_dep_status::<T>,
+        update_async_state::<T>,
+        warn_if_stuck::<T>,
+    )
+        .chain()
+        .in_set(system_set);
+    schedules.add_systems(PreUpdate, set);
+
+    let set = (
+        || debug!("({}) Running PostStartup Service Lifecycle", T::name()),
+        watch_service_commands::<T>,
+        update_init_progress::<T>,
+        poll_tasks::<T>,
+        update_dep_status::<T>,
+        update_async_state::<T>,
+    )
+        .chain()
+        .in_set(system_set);
+    schedules.add_systems(PostStartup, set);
+
+    for schedule in [PreUpdate.intern(), PostStartup.intern()] {
+        schedules.configure_sets(
+            schedule,
+            broadcast_set
+                .in_set(GlobalLifecycleSystems::Broadcast)
+                .run_if(lifecycle_pending::<T>),
+        );
+        schedules.add_systems(schedule, broadcast_new_state::<T>.in_set(broadcast_set));
+    }
+}
+
+/// The startup priorities of the services registered so far. See
+/// [ServiceScope::startup_priority].
+#[derive(Resource, Default)]
+struct StartupPriorities(Vec<(ComponentId, i32)>);
+
+/// Holds the system which spins up a prioritized startup service.
+#[derive(SystemSet, Debug, Hash, Eq, PartialEq, Clone, Copy)]
+struct StartupSpinUp(ComponentId);
+
+/// Spins the service up in [Startup], ordered against each other prioritized
+/// service. The spin-up is queued as a command rather than sent as a
+/// [LifecycleCommand], so the commands run in the order of their systems.
+/// Nothing else orders these systems, so the priorities can't form a cycle.
+fn add_prioritized_startup<T: Service>(world: &mut World, id: ComponentId, priority: i32) {
+    let mut priorities = world.get_resource_or_init::<StartupPriorities>();
+    let orderings = priorities
+        .0
+        .iter()
+        .filter(|(_, other_priority)| *other_priority != priority)
+        .map(|(other, other_priority)| match priority < *other_priority {
+            true => (id, *other),
+            false => (*other, id),
+        })
+        .collect::<Vec<_>>();
+    priorities.0.push((id, priority));
+
+    let mut schedules = world.resource_mut::<Schedules>();
+    schedules.add_systems(
+        Startup,
+        (|mut commands: Commands| {
+            commands.queue(|world: &mut World| {
+                world.service_scope::<T, ()>(|world, service| {
+                    // a prioritized dependent may have brought it up already
+                    if service.status().is_down() {
+                        service.spin_up(world);
+                    }
+                });
+            });
+        })
+        .in_set(StartupSpinUp(id)),
+    );
+    for (first, second) in orderings {
+        schedules.configure_sets(Startup, StartupSpinUp(first).before(StartupSpinUp(second)));
+    }
+}
+
+/// Initializes the resources, events, and system sets shared by all services.
+/// Does nothing if they have already been initialized.
+pub(crate) fn init_service_globals(app: &mut App) {
+    if app.world().contains_resource::<GraphDataCache>() {
+        return;
+    }
+    app.init_resource::<DependencyGraph>()
+        .init_resource::<GraphDataCache>()
+        .init_resource::<ServiceGroups>()
+        .init_resource::<ServiceContexts>()
+        .init_resource::<ServiceRegistry>()
+        .init_resource::<DeregisteredServices>()
+        .init_resource::<RejectedDeps>()
+        .init_resource::<ServicesSettled>()
+        .init_resource::<ServiceReadyNotifiers>()
+        .add_event::<ServiceUpdated>()
+        .add_event::<ServiceCascadeFailure>()
+        .add_event::<AllServicesSettled>()
+        .add_event::<SomeServicesFailed>()
+        .add_event::<BatchLifecycleCommand>()
+        .add_plugins(DepValidationPlugin)
+        .add_systems(
+            PostStartup,
+            warn_unregistered_deps.in_set(GlobalLifecycleSystems::Before),
+        );
+    configure_global_sets(app, PreUpdate);
+    configure_global_sets(app, PostStartup);
+}
+
+fn configure_global_sets(app: &mut App, schedule: impl ScheduleLabel) {
+    app.configure_sets(
+        schedule.intern(),
+        (
+            GlobalLifecycleSystems::Before,
+            GlobalLifecycleSystems::Services,
+            GlobalLifecycleSystems::Broadcast,
+            GlobalLifecycleSystems::After,
+        )
+            .chain(),
+    );
+    app.add_systems(
+        schedule.intern(),
+        watch_batch_commands.in_set(GlobalLifecycleSystems::Before),
+    );
+    app.add_systems(
+        schedule,
+        (
+            crate::service_data::watch_settled,
+            crate::ready::notify_ready,
+        )
+            .in_set(GlobalLifecycleSystems::After),
+    );
+}
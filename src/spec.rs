@@ -1,5 +1,8 @@
 use crate::prelude::*;
+use bevy_ecs::system::System;
 use bevy_platform::prelude::*;
+use core::time::Duration;
+use std::collections::BTreeSet;
 
 #[derive(Debug)]
 pub(crate) struct ServiceSpec<T: Service> {
@@ -8,7 +11,26 @@ pub(crate) struct ServiceSpec<T: Service> {
     pub on_deinit: Option<DeinitHook<T>>,
     pub on_up: Option<UpHook<T>>,
     pub on_down: Option<DownHook<T>>,
+    pub on_transition: Option<TransitionHook<T>>,
+    pub tags: BTreeSet<String>,
+    /// Overrides [Service::name] when set.
+    pub name: Option<String>,
+    pub stuck_warning_after: Option<u32>,
+    /// See [ServiceScope::task_poll_interval].
+    pub task_poll_interval: Option<Duration>,
+    /// See [ServiceScope::stop_deps_on_failure].
+    pub stop_deps_on_failure: bool,
+    /// See [ServiceScope::catch_hook_panics].
+    pub catch_hook_panics: bool,
+    /// Conditions for deps added with [ServiceScope::add_dep_if].
+    pub dep_conditions: Vec<(NodeId, Box<dyn System<In = (), Out = bool>>)>,
+    /// See [ServiceScope::can_spin_down].
+    pub can_spin_down: Option<Box<dyn System<In = (), Out = bool>>>,
     pub is_startup: bool,
+    /// Orders this service's startup spin-up. See [ServiceScope::startup_priority].
+    pub startup_priority: Option<i32>,
+    /// Whether systems were added through the scope.
+    pub has_systems: bool,
 }
 
 impl<T> Default for ServiceSpec<T>
@@ -22,7 +44,18 @@ where
             on_deinit: None,
             on_up: None,
             on_down: None,
+            on_transition: None,
+            tags: BTreeSet::new(),
+            name: None,
+            stuck_warning_after: None,
+            task_poll_interval: None,
+            stop_deps_on_failure: false,
+            catch_hook_panics: false,
+            dep_conditions: vec![],
+            can_spin_down: None,
             is_startup: false,
+            startup_priority: None,
+            has_systems: false,
         }
     }
 }
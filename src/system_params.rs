@@ -3,7 +3,7 @@ use std::marker::PhantomData;
 use bevy_derive::{Deref, DerefMut};
 use bevy_ecs::{
     component::Tick,
-    system::{ReadOnlySystemParam, SystemMeta, SystemParam},
+    system::{ReadOnlySystemParam, Res, SystemMeta, SystemParam, SystemParamValidationError},
     world::{Mut, World, unsafe_world_cell::UnsafeWorldCell},
 };
 
@@ -14,9 +14,39 @@ use crate::prelude::*;
 pub struct ServiceRef<'a, T: Service> {
     #[deref]
     service: &'a ServiceData,
+    /// The change tick of the running system.
+    this_run: Tick,
     _handle: PhantomData<T>,
 }
 
+impl<T: Service> ServiceRef<'_, T> {
+    /// Has the service changed status since the given tick, e.g. a
+    /// [ServiceData::last_status_change] stored by the system on a previous
+    /// run? Useful for invalidating data cached per service.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::{ecs::component::Tick, prelude::*};
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct Database;
+    /// # impl Service for Database {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// fn refresh(service: ServiceRef<Database>, mut seen: Local<Tick>) {
+    ///     if service.changed_since(*seen) {
+    ///         *seen = service.last_status_change();
+    ///         // rebuild cached data...
+    ///     }
+    /// }
+    /// ```
+    pub fn changed_since(&self, tick: Tick) -> bool {
+        self.service
+            .last_status_change()
+            .is_newer_than(tick, self.this_run)
+    }
+}
+
 unsafe impl<'a, T: Service> SystemParam for ServiceRef<'a, T> {
     type State = ();
 
@@ -24,16 +54,25 @@ unsafe impl<'a, T: Service> SystemParam for ServiceRef<'a, T> {
 
     fn init_state(_: &mut World, _: &mut SystemMeta) -> Self::State {}
 
+    unsafe fn validate_param(
+        _: &Self::State,
+        _: &SystemMeta,
+        world: UnsafeWorldCell,
+    ) -> Result<(), SystemParamValidationError> {
+        validate_service::<T, Self>(unsafe { world.world() })
+    }
+
     unsafe fn get_param<'world, 'state>(
         _: &'state mut Self::State,
         _: &SystemMeta,
         world: UnsafeWorldCell<'world>,
-        _: Tick,
+        this_run: Tick,
     ) -> Self::Item<'world, 'state> {
         let world = unsafe { world.world() };
         let service = world.service::<T>();
         Self::Item {
             service,
+            this_run,
             _handle: PhantomData,
         }
     }
@@ -55,6 +94,14 @@ unsafe impl<'a, T: Service> SystemParam for ServiceMut<'a, T> {
 
     fn init_state(_: &mut World, _: &mut SystemMeta) -> Self::State {}
 
+    unsafe fn validate_param(
+        _: &Self::State,
+        _: &SystemMeta,
+        world: UnsafeWorldCell,
+    ) -> Result<(), SystemParamValidationError> {
+        validate_service::<T, Self>(unsafe { world.world() })
+    }
+
     unsafe fn get_param<'world, 'state>(
         _: &'state mut Self::State,
         _: &SystemMeta,
@@ -69,3 +116,79 @@ unsafe impl<'a, T: Service> SystemParam for ServiceMut<'a, T> {
         }
     }
 }
+
+/// Systems which access a deregistered service are skipped, as they can't be
+/// removed from their schedules. See [ServiceWorldExt::deregister_service].
+fn validate_service<T: Service, P>(world: &World) -> Result<(), SystemParamValidationError> {
+    let id = world.resource_id::<T>().map(NodeId::Service);
+    if id.and_then(|id| world.service_by_id(id)).is_some() {
+        return Ok(());
+    }
+    let deregistered = world.get_resource::<DeregisteredServices>();
+    if id.is_some_and(|id| deregistered.is_some_and(|services| services.contains(&id))) {
+        return Err(SystemParamValidationError::skipped::<P>(format!(
+            "Service {} was deregistered",
+            T::name()
+        )));
+    }
+    Err(SystemParamValidationError::invalid::<P>(format!(
+        "Service {} is not registered",
+        T::name()
+    )))
+}
+
+/// SystemParam for read-only access to services by their [NodeId], for when
+/// the service's type isn't known at compile time.
+#[derive(SystemParam)]
+pub struct ServiceById<'w> {
+    cache: Res<'w, GraphDataCache>,
+}
+
+impl ServiceById<'_> {
+    /// Gets the service with the given id, if it exists.
+    pub fn get(&self, id: NodeId) -> Option<&ServiceData> {
+        self.cache.get_service(id)
+    }
+}
+
+/// Read-only SystemParam bundling a service with the statuses of its
+/// dependencies, e.g. for UI systems which display both.
+#[derive(SystemParam)]
+pub struct ServiceInfo<'w, T: Service> {
+    service: ServiceRef<'w, T>,
+    cache: Res<'w, GraphDataCache>,
+}
+
+impl<T: Service> ServiceInfo<'_, T> {
+    /// Gets the service's status. See [ServiceData::status].
+    pub fn status(&self) -> ServiceStatus {
+        self.service.status()
+    }
+
+    /// Gets the service's name. See [ServiceData::name].
+    pub fn name(&self) -> &str {
+        self.service.name()
+    }
+
+    /// Gets the service's dependencies. See [ServiceData::deps].
+    pub fn deps(&self) -> &[NodeId] {
+        self.service.deps()
+    }
+
+    /// Gets the number of async tasks the service is waiting on. See
+    /// [ServiceData::pending_tasks].
+    pub fn pending_tasks(&self) -> usize {
+        self.service.pending_tasks()
+    }
+
+    /// Gets the id, name, and status of each of the service's dependencies.
+    /// See [ServiceData::deps_status].
+    pub fn deps_status(&self) -> Vec<(NodeId, String, ServiceStatus)> {
+        self.service.deps_status(&self.cache)
+    }
+
+    /// Gets the underlying [ServiceData], for anything not exposed here.
+    pub fn data(&self) -> &ServiceData {
+        &self.service
+    }
+}
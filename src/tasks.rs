@@ -1,35 +1,106 @@
 use crate::prelude::*;
-use bevy_ecs::{prelude::*, world::CommandQueue};
+use bevy_ecs::{prelude::*, system::BoxedSystem, world::CommandQueue};
 use bevy_tasks::{Task, futures_lite::future, prelude::*};
-use tracing::{debug, warn};
+use std::sync::{
+    Mutex, PoisonError,
+    mpsc::{Receiver, Sender, channel},
+};
 
 /// A wrapper around a [bevy_tasks::Task] which can be returned
-/// from the on_init or on_deinit hooks.
+/// from the on_init or on_deinit hooks. Once the task succeeds, the commands
+/// it pushed to its [CommandQueue] are applied to the world, followed by the
+/// systems added with [AsyncHook::on_complete].
 #[derive(Component)]
-pub struct AsyncHook(pub Task<TaskResult>);
+pub struct AsyncHook {
+    task: Task<TaskResult>,
+    progress: Option<TaskProgress>,
+    on_complete: Vec<BoxedSystem>,
+    pool: TaskPoolKind,
+}
+
+type TaskResult = Result<CommandQueue, BevyError>;
+
+impl From<Task<TaskResult>> for AsyncHook {
+    fn from(task: Task<TaskResult>) -> Self {
+        AsyncHook::new(task)
+    }
+}
+
+/// The task pool an [AsyncHook] runs on. See [AsyncHook::pool].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskPoolKind {
+    /// The [IoTaskPool]. See [AsyncHook::io].
+    Io,
+    /// The [ComputeTaskPool], whose work must finish within the frame. See
+    /// [AsyncHook::compute].
+    Compute,
+    /// The [AsyncComputeTaskPool]. See [AsyncHook::async_compute] and
+    /// [AsyncHook::progress_task].
+    AsyncCompute,
+    /// A task spawned elsewhere and wrapped with [AsyncHook::new].
+    Other,
+}
+
+/// Reports the progress of a task created with [AsyncHook::progress_task].
+#[derive(Debug, Clone)]
+pub struct ProgressSender(Sender<f32>);
+impl ProgressSender {
+    /// Reports the task's progress, from 0.0 to 1.0. Values outside that range
+    /// are clamped.
+    pub fn send(&self, progress: f32) {
+        // the receiver is gone once the task's entity is despawned
+        let _ = self.0.send(progress.clamp(0.0, 1.0));
+    }
+}
 
-type TaskResult = Result<(), BevyError>;
+/// The receiving end of a task's [ProgressSender].
+struct TaskProgress {
+    receiver: Mutex<Receiver<f32>>,
+    latest: Option<f32>,
+}
 
 // TODO: Trigger an event instead of polling every frame?
 impl AsyncHook {
+    /// Wraps a task spawned elsewhere. Once it succeeds, the commands in the
+    /// [CommandQueue] it returns are applied to the world. Its pool is
+    /// [TaskPoolKind::Other].
+    pub fn new(task: Task<Result<CommandQueue, BevyError>>) -> Self {
+        Self::with_pool(task, TaskPoolKind::Other)
+    }
+
+    fn with_pool(task: Task<TaskResult>, pool: TaskPoolKind) -> Self {
+        AsyncHook {
+            task,
+            progress: None,
+            on_complete: vec![],
+            pool,
+        }
+    }
+
     /// Create an IO-bound task. Takes an async lambda as parameter. Uses the
     /// [IoTaskPool] as its backing executor. See those docs for usage info.
     ///
     /// ## Example usage
     /// ```
+    /// # use q_service::prelude::*;
+    /// # use bevy::ecs::world::CommandQueue;
     /// fn my_init() -> InitResult {
-    ///     let task = AsyncHook::io_task(async |q: CommandQueue| {
+    ///     let task = AsyncHook::io(async |q: &mut CommandQueue| {
     ///         // ...
-    ///     })
+    ///         Ok(())
+    ///     });
     ///     Ok(Some(task))
     /// }
     /// ```
-    pub fn io_task(mut f: impl AsyncFnMut(CommandQueue) -> TaskResult + 'static) -> Self {
+    pub fn io(
+        mut f: impl AsyncFnMut(&mut CommandQueue) -> Result<(), BevyError> + 'static,
+    ) -> Self {
         let task = IoTaskPool::get().spawn_local(async move {
-            let q = CommandQueue::default();
-            (f)(q).await
+            let mut q = CommandQueue::default();
+            (f)(&mut q).await?;
+            Ok(q)
         });
-        AsyncHook(task)
+        AsyncHook::with_pool(task, TaskPoolKind::Io)
     }
     /// Create an IO-bound task. Takes an async lambda as parameter. Uses the
     /// [ComputeTaskPool] as its backing executor. Note that this work must be
@@ -37,19 +108,25 @@ impl AsyncHook {
     ///
     /// ## Example usage
     /// ```
+    /// # use q_service::prelude::*;
+    /// # use bevy::ecs::world::CommandQueue;
     /// fn my_init() -> InitResult {
-    ///     let task = AsyncHook::compute_task(async |q: CommandQueue| {
+    ///     let task = AsyncHook::compute(async |q: &mut CommandQueue| {
     ///         // ...
-    ///     })
+    ///         Ok(())
+    ///     });
     ///     Ok(Some(task))
     /// }
     /// ```
-    pub fn compute_task(mut f: impl AsyncFnMut(CommandQueue) -> TaskResult + 'static) -> Self {
+    pub fn compute(
+        mut f: impl AsyncFnMut(&mut CommandQueue) -> Result<(), BevyError> + 'static,
+    ) -> Self {
         let task = ComputeTaskPool::get().spawn_local(async move {
-            let q = CommandQueue::default();
-            (f)(q).await
+            let mut q = CommandQueue::default();
+            (f)(&mut q).await?;
+            Ok(q)
         });
-        AsyncHook(task)
+        AsyncHook::with_pool(task, TaskPoolKind::Compute)
     }
     /// Create a compute-bound task with [AsyncComputeTaskPool] as its backing
     /// executor. Takes an async lambda as parameter. This work can span
@@ -57,59 +134,202 @@ impl AsyncHook {
     ///
     /// ## Example usage
     /// ```
+    /// # use q_service::prelude::*;
+    /// # use bevy::ecs::world::CommandQueue;
     /// fn my_init() -> InitResult {
-    ///     let task = AsyncHook::async_compute_task(async |q: CommandQueue| {
+    ///     let task = AsyncHook::async_compute(async |q: &mut CommandQueue| {
     ///         // ...
-    ///     })
+    ///         Ok(())
+    ///     });
     ///     Ok(Some(task))
     /// }
     /// ```
+    pub fn async_compute(
+        mut f: impl AsyncFnMut(&mut CommandQueue) -> Result<(), BevyError> + 'static,
+    ) -> Self {
+        let task = AsyncComputeTaskPool::get().spawn_local(async move {
+            let mut q = CommandQueue::default();
+            (f)(&mut q).await?;
+            Ok(q)
+        });
+        AsyncHook::with_pool(task, TaskPoolKind::AsyncCompute)
+    }
+    /// Create an IO-bound task on the [IoTaskPool]. The lambda owns its
+    /// [CommandQueue], so commands pushed to it are never applied.
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `AsyncHook::io`, which applies the task's `CommandQueue` once it succeeds"
+    )]
+    pub fn io_task(
+        mut f: impl AsyncFnMut(CommandQueue) -> Result<(), BevyError> + 'static,
+    ) -> Self {
+        let task = IoTaskPool::get().spawn_local(async move {
+            (f)(CommandQueue::default()).await?;
+            Ok(CommandQueue::default())
+        });
+        AsyncHook::with_pool(task, TaskPoolKind::Io)
+    }
+    /// Create a task on the [ComputeTaskPool]. The lambda owns its
+    /// [CommandQueue], so commands pushed to it are never applied.
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `AsyncHook::compute`, which applies the task's `CommandQueue` once it succeeds"
+    )]
+    pub fn compute_task(
+        mut f: impl AsyncFnMut(CommandQueue) -> Result<(), BevyError> + 'static,
+    ) -> Self {
+        let task = ComputeTaskPool::get().spawn_local(async move {
+            (f)(CommandQueue::default()).await?;
+            Ok(CommandQueue::default())
+        });
+        AsyncHook::with_pool(task, TaskPoolKind::Compute)
+    }
+    /// Create a task on the [AsyncComputeTaskPool]. The lambda owns its
+    /// [CommandQueue], so commands pushed to it are never applied.
+    #[deprecated(
+        since = "0.3.0",
+        note = "use `AsyncHook::async_compute`, which applies the task's `CommandQueue` once it succeeds"
+    )]
     pub fn async_compute_task(
-        mut f: impl AsyncFnMut(CommandQueue) -> TaskResult + 'static,
+        mut f: impl AsyncFnMut(CommandQueue) -> Result<(), BevyError> + 'static,
     ) -> Self {
         let task = AsyncComputeTaskPool::get().spawn_local(async move {
-            let q = CommandQueue::default();
-            (f)(q).await
+            (f)(CommandQueue::default()).await?;
+            Ok(CommandQueue::default())
         });
-        AsyncHook(task)
+        AsyncHook::with_pool(task, TaskPoolKind::AsyncCompute)
+    }
+    /// Create a compute-bound task which reports its progress, e.g. to drive a
+    /// loading bar. Uses the [AsyncComputeTaskPool] as its backing executor.
+    /// While the service is initializing, the latest progress is available
+    /// through [ServiceData::init_progress].
+    ///
+    /// ## Example usage
+    /// ```
+    /// # use q_service::prelude::*;
+    /// # use bevy::ecs::world::CommandQueue;
+    /// fn my_init() -> InitResult {
+    ///     let task = AsyncHook::progress_task(async |q: &mut CommandQueue, progress: ProgressSender| {
+    ///         for i in 0..10 {
+    ///             // ...
+    ///             progress.send(i as f32 / 10.0);
+    ///         }
+    ///         Ok(())
+    ///     });
+    ///     Ok(Some(task))
+    /// }
+    /// ```
+    pub fn progress_task(
+        mut f: impl AsyncFnMut(&mut CommandQueue, ProgressSender) -> Result<(), BevyError> + 'static,
+    ) -> Self {
+        let (sender, receiver) = channel();
+        let task = AsyncComputeTaskPool::get().spawn_local(async move {
+            let mut q = CommandQueue::default();
+            (f)(&mut q, ProgressSender(sender)).await?;
+            Ok(q)
+        });
+        let progress = TaskProgress {
+            receiver: Mutex::new(receiver),
+            latest: None,
+        };
+        AsyncHook {
+            progress: Some(progress),
+            ..AsyncHook::with_pool(task, TaskPoolKind::AsyncCompute)
+        }
+    }
+
+    /// Runs the system once the task succeeds, after the commands it pushed
+    /// to its [CommandQueue] have been applied. Systems run in the order they
+    /// were added, and don't run if the task fails.
+    ///
+    /// ## Example usage
+    /// ```
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # use bevy::ecs::world::CommandQueue;
+    /// #[derive(Resource)]
+    /// struct Loaded(u32);
+    /// fn my_init() -> InitResult {
+    ///     let task = AsyncHook::async_compute(async |q: &mut CommandQueue| {
+    ///         q.push(|world: &mut World| world.insert_resource(Loaded(42)));
+    ///         Ok(())
+    ///     })
+    ///     .on_complete(|loaded: Res<Loaded>| info!("Loaded {}", loaded.0));
+    ///     Ok(Some(task))
+    /// }
+    /// ```
+    pub fn on_complete<M>(mut self, system: impl IntoSystem<(), (), M>) -> Self {
+        self.on_complete
+            .push(Box::new(IntoSystem::into_system(system)));
+        self
+    }
+
+    /// Gets the task pool the task runs on.
+    pub fn pool(&self) -> TaskPoolKind {
+        self.pool
+    }
+
+    /// Polls the task once, returning `None` while it's still running. Once it
+    /// succeeds, the returned queue holds its commands followed by its
+    /// [AsyncHook::on_complete] systems.
+    pub(crate) fn poll(&mut self) -> Option<Result<CommandQueue, ServiceError>> {
+        let mut queue = match block_on(future::poll_once(&mut self.task))? {
+            Ok(queue) => queue,
+            Err(e) => return Some(Err(e.into())),
+        };
+        let on_complete = std::mem::take(&mut self.on_complete);
+        if !on_complete.is_empty() {
+            queue.push(move |world: &mut World| {
+                for mut system in on_complete {
+                    system.initialize(world);
+                    system.run((), world);
+                }
+            });
+        }
+        Some(Ok(queue))
+    }
+
+    /// Gets the latest progress reported by the task, if it reports progress.
+    fn progress(&mut self) -> Option<f32> {
+        let progress = self.progress.as_mut()?;
+        let receiver = progress
+            .receiver
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(latest) = receiver.try_iter().last() {
+            progress.latest = Some(latest);
+        }
+        progress.latest
     }
 }
 
-/// Poll tasks. This happens on PreUpdate.
-pub(crate) fn poll_tasks<T: Service>(
+/// Reads the progress reported by the service's tasks while it initializes.
+/// The service's progress is the average over its tasks which report it.
+pub(crate) fn update_init_progress<T: Service>(
     mut service: ServiceMut<T>,
-    mut commands: Commands,
     mut q_tasks: Query<&mut AsyncHook>,
 ) {
-    let tasks = std::mem::take(&mut service.tasks);
-    let id = service.id();
-    let status = service.status();
-    if !status.is_initializing() && !status.is_deinitializing() && !tasks.is_empty() {
-        warn!(
-            "Non-empty task queue for service {} despite having status {status:?}",
-            T::name()
-        );
-    }
-    service.tasks = tasks
-        .into_iter()
-        .filter(|entity| {
-            let mut task = q_tasks.get_mut(*entity).unwrap();
-            let poll_res = block_on(future::poll_once(&mut task.0));
-            let keep = poll_res.is_none();
-            if let Some(res) = poll_res {
-                match res {
-                    Ok(_) => {
-                        debug!("Finished task");
-                        commands.entity(*entity).despawn();
-                    }
-                    Err(e) => commands.queue(move |world: &mut World| {
-                        world.service_scope_by_id(id, |world, service| {
-                            service.fail(world, ServiceError::Own(e.to_string()));
-                        });
-                    }),
-                }
-            }
-            keep
-        })
-        .collect();
+    if !service.status().is_initializing() {
+        return;
+    }
+    let reported = service
+        .tasks
+        .iter()
+        .filter_map(|entity| q_tasks.get_mut(*entity).ok()?.progress())
+        .collect::<Vec<_>>();
+    if reported.is_empty() {
+        return;
+    }
+    let progress = reported.iter().sum::<f32>() / reported.len() as f32;
+    if service.init_progress() != Some(progress) {
+        service.set_init_progress(progress);
+    }
+}
+
+/// Poll tasks. This happens on PreUpdate.
+pub(crate) fn poll_tasks<T: Service>(world: &mut World) {
+    if world.service::<T>().tasks.is_empty() {
+        return;
+    }
+    world.service_scope::<T, _>(|world, service| service.poll_tasks(world));
 }
@@ -0,0 +1,49 @@
+use crate::prelude::*;
+use bevy_app::App;
+use bevy_platform::time::Instant;
+use core::time::Duration;
+
+/// Test helpers for [App].
+pub trait ServiceTestingExt {
+    /// Spins the service up and updates the app until the service is
+    /// [up](ServiceStatus::Up) or has [failed](ServiceStatus::is_failed), or
+    /// until `timeout` has passed. Returns the service's final status.
+    ///
+    /// The service won't be spun up again if it's already initializing, so
+    /// this can also be used to wait for a startup service. Call it after the
+    /// first [App::update], once startup services have begun initializing.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # use core::time::Duration;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// let mut app = App::new();
+    /// app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+    ///     .register_service::<ExampleService>();
+    /// let status = app.spin_service_up_blocking::<ExampleService>(Duration::from_secs(1));
+    /// assert_eq!(status, ServiceStatus::Up);
+    /// ```
+    fn spin_service_up_blocking<T: Service>(&mut self, timeout: Duration) -> ServiceStatus;
+}
+impl ServiceTestingExt for App {
+    fn spin_service_up_blocking<T: Service>(&mut self, timeout: Duration) -> ServiceStatus {
+        let status = self.world().service::<T>().status();
+        if !status.is_up() && !status.is_initializing() {
+            self.world_mut().commands().spin_service_up::<T>();
+        }
+        let start = Instant::now();
+        loop {
+            self.update();
+            let status = self.world().service::<T>().status();
+            if status.is_up() || status.is_failed() || start.elapsed() >= timeout {
+                return status;
+            }
+        }
+    }
+}
@@ -1,6 +1,23 @@
-use bevy_ecs::world::{Mut, World};
+use bevy_asset::{AssetServer, UntypedAssetId};
+use bevy_ecs::{
+    event::{EventCursor, Events},
+    resource::Resource,
+    system::RunSystemOnce,
+    world::{Mut, World},
+};
+use bevy_platform::{
+    collections::{HashMap, HashSet},
+    prelude::*,
+};
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+use tracing::{debug, warn};
 
-use crate::prelude::*;
+use crate::{
+    deps::{GraphData, ReloadAsset},
+    lifecycle::commands::settle_deinit,
+    prelude::*,
+    service_data::broadcast_new_state,
+};
 
 /// Extension trait for the World.
 pub trait ServiceWorldExt {
@@ -21,6 +38,10 @@ pub trait ServiceWorldExt {
     /// Mutably gets a service by its handle if it exists.
     fn get_service_mut<'w, T: Service>(&'w mut self) -> Option<Mut<'w, ServiceData>>;
 
+    /// Gets an instance of a service by its key. See
+    /// [ServiceAppExt::register_service_instance].
+    fn service_instance<T: Service>(&self, key: &str) -> Option<&ServiceData>;
+
     /// Gets a service by its ID.
     fn service_by_id(&self, id: NodeId) -> Option<&ServiceData>;
 
@@ -29,7 +50,8 @@ pub trait ServiceWorldExt {
 
     /// Temporarily removes a service from the [GraphDataCache] in order to perform operations on it.
     /// # Panics
-    /// Will panic if the service has not been registered.
+    /// Will panic if the service has not been registered, or if the service is
+    /// already being scoped, e.g. when called from one of its own hooks.
     fn service_scope<T: Service, R>(
         &mut self,
         scope: impl FnMut(&mut Self, &mut ServiceData) -> R,
@@ -40,6 +62,137 @@ pub trait ServiceWorldExt {
         id: NodeId,
         scope: impl FnMut(&mut Self, &mut ServiceData) -> R,
     ) -> R;
+
+    /// Gets the id of the registered service with the given [ServiceKey] in
+    /// this world, e.g. to restore statuses saved in a previous run.
+    fn key_to_node_id(&self, key: ServiceKey) -> Option<NodeId>;
+
+    /// Gets the ids of all registered services with the given status.
+    fn services_with_status(&self, status: ServiceStatus) -> Vec<NodeId>;
+    /// Gets the ids of all registered services with the given tag. See
+    /// [ServiceScope::with_tag].
+    fn services_with_tag(&self, tag: &str) -> Vec<NodeId>;
+    /// Gets the ids of all registered services in the order of
+    /// [DependencyGraph::topsort], i.e. dependents before their dependencies.
+    /// Resources and assets are skipped.
+    fn services_topo(&self) -> Vec<NodeId>;
+    /// Gets every registered service in registration order.
+    ///
+    /// # Panics
+    /// Panics if no service has been registered.
+    fn registry(&self) -> &ServiceRegistry;
+    /// Counts the registered services in each status.
+    fn count_services_by_status(&self) -> HashMap<ServiceStatus, usize>;
+
+    /// Gets the status of every registered service, e.g. to save them. See
+    /// [ServiceWorldExt::restore_statuses].
+    fn snapshot_statuses(&self) -> HashMap<NodeId, ServiceStatus>;
+    /// Drives each service in the snapshot toward its stored status. Services
+    /// are brought down first, dependents before their dependencies, then
+    /// brought up, dependencies first. Failed services are failed again with
+    /// the stored error. Transitions which take more than a frame finish as
+    /// usual.
+    ///
+    /// Deinitializing services are restored as down, and initializing ones as
+    /// up. A service can't be made uninitialized again, so it's spun down
+    /// instead. Services missing from the snapshot are left alone.
+    fn restore_statuses(&mut self, snapshot: HashMap<NodeId, ServiceStatus>);
+
+    /// Are all registered services live? See [ServiceData::is_live].
+    fn registry_live(&self) -> bool;
+    /// Are all registered services ready? See [ServiceData::is_ready].
+    fn registry_ready(&self) -> bool;
+
+    /// Gets the first direct dependency of the service which isn't up, as
+    /// returned by [ServiceData::deps_status]. Returns `None` if every
+    /// dependency is up, or if the service isn't registered.
+    fn why_not_up<T: Service>(&self) -> Option<(NodeId, String, ServiceStatus)>;
+
+    /// Stores a value for the service's hooks to share, replacing any previous
+    /// value. The value is dropped once the service deinitializes. See
+    /// [ServiceScope::init_with_context].
+    fn set_service_context<T: Service, C: Send + Sync + 'static>(&mut self, context: C);
+    /// Gets the value stored with [ServiceWorldExt::set_service_context], if it
+    /// has the type `C`.
+    fn service_context<T: Service, C: 'static>(&self) -> Option<&C>;
+
+    /// Gets a future which resolves once the service comes up, so that async
+    /// code outside of Bevy can wait on it. Resolves immediately if the service
+    /// is already up. If the service goes down before coming up, e.g. because
+    /// it failed or was forced down, the future resolves with `false`. See
+    /// [ServiceReady].
+    ///
+    /// # Panics
+    /// Panics if the service is not registered.
+    fn service_ready<T: Service>(&mut self) -> ServiceReady;
+
+    /// Removes a registered service. The service is spun down first, blocking
+    /// on its deinit tasks, and its hooks are despawned. Its node is removed
+    /// from the [DependencyGraph] and its data from the [GraphDataCache],
+    /// along with any resource and asset deps no other service uses.
+    ///
+    /// Bevy can't remove systems from a schedule, so the service's lifecycle
+    /// and scoped systems are skipped from then on. They're reused if the
+    /// service is registered again, in which case the scoped systems and
+    /// observers its [build](Service::build) adds are ignored.
+    ///
+    /// Returns an error, leaving the service as it is, if it isn't registered
+    /// or if other registered services still depend on it.
+    fn deregister_service<T: Service>(&mut self) -> Result<(), DeregisterErr>;
+
+    /// Gets the service's lifecycle commands which haven't run yet, in the
+    /// order they were sent. A command sent several times in a row is only
+    /// reported once, as it's only run once. Commands sent through [Commands](bevy_ecs::system::Commands)
+    /// show up once the commands have been applied. See
+    /// [ServiceCommandsExt] for how pending commands are prioritized.
+    fn pending_commands<T: Service>(&self) -> Vec<LifecycleCommandKind>;
+
+    /// Retries loading one of the service's asset dependencies which failed
+    /// to load, e.g. once the file has been fixed. The asset goes back to
+    /// initializing and its status is picked up again as it loads, without
+    /// restarting the service, e.g. for [weak assets](ServiceScope::add_weak_asset)
+    /// of a service which is up.
+    ///
+    /// A failed asset's handle is kept even once the services which held it
+    /// have gone down, so a service which failed along with its asset can be
+    /// spun up again after the reload. It comes up once the asset has loaded.
+    ///
+    /// Returns false, doing nothing, if the asset isn't one of the service's
+    /// dependencies or hasn't failed.
+    fn reload_asset_dep<T: Service>(&mut self, id: UntypedAssetId) -> bool;
+
+    /// Replaces a registered service's hooks, guards, and settings with those
+    /// declared in `build`, keeping its current status and owned
+    /// dependencies. The service's lifecycle is not cycled, so an up service
+    /// stays up; the new hooks take effect the next time they would run.
+    ///
+    /// The swap is only compatible if `build` declares the same dependencies
+    /// as the current implementation. Otherwise, a warning is logged and the
+    /// old hooks are kept. Bevy can't remove systems from a schedule, so
+    /// systems and observers can't be added while hot swapping; the scope
+    /// warns and ignores them. `is_startup` is ignored too.
+    ///
+    /// ## Example usage
+    /// ```rust
+    /// # use q_service::prelude::*;
+    /// # use bevy::prelude::*;
+    /// # #[derive(Resource, Debug, Default)]
+    /// # struct ExampleService;
+    /// # impl Service for ExampleService {
+    /// #     fn build(_: &mut ServiceScope<Self>) {}
+    /// # }
+    /// # fn new_on_up() -> UpResult { Ok(()) }
+    /// let mut app = App::new();
+    /// app.register_service::<ExampleService>();
+    /// app.world_mut()
+    ///     .hot_swap_service::<ExampleService>(|scope| {
+    ///         scope.on_up(new_on_up);
+    ///     });
+    /// ```
+    /// ## Panics
+    ///
+    /// This function panics if the service has not been registered.
+    fn hot_swap_service<T: Service>(&mut self, build: impl FnOnce(&mut ServiceScope<T>));
 }
 
 impl ServiceWorldExt for World {
@@ -54,6 +207,11 @@ impl ServiceWorldExt for World {
             .map_unchanged(|cache| cache.get_service_mut(id).unwrap())
     }
 
+    fn service_instance<T: Service>(&self, key: &str) -> Option<&ServiceData> {
+        let id = NodeId::service_instance(self.resource_id::<T>()?, key);
+        self.service_by_id(id)
+    }
+
     fn service_by_id(&self, id: NodeId) -> Option<&ServiceData> {
         self.get_resource::<GraphDataCache>()
             .and_then(|c| c.get_service(id))
@@ -64,19 +222,13 @@ impl ServiceWorldExt for World {
     }
 
     fn get_service<T: Service>(&self) -> Option<&ServiceData> {
-        let id = self
-            .service::<T>()
-            .registered()
-            .then_some(NodeId::Service(self.resource_id::<T>()?))?;
-        self.get_resource::<GraphDataCache>()
-            .and_then(|c| c.get_service(id))
+        let id = NodeId::Service(self.resource_id::<T>()?);
+        self.service_by_id(id)
+            .filter(|service| service.registered())
     }
 
     fn get_service_mut<'w, T: Service>(&'w mut self) -> Option<Mut<'w, ServiceData>> {
-        let id = self
-            .service::<T>()
-            .registered()
-            .then_some(NodeId::Service(self.resource_id::<T>()?))?;
+        let id = self.get_service::<T>()?.id();
         self.get_resource_mut::<GraphDataCache>()
             .map(|cache| cache.map_unchanged(|cache| cache.get_service_mut(id).unwrap()))
     }
@@ -86,13 +238,9 @@ impl ServiceWorldExt for World {
         scope: impl FnOnce(&mut Self, &mut ServiceData) -> R,
     ) -> R {
         let id = NodeId::Service(self.resource_id::<T>().unwrap());
-        let mut service = self
-            .resource_mut::<GraphDataCache>()
-            .remove(&id)
-            .unwrap_or_else(|| panic!("ServiceData for {} should be in the cache.", T::name()));
-        let res = scope(self, service.as_service_mut().unwrap());
-        self.resource_mut::<GraphDataCache>().insert(id, service);
-        res
+        scope_service(self, id, scope, || {
+            format!("ServiceData for {} should be in the cache.", T::name())
+        })
     }
 
     fn service_scope_by_id<R>(
@@ -100,14 +248,368 @@ impl ServiceWorldExt for World {
         id: NodeId,
         scope: impl FnOnce(&mut Self, &mut ServiceData) -> R,
     ) -> R {
-        let mut service = self
-            .resource_mut::<GraphDataCache>()
-            .remove(&id)
-            .unwrap_or_else(
-                || panic!("ServiceData for service id {id:?} should be in the cache.",),
-            );
-        let res = scope(self, service.as_service_mut().unwrap());
-        self.resource_mut::<GraphDataCache>().insert(id, service);
-        res
+        scope_service(self, id, scope, || {
+            format!("ServiceData for service id {id:?} should be in the cache.")
+        })
+    }
+
+    fn key_to_node_id(&self, key: ServiceKey) -> Option<NodeId> {
+        self.get_resource::<GraphDataCache>()?
+            .values()
+            .filter_map(GraphData::as_service)
+            .find(|service| service.registered() && *service.key() == key)
+            .map(ServiceData::id)
+    }
+
+    fn services_with_status(&self, status: ServiceStatus) -> Vec<NodeId> {
+        self.get_resource::<GraphDataCache>()
+            .map(|cache| {
+                cache
+                    .values()
+                    .filter_map(GraphData::as_service)
+                    .filter(|service| service.registered() && service.status == status)
+                    .map(ServiceData::id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn services_with_tag(&self, tag: &str) -> Vec<NodeId> {
+        self.get_resource::<GraphDataCache>()
+            .map(|cache| {
+                cache
+                    .values()
+                    .filter_map(GraphData::as_service)
+                    .filter(|service| service.registered() && service.has_tag(tag))
+                    .map(ServiceData::id)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn services_topo(&self) -> Vec<NodeId> {
+        let (Some(graph), Some(cache)) = (
+            self.get_resource::<DependencyGraph>(),
+            self.get_resource::<GraphDataCache>(),
+        ) else {
+            return vec![];
+        };
+        graph
+            .topsort()
+            .iter()
+            .copied()
+            .filter(|id| cache.get_service(*id).is_some_and(ServiceData::registered))
+            .collect()
+    }
+
+    fn registry(&self) -> &ServiceRegistry {
+        self.resource::<ServiceRegistry>()
+    }
+
+    fn count_services_by_status(&self) -> HashMap<ServiceStatus, usize> {
+        let mut counts = HashMap::default();
+        let Some(cache) = self.get_resource::<GraphDataCache>() else {
+            return counts;
+        };
+        for service in cache.values().filter_map(GraphData::as_service) {
+            if service.registered() {
+                *counts.entry(service.status()).or_default() += 1;
+            }
+        }
+        counts
+    }
+
+    fn snapshot_statuses(&self) -> HashMap<NodeId, ServiceStatus> {
+        self.get_resource::<GraphDataCache>()
+            .map(|cache| {
+                cache
+                    .values()
+                    .filter_map(GraphData::as_service)
+                    .filter(|service| service.registered())
+                    .map(|service| (service.id(), service.status()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn restore_statuses(&mut self, snapshot: HashMap<NodeId, ServiceStatus>) {
+        let order = self
+            .services_topo()
+            .into_iter()
+            .filter_map(|id| {
+                let target = match snapshot.get(&id)? {
+                    ServiceStatus::Deinit(reason) => ServiceStatus::Down(reason.clone()),
+                    ServiceStatus::Init => ServiceStatus::Up,
+                    status => status.clone(),
+                };
+                Some((id, target))
+            })
+            .collect::<Vec<_>>();
+        // The topsort places dependents before their dependencies.
+        for (id, target) in order.iter() {
+            let status = self.service_by_id(*id).unwrap().status();
+            if status == *target {
+                continue;
+            }
+            self.service_scope_by_id(*id, |world, service| match target {
+                ServiceStatus::Down(DownReason::Failed(error)) => {
+                    service.fail(world, error.clone())
+                }
+                ServiceStatus::Down(DownReason::Custom(reason)) => {
+                    service.spin_down_with_reason(world, reason.clone())
+                }
+                ServiceStatus::Down(_) if !status.is_down() => service.spin_down(world),
+                // standby is only reachable from down
+                ServiceStatus::Standby if status.is_up() => service.spin_down(world),
+                _ => {}
+            });
+        }
+        for (id, target) in order.iter().rev() {
+            let status = self.service_by_id(*id).unwrap().status();
+            if status == *target {
+                continue;
+            }
+            self.service_scope_by_id(*id, |world, service| match target {
+                ServiceStatus::Up if !status.is_initializing() => service.spin_up(world),
+                ServiceStatus::Standby if status.is_down() => service.prewarm(world),
+                _ => {}
+            });
+        }
+    }
+
+    fn registry_live(&self) -> bool {
+        self.get_resource::<GraphDataCache>().is_none_or(|cache| {
+            cache
+                .values()
+                .filter_map(GraphData::as_service)
+                .filter(|service| service.registered())
+                .all(ServiceData::is_live)
+        })
+    }
+
+    fn registry_ready(&self) -> bool {
+        self.get_resource::<GraphDataCache>().is_none_or(|cache| {
+            cache
+                .values()
+                .filter_map(GraphData::as_service)
+                .filter(|service| service.registered())
+                .all(|service| service.is_ready(cache))
+        })
+    }
+
+    fn why_not_up<T: Service>(&self) -> Option<(NodeId, String, ServiceStatus)> {
+        let cache = self.get_resource::<GraphDataCache>()?;
+        self.get_service::<T>()?
+            .deps_status(cache)
+            .into_iter()
+            .find(|(_, _, status)| !status.is_up())
+    }
+
+    fn set_service_context<T: Service, C: Send + Sync + 'static>(&mut self, context: C) {
+        let id = NodeId::Service(self.register_resource::<T>());
+        self.get_resource_or_init::<ServiceContexts>()
+            .insert(id, Box::new(context));
+    }
+
+    fn service_context<T: Service, C: 'static>(&self) -> Option<&C> {
+        let id = NodeId::Service(self.resource_id::<T>()?);
+        self.get_resource::<ServiceContexts>()?.get_context(id)
+    }
+
+    fn deregister_service<T: Service>(&mut self) -> Result<(), DeregisterErr> {
+        let Some(id) = self.get_service::<T>().map(ServiceData::id) else {
+            return Err(DeregisterErr::NotRegistered(T::name()));
+        };
+        let name = self.service_by_id(id).unwrap().name().to_string();
+        let graph = self.resource::<DependencyGraph>();
+        let dependents = graph
+            .dependents(id)
+            .filter_map(|dependent| self.service_by_id(dependent))
+            .filter(|dependent| dependent.registered())
+            .map(|dependent| dependent.name().to_string())
+            .collect::<Vec<_>>();
+        if !dependents.is_empty() {
+            return Err(DeregisterErr::HasDependents(name, dependents.join("', '")));
+        }
+        debug!("({name}) Deregistering");
+
+        // bring the service and its deps down before they lose their data
+        let status = self.service_by_id(id).unwrap().status();
+        if !status.is_down() && !status.is_deinitializing() {
+            self.service_scope_by_id(id, |world, service| service.spin_down(world));
+        }
+        let services = self
+            .resource::<DependencyGraph>()
+            .reachable_topsort(id)
+            .into_iter()
+            .filter(|id| self.service_by_id(*id).is_some_and(ServiceData::registered))
+            .collect::<Vec<_>>();
+        settle_deinit(self, &services);
+        if !self.service_by_id(id).unwrap().status().is_down() {
+            warn!("({name}) Couldn't spin down cleanly while deregistering, forcing down");
+            self.service_scope_by_id(id, |world, service| service.force_down(world));
+        }
+        let _ = self.run_system_once(broadcast_new_state::<T>);
+
+        let direct_deps = self
+            .resource::<DependencyGraph>()
+            .neighbors(id)
+            .collect::<Vec<_>>();
+        let mut removed = vec![id];
+        self.resource_scope(|world, mut graph: Mut<DependencyGraph>| {
+            graph.remove_node(id);
+            for dep in direct_deps {
+                let is_service = world
+                    .service_by_id(dep)
+                    .is_some_and(ServiceData::registered);
+                if !is_service && graph.dependents(dep).next().is_none() {
+                    graph.remove_node(dep);
+                    removed.push(dep);
+                }
+            }
+            graph
+                .update_topsort()
+                .expect("Removing nodes can't create cycles.");
+        });
+        for id in removed {
+            let owned = match self.resource_mut::<GraphDataCache>().remove(&id) {
+                Some(GraphData::Service(service)) => {
+                    service.despawn_systems(self);
+                    vec![]
+                }
+                Some(GraphData::Resource(resource)) => vec![resource.init, resource.deinit],
+                Some(GraphData::Asset(asset)) => vec![asset.container],
+                None => vec![],
+            };
+            for entity in owned {
+                self.despawn(entity);
+            }
+        }
+
+        self.resource_mut::<ServiceRegistry>().remove(id);
+        self.resource_mut::<ServiceContexts>().remove(&id);
+        self.resource_mut::<ServiceReadyNotifiers>().0.remove(&id);
+        for group in self.resource_mut::<ServiceGroups>().values_mut() {
+            group.members.retain(|member| *member != id);
+        }
+        self.resource_mut::<DeregisteredServices>().insert(id);
+        self.remove_resource::<T>();
+        Ok(())
+    }
+
+    fn pending_commands<T: Service>(&self) -> Vec<LifecycleCommandKind> {
+        // commands already taken from the events wait in the service's queue
+        let mut pending = self
+            .get_service::<T>()
+            .map(|service| service.commands.clone())
+            .unwrap_or_default();
+        if let Some(events) = self.get_resource::<Events<LifecycleCommand<T>>>() {
+            for command in EventCursor::default()
+                .read(events)
+                .map(LifecycleCommand::kind)
+            {
+                if pending.last() != Some(&command) {
+                    pending.push(command);
+                }
+            }
+        }
+        pending
+    }
+
+    fn reload_asset_dep<T: Service>(&mut self, id: UntypedAssetId) -> bool {
+        let node = NodeId::Asset(id);
+        if !self
+            .get_service::<T>()
+            .is_some_and(|service| service.deps().contains(&node))
+        {
+            return false;
+        }
+        let Some(container) = self
+            .resource::<GraphDataCache>()
+            .get_asset(node)
+            .filter(|asset| asset.status.is_failed())
+            .map(|asset| asset.container)
+        else {
+            return false;
+        };
+        let (Some(&ReloadAsset(reload)), Some(path)) = (
+            self.get::<ReloadAsset>(container),
+            self.resource::<AssetServer>().get_path(id),
+        ) else {
+            return false;
+        };
+        let path = path.into_owned();
+        let mut cache = self.resource_mut::<GraphDataCache>();
+        let asset = cache.get_asset_mut(node).unwrap();
+        debug!("({}) Reloading asset dep", asset.name);
+        asset.status = ServiceStatus::Init;
+        reload(self.resource::<AssetServer>(), path);
+        true
+    }
+
+    fn hot_swap_service<T: Service>(&mut self, build: impl FnOnce(&mut ServiceScope<T>)) {
+        let mut scope = ServiceScope::new_hot_swap(self);
+        build(&mut scope);
+        let (spec, setup) = scope.into_parts();
+        ServiceData::hot_swap::<T>(self, spec, setup);
+    }
+
+    fn service_ready<T: Service>(&mut self) -> ServiceReady {
+        let service = self.service::<T>();
+        let (id, is_up) = (service.id(), service.status().is_up());
+        let (notifier, ready) = ServiceReadyNotifier::channel();
+        if is_up {
+            notifier.notify();
+        } else {
+            self.resource_mut::<ServiceReadyNotifiers>()
+                .0
+                .entry(id)
+                .or_default()
+                .push(notifier);
+        }
+        ready
+    }
+}
+
+/// The services which are currently removed from the [GraphDataCache] by
+/// [ServiceWorldExt::service_scope].
+#[derive(Resource, Default)]
+struct ScopedServices(HashSet<NodeId>);
+
+/// Removes the service from the cache for the duration of `scope`. Panics with
+/// `missing` if the service isn't in the cache, or with an explanation if it's
+/// already scoped further up the stack.
+fn scope_service<R>(
+    world: &mut World,
+    id: NodeId,
+    scope: impl FnOnce(&mut World, &mut ServiceData) -> R,
+    missing: impl FnOnce() -> String,
+) -> R {
+    let mut scoped = world.get_resource_or_init::<ScopedServices>();
+    if !scoped.0.insert(id) {
+        // the service is out of the cache, so its type name has to do
+        let name = match id {
+            NodeId::Service(cid) | NodeId::ServiceInstance(cid, _) => world
+                .components()
+                .get_name(cid)
+                .map(|name| name.rsplit("::").next().unwrap_or(&name).to_string()),
+            _ => None,
+        }
+        .unwrap_or_else(|| format!("{id:?}"));
+        panic!(
+            "Service {name} is already in a service_scope, so it can't be scoped again. \
+            Use the ServiceData passed to the outer scope instead."
+        );
     }
+    let Some(mut service) = world.resource_mut::<GraphDataCache>().remove(&id) else {
+        world.resource_mut::<ScopedServices>().0.remove(&id);
+        panic!("{}", missing());
+    };
+    // put the service back even if the scope panics
+    let res = catch_unwind(AssertUnwindSafe(|| {
+        scope(world, service.as_service_mut().unwrap())
+    }));
+    world.resource_mut::<ScopedServices>().0.remove(&id);
+    world.resource_mut::<GraphDataCache>().insert(id, service);
+    res.unwrap_or_else(|payload| resume_unwind(payload))
 }
@@ -1,6 +1,11 @@
 use bevy::prelude::*;
 use bevy_asset::AssetLoader;
+use core::time::Duration;
 use q_service::prelude::*;
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
 
 mod common;
 use common::*;
@@ -59,11 +64,10 @@ fn asset_dep() {
                 }
             });
         });
-    busy_wait(1000); // wait extra long for CI
-    app.update();
+    let status = app.spin_service_up_blocking::<AssetDep>(Duration::from_secs(5));
+    assert!(status.is_up());
     app.world_mut()
         .service_scope::<AssetDep, _>(|world, service| {
-            assert!(service.status().is_up());
             service.deps().iter().for_each(|dep| {
                 if let Some(asset) = world.resource::<GraphDataCache>().get_asset(*dep) {
                     assert!(asset.status.is_up());
@@ -125,3 +129,345 @@ fn persistent_asset() {
         .get(handle.unwrap().id())
         .unwrap();
 }
+
+#[derive(Resource, Debug, Default)]
+struct SharedAssetDep;
+impl Service for SharedAssetDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.is_startup(true).add_asset::<TestAsset>("test.txt");
+    }
+}
+
+#[test]
+fn shared_asset() {
+    let mut app = setup();
+    app.init_asset::<TestAsset>()
+        .register_asset_loader(TestAssetLoader)
+        .register_service::<AssetDep>()
+        .register_service::<SharedAssetDep>();
+    let world = app.world_mut();
+    assert_eq!(
+        world
+            .query::<&KeepHandleAlive<TestAsset>>()
+            .iter(world)
+            .count(),
+        1
+    );
+    app.update();
+    app.spin_service_up_blocking::<AssetDep>(Duration::from_secs(5));
+    app.spin_service_up_blocking::<SharedAssetDep>(Duration::from_secs(5));
+    status_matches!(app.world(), AssetDep, ServiceStatus::Up);
+    status_matches!(app.world(), SharedAssetDep, ServiceStatus::Up);
+
+    let dep = app.world().service::<AssetDep>().deps()[0];
+    assert_eq!(app.world().service::<SharedAssetDep>().deps(), [dep]);
+    let container = app
+        .world()
+        .resource::<GraphDataCache>()
+        .get_asset(dep)
+        .unwrap()
+        .container;
+
+    app.world_mut().commands().spin_service_down::<AssetDep>();
+    app.update();
+    status_matches!(app.world(), AssetDep, ServiceStatus::Down(_));
+    let asset = app.world().resource::<GraphDataCache>().get_asset(dep);
+    assert!(asset.unwrap().status.is_up());
+    assert!(app.world().get_entity(container).is_ok());
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<SharedAssetDep>();
+    app.update();
+    status_matches!(app.world(), SharedAssetDep, ServiceStatus::Down(_));
+    let asset = app.world().resource::<GraphDataCache>().get_asset(dep);
+    assert!(asset.unwrap().status.is_down());
+    assert!(app.world().get_entity(container).is_err());
+}
+
+#[derive(Resource, Debug, Default)]
+struct WeakAssetDep;
+impl Service for WeakAssetDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .is_startup(true)
+            .add_weak_asset::<TestAsset>("does_not_exist.txt");
+    }
+}
+
+#[test]
+fn weak_asset() {
+    let mut app = setup();
+    app.init_asset::<TestAsset>()
+        .register_asset_loader(TestAssetLoader)
+        .register_service::<WeakAssetDep>();
+    app.update();
+    assert_status::<WeakAssetDep>(app.world(), ServiceStatus::Up);
+    for _ in 0..100 {
+        app.update();
+        let world = app.world();
+        let cache = world.resource::<GraphDataCache>();
+        let dep = world.service::<WeakAssetDep>().deps()[0];
+        if cache.get(&dep).unwrap().status().is_failed() {
+            break;
+        }
+        busy_wait(10);
+    }
+    let world = app.world();
+    let dep = world.service::<WeakAssetDep>().deps()[0];
+    assert!(
+        world
+            .resource::<GraphDataCache>()
+            .get(&dep)
+            .unwrap()
+            .status()
+            .is_failed()
+    );
+    assert_status::<WeakAssetDep>(app.world(), ServiceStatus::Up);
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Loaded without settings.")]
+struct MissingSettingsError;
+
+#[derive(Asset, Reflect)]
+struct SettingsAsset;
+
+/// Only loads when its settings are overridden.
+struct SettingsAssetLoader;
+impl AssetLoader for SettingsAssetLoader {
+    type Asset = SettingsAsset;
+
+    type Settings = bool;
+
+    type Error = MissingSettingsError;
+
+    async fn load(
+        &self,
+        _reader: &mut dyn bevy_asset::io::Reader,
+        settings: &Self::Settings,
+        _load_context: &mut bevy_asset::LoadContext<'_>,
+    ) -> std::result::Result<Self::Asset, Self::Error> {
+        if *settings {
+            Ok(SettingsAsset)
+        } else {
+            Err(MissingSettingsError)
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct SettingsAssetDep;
+impl Service for SettingsAssetDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .is_startup(true)
+            .add_asset_with_settings::<SettingsAsset, bool>("test.txt", |settings| {
+                *settings = true;
+            });
+    }
+}
+
+#[test]
+fn asset_with_settings() {
+    let mut app = setup();
+    app.init_asset::<SettingsAsset>()
+        .register_asset_loader(SettingsAssetLoader)
+        .register_service::<SettingsAssetDep>();
+    let status = app.spin_service_up_blocking::<SettingsAssetDep>(Duration::from_secs(5));
+    assert!(status.is_up());
+    let world = app.world();
+    let dep = world.service::<SettingsAssetDep>().deps()[0];
+    let asset = world.resource::<GraphDataCache>().get_asset(dep);
+    assert!(asset.unwrap().status.is_up());
+}
+
+#[derive(Asset, Reflect)]
+struct FolderItem;
+
+struct FolderItemLoader;
+impl AssetLoader for FolderItemLoader {
+    type Asset = FolderItem;
+
+    type Settings = ();
+
+    type Error = TestAssetError;
+
+    async fn load(
+        &self,
+        _reader: &mut dyn bevy_asset::io::Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut bevy_asset::LoadContext<'_>,
+    ) -> std::result::Result<Self::Asset, Self::Error> {
+        Ok(FolderItem)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["txt"]
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct FolderDep;
+impl Service for FolderDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.is_startup(true).add_asset_folder("folder");
+    }
+}
+
+#[test]
+fn asset_folder() {
+    let mut app = setup();
+    app.init_asset::<FolderItem>()
+        .register_asset_loader(FolderItemLoader)
+        .register_service::<FolderDep>();
+    let status = app.spin_service_up_blocking::<FolderDep>(Duration::from_secs(5));
+    assert!(status.is_up());
+    assert_eq!(app.world().resource::<Assets<FolderItem>>().len(), 2);
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Failed on the first load.")]
+struct FlakyAssetError;
+
+#[derive(Asset, Reflect)]
+struct FlakyAsset;
+
+/// Fails the first time it loads.
+struct FlakyAssetLoader(Arc<AtomicBool>);
+impl AssetLoader for FlakyAssetLoader {
+    type Asset = FlakyAsset;
+
+    type Settings = ();
+
+    type Error = FlakyAssetError;
+
+    async fn load(
+        &self,
+        _reader: &mut dyn bevy_asset::io::Reader,
+        _settings: &Self::Settings,
+        _load_context: &mut bevy_asset::LoadContext<'_>,
+    ) -> std::result::Result<Self::Asset, Self::Error> {
+        if self.0.swap(true, Ordering::SeqCst) {
+            Ok(FlakyAsset)
+        } else {
+            Err(FlakyAssetError)
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct FlakyAssetDep;
+impl Service for FlakyAssetDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .is_startup(true)
+            .add_weak_asset::<FlakyAsset>("test.txt");
+    }
+}
+
+/// Updates the app until the asset dep has the given status.
+fn wait_for_asset(app: &mut App, dep: NodeId, done: impl Fn(&ServiceStatus) -> bool) {
+    for _ in 0..100 {
+        app.update();
+        if done(
+            &app.world()
+                .resource::<GraphDataCache>()
+                .get(&dep)
+                .unwrap()
+                .status(),
+        ) {
+            return;
+        }
+        busy_wait(10);
+    }
+    panic!("Asset dep never reached the expected status");
+}
+
+#[test]
+fn reload_failed_asset() {
+    let mut app = setup();
+    app.init_asset::<FlakyAsset>()
+        .register_asset_loader(FlakyAssetLoader(default()))
+        .register_service::<FlakyAssetDep>();
+    app.update();
+    let dep = app.world().service::<FlakyAssetDep>().deps()[0];
+    let NodeId::Asset(id) = dep else {
+        panic!("Expected an asset dep");
+    };
+    wait_for_asset(&mut app, dep, ServiceStatus::is_failed);
+    assert_status::<FlakyAssetDep>(app.world(), ServiceStatus::Up);
+
+    assert!(app.world_mut().reload_asset_dep::<FlakyAssetDep>(id));
+    let cache = app.world().resource::<GraphDataCache>();
+    assert!(cache.get(&dep).unwrap().status().is_initializing());
+    wait_for_asset(&mut app, dep, ServiceStatus::is_up);
+    assert_status::<FlakyAssetDep>(app.world(), ServiceStatus::Up);
+
+    // only failed assets are reloaded
+    assert!(!app.world_mut().reload_asset_dep::<FlakyAssetDep>(id));
+}
+
+#[derive(Resource, Debug, Default)]
+struct FlakyStrongAssetDep;
+impl Service for FlakyStrongAssetDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_asset::<FlakyAsset>("test.txt");
+    }
+}
+
+#[test]
+fn reload_failed_strong_asset() {
+    let mut app = setup();
+    app.init_asset::<FlakyAsset>()
+        .register_asset_loader(FlakyAssetLoader(default()))
+        .register_service::<FlakyStrongAssetDep>();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<FlakyStrongAssetDep>();
+    app.update();
+    let dep = app.world().service::<FlakyStrongAssetDep>().deps()[0];
+    let NodeId::Asset(id) = dep else {
+        panic!("Expected an asset dep");
+    };
+    for _ in 0..100 {
+        app.update();
+        if app
+            .world()
+            .service::<FlakyStrongAssetDep>()
+            .status()
+            .is_failed()
+        {
+            break;
+        }
+        busy_wait(10);
+    }
+    status_matches!(
+        app.world(),
+        FlakyStrongAssetDep,
+        ServiceStatus::Down(DownReason::Failed(_))
+    );
+    let cache = app.world().resource::<GraphDataCache>();
+    assert!(cache.get(&dep).unwrap().status().is_failed());
+
+    // the failed asset is still held, so it can be reloaded
+    assert!(app.world_mut().reload_asset_dep::<FlakyStrongAssetDep>(id));
+    app.world_mut()
+        .commands()
+        .spin_service_up::<FlakyStrongAssetDep>();
+    for _ in 0..100 {
+        app.update();
+        if app
+            .world()
+            .service::<FlakyStrongAssetDep>()
+            .status()
+            .is_up()
+        {
+            break;
+        }
+        busy_wait(10);
+    }
+    assert_status::<FlakyStrongAssetDep>(app.world(), ServiceStatus::Up);
+    let cache = app.world().resource::<GraphDataCache>();
+    assert!(cache.get(&dep).unwrap().status().is_up());
+}
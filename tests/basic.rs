@@ -1,6 +1,16 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+    },
+    time::{Duration, Instant},
+};
 
-use bevy::prelude::*;
+use bevy::{
+    diagnostic::DiagnosticsStore,
+    ecs::{component::Tick, world::CommandQueue},
+    prelude::*,
+};
 use q_service::prelude::*;
 mod common;
 use common::*;
@@ -52,6 +62,19 @@ fn no_dupes() {
     assert_eq!(count.up, 1);
 }
 
+#[test]
+fn reregister_keeps_state() {
+    let mut app = setup();
+    app.init_resource::<Count>().register_service::<NoDupes>();
+    app.update();
+    status_matches!(app.world(), NoDupes, ServiceStatus::Up);
+
+    app.register_service::<NoDupes>();
+    app.update();
+    status_matches!(app.world(), NoDupes, ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().up, 1);
+}
+
 #[derive(Resource, Default, Debug)]
 struct HookFailure;
 impl Service for HookFailure {
@@ -68,7 +91,7 @@ fn hook_failure() {
     let status = app.world().service::<HookFailure>().status();
     matches!(
         status,
-        ServiceStatus::Down(DownReason::Failed(ServiceError::Own(_)))
+        ServiceStatus::Down(DownReason::Failed(ServiceError::Own(..)))
     );
 }
 
@@ -103,6 +126,31 @@ fn hooks() {
     );
 }
 
+#[test]
+fn force_down() {
+    let mut app = setup();
+    app.init_resource::<Count>().register_service::<Hooks>();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.update();
+    status_matches!(app.world(), Hooks, ServiceStatus::Up);
+    app.world_mut().commands().force_service_down::<Hooks>();
+    app.update();
+    status_matches!(
+        app.world(),
+        Hooks,
+        ServiceStatus::Down(DownReason::SpunDown)
+    );
+    assert_eq!(
+        app.world_mut().resource::<Count>(),
+        &Count {
+            init: 1,
+            up: 1,
+            down: 0,
+            deinit: 0,
+        }
+    );
+}
+
 #[derive(Default, Resource, Debug)]
 struct Events;
 impl Service for Events {
@@ -111,7 +159,7 @@ impl Service for Events {
     }
 }
 fn noop_init() -> InitResult {
-    let hook = AsyncHook::io_task(async |_| Ok(()));
+    let hook = AsyncHook::io(async |_| Ok(()));
     Ok(Some(hook))
 }
 
@@ -135,6 +183,7 @@ fn respond_to_events() {
                     ServiceStatus::Down(_) => {
                         r.down += 1;
                     }
+                    ServiceStatus::Standby => {}
                 }
             }
         },
@@ -155,52 +204,56 @@ fn respond_to_events() {
     );
 }
 
-// #[test]
-// fn observers() {
-//     let mut app = setup();
-//     app.init_resource::<Count>();
-//     app.register_service::<Events>().add_observer(
-//         |trigger: Trigger<EnterServiceState<Events>>,
-//          mut r: ResMut<Count>,
-//          mut commands: Commands| {
-//             match &**trigger.event() {
-//                 ServiceStatus::Init => {
-//                     debug!("init!");
-//                     r.init += 1;
-//                 }
-//                 ServiceStatus::Up => {
-//                     debug!("up!");
-//                     r.up += 1;
-//                     commands.spin_service_down::<Events>();
-//                 }
-//                 ServiceStatus::Deinit(_) => {
-//                     debug!("deinit!");
-//                     r.deinit += 1;
-//                 }
-//                 ServiceStatus::Down(_) => {
-//                     debug!("down!");
-//                     r.down += 1;
-//                 }
-//             }
-//         },
-//     );
-//     // note: These are all observers so they _should_ be happening ASAP,
-//     // but async polling is happening once per update, making this relatively slow...
-//     app.world_mut().commands().spin_service_up::<Events>();
-//     app.update(); // init
-//     app.update(); // up
-//     app.update(); // deinit
-//     app.update(); // down
-//     assert_eq!(
-//         app.world_mut().resource::<Count>(),
-//         &Count {
-//             init: 1,
-//             up: 1,
-//             down: 1,
-//             deinit: 1,
-//         }
-//     );
-// }
+#[test]
+fn observers() {
+    let mut app = setup();
+    app.init_resource::<Count>();
+    app.register_service::<Events>().add_observer(
+        |trigger: Trigger<EnterServiceState<Events>>,
+         mut r: ResMut<Count>,
+         mut commands: Commands| {
+            match &**trigger.event() {
+                ServiceStatus::Init => {
+                    debug!("init!");
+                    r.init += 1;
+                }
+                ServiceStatus::Up => {
+                    debug!("up!");
+                    r.up += 1;
+                    commands.spin_service_down::<Events>();
+                }
+                ServiceStatus::Deinit(_) => {
+                    debug!("deinit!");
+                    r.deinit += 1;
+                }
+                ServiceStatus::Down(_) => {
+                    debug!("down!");
+                    r.down += 1;
+                }
+                ServiceStatus::Standby => {}
+            }
+        },
+    );
+    // Observers fire in the same update as the status change. Async hooks are
+    // polled once per update, so each transition still takes an update.
+    app.world_mut().commands().spin_service_up::<Events>();
+    app.update(); // init
+    assert_eq!(app.world().resource::<Count>().init, 1);
+    app.update(); // up
+    assert_eq!(app.world().resource::<Count>().up, 1);
+    app.update(); // deinit
+    assert_eq!(app.world().resource::<Count>().deinit, 1);
+    app.update(); // down
+    assert_eq!(
+        app.world_mut().resource::<Count>(),
+        &Count {
+            init: 1,
+            up: 1,
+            down: 1,
+            deinit: 1,
+        }
+    );
+}
 
 #[derive(Resource, Default, Debug, PartialEq)]
 struct Ran {
@@ -240,7 +293,7 @@ fn busy_wait(millis: u64) {
     while Instant::now().duration_since(start) <= Duration::from_millis(millis) {}
 }
 fn run_condition_async() -> InitResult {
-    let task = AsyncHook::async_compute_task(async |_| {
+    let task = AsyncHook::async_compute(async |_| {
         debug!("In AsyncComputeTaskPool");
         busy_wait(100);
         debug!("...AsyncComputeTaskPool DONE");
@@ -267,7 +320,7 @@ fn run_conditions() {
             ran.service_failed_with_error = true;
         })
         .run_if(service_failed_with_error::<RunConditions>(
-            ServiceError::Own("oh no".into()),
+            ServiceError::own("oh no"),
         )),
     );
     check_run_condition!(app, RunConditions, service_initializing);
@@ -285,7 +338,7 @@ fn run_conditions() {
     app.update(); // service_up, service_has_status(up)
     app.world_mut()
         .commands()
-        .fail_service::<RunConditions>(ServiceError::Own("oh no".into()));
+        .fail_service::<RunConditions>(ServiceError::own("oh no"));
     app.update(); // deinit
     busy_wait(200); // wait for it to be finished...
     app.update(); // service_down, service_failed, service_failed_with
@@ -364,7 +417,2223 @@ fn command_priority() {
     app.world_mut().commands().restart_service::<Hooks>();
     app.world_mut()
         .commands()
-        .fail_service::<Hooks>(ServiceError::Own("oh no".to_string()));
+        .fail_service::<Hooks>(ServiceError::own("oh no"));
     app.update();
     assert!(app.world_mut().service::<Hooks>().status().is_failed());
 }
+
+#[derive(Resource, Default, Debug)]
+struct Stuck(bool);
+
+#[test]
+fn time_in_status() {
+    let mut app = setup();
+    app.init_resource::<Stuck>().register_service::<Simple>();
+    app.add_systems(
+        Update,
+        (|mut stuck: ResMut<Stuck>| stuck.0 = true).run_if(
+            service_in_status_longer_than::<Simple>(Duration::from_millis(50)),
+        ),
+    );
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    let before = app.world().service::<Simple>().time_in_status();
+    assert!(!app.world().resource::<Stuck>().0);
+
+    busy_wait(60);
+    app.update();
+    let after = app.world().service::<Simple>().time_in_status();
+    assert!(after > before);
+    assert!(after >= Duration::from_millis(50));
+    assert!(app.world().service::<Simple>().status().is_up());
+    assert!(app.world().resource::<Stuck>().0);
+}
+
+#[test]
+fn total_uptime() {
+    let mut app = setup();
+    app.register_service::<Simple>();
+    app.update();
+    assert_eq!(
+        app.world().service::<Simple>().total_uptime(),
+        Duration::ZERO
+    );
+
+    let mut up_for = Duration::ZERO;
+    for _ in 0..2 {
+        app.world_mut().commands().spin_service_up::<Simple>();
+        app.update();
+        busy_wait(30);
+        app.world_mut().commands().spin_service_down::<Simple>();
+        app.update();
+        up_for += Duration::from_millis(30);
+        busy_wait(30);
+    }
+    let service = app.world().service::<Simple>();
+    assert!(service.status().is_down());
+    assert!(service.total_uptime() >= up_for);
+}
+
+#[derive(Resource, Default, Debug)]
+struct Initializing;
+impl Service for Initializing {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|| {
+            let hook = AsyncHook::io(async |_| {
+                core::future::pending::<()>().await;
+                Ok(())
+            });
+            Ok(Some(hook))
+        });
+    }
+}
+
+#[test]
+fn liveness_and_readiness() {
+    let mut app = setup();
+    app.register_service::<Simple>()
+        .register_service::<HookFailure>()
+        .register_service::<Initializing>();
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.world_mut().commands().spin_service_up::<Initializing>();
+    app.update();
+
+    let world = app.world();
+    let cache = world.resource::<GraphDataCache>();
+    let up = world.service::<Simple>();
+    assert!(up.is_live() && up.is_ready(cache));
+    let failed = world.service::<HookFailure>();
+    assert!(!failed.is_live() && !failed.is_ready(cache));
+    let initializing = world.service::<Initializing>();
+    assert!(initializing.status().is_initializing());
+    assert!(initializing.is_live() && !initializing.is_ready(cache));
+    assert!(!world.registry_live());
+    assert!(!world.registry_ready());
+}
+
+#[test]
+fn services_by_status() {
+    let mut app = setup();
+    app.register_service::<Simple>()
+        .register_service::<NoDupes>()
+        .register_service::<HookFailure>()
+        .register_service::<Initializing>();
+    app.init_resource::<Count>();
+    app.world_mut().commands().spin_service_up::<Initializing>();
+    app.update();
+
+    let world = app.world();
+    let up = world.services_with_status(ServiceStatus::Up);
+    assert_eq!(up, vec![world.service::<NoDupes>().id()]);
+    let uninit = world.services_with_status(ServiceStatus::uninit());
+    assert_eq!(uninit, vec![world.service::<Simple>().id()]);
+    let init = world.services_with_status(ServiceStatus::Init);
+    assert_eq!(init, vec![world.service::<Initializing>().id()]);
+
+    let counts = world.count_services_by_status();
+    assert_eq!(counts.values().sum::<usize>(), 4);
+    assert_eq!(counts[&ServiceStatus::Up], 1);
+    assert_eq!(counts[&ServiceStatus::uninit()], 1);
+    assert_eq!(counts[&ServiceStatus::Init], 1);
+    assert_eq!(counts[&world.service::<HookFailure>().status()], 1);
+}
+
+#[derive(Resource, Default, Debug)]
+struct Swappable;
+impl Service for Swappable {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_resource::<TestResource>()
+            .on_up(count_up)
+            .on_down(count_down)
+            .is_startup(true);
+    }
+}
+#[derive(Resource, Default, Debug)]
+struct TestResource;
+#[derive(Resource, Default, Debug)]
+struct Swapped(u32);
+fn swapped_up(mut swapped: ResMut<Swapped>) -> UpResult {
+    swapped.0 += 1;
+    Ok(())
+}
+
+#[test]
+fn hot_swap() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<Swapped>()
+        .register_service::<Swappable>();
+    app.update();
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().up, 1);
+
+    app.world_mut().hot_swap_service::<Swappable>(|scope| {
+        scope
+            .add_resource::<TestResource>()
+            .on_up(swapped_up)
+            .on_down(count_down);
+    });
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+    assert!(app.world().contains_resource::<TestResource>());
+    app.update();
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+
+    app.world_mut().commands().restart_service::<Swappable>();
+    app.update();
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+    let count = app.world().resource::<Count>();
+    assert_eq!(count.up, 1);
+    assert_eq!(count.down, 0);
+    assert_eq!(app.world().resource::<Swapped>().0, 1);
+}
+
+#[test]
+fn hot_swap_guards() {
+    let mut app = setup();
+    app.init_resource::<Count>().register_service::<Swappable>();
+    app.update();
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+
+    app.world_mut().hot_swap_service::<Swappable>(|scope| {
+        scope.add_resource::<TestResource>().can_spin_down(|| false);
+    });
+    app.world_mut().commands().spin_service_down::<Swappable>();
+    app.update();
+    app.update();
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+}
+
+#[test]
+fn hot_swap_incompatible() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<Swapped>()
+        .register_service::<Simple>()
+        .register_service::<Swappable>();
+    app.update();
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+
+    // the deps changed, so the swap is rejected, and its systems never run
+    app.world_mut().hot_swap_service::<Swappable>(|scope| {
+        scope
+            .add_dep::<Simple>()
+            .add_resource::<TestResource>()
+            .on_up(swapped_up)
+            .add_systems(Update, |mut swapped: ResMut<Swapped>| swapped.0 += 1);
+    });
+    app.world_mut().commands().restart_service::<Swappable>();
+    app.update();
+    app.update();
+    status_matches!(app.world(), Swappable, ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().up, 2);
+    assert_eq!(app.world().resource::<Swapped>().0, 0);
+}
+
+#[derive(Resource, Default, Debug)]
+struct Prewarmed;
+impl Service for Prewarmed {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(slow_init).on_up(count_up);
+    }
+}
+fn slow_init(mut count: ResMut<Count>) -> InitResult {
+    count.init += 1;
+    let hook = AsyncHook::io(async |_| {
+        busy_wait(20);
+        Ok(())
+    });
+    Ok(Some(hook))
+}
+
+#[test]
+fn prewarm() {
+    let mut app = setup();
+    app.init_resource::<Count>().register_service::<Prewarmed>();
+    app.world_mut().commands().prewarm_service::<Prewarmed>();
+    app.update();
+    status_matches!(app.world(), Prewarmed, ServiceStatus::Init);
+    while app
+        .world()
+        .service::<Prewarmed>()
+        .status()
+        .is_initializing()
+    {
+        app.update();
+    }
+    status_matches!(app.world(), Prewarmed, ServiceStatus::Standby);
+    assert_eq!(app.world().resource::<Count>().up, 0);
+
+    app.world_mut().commands().spin_service_up::<Prewarmed>();
+    app.update();
+    status_matches!(app.world(), Prewarmed, ServiceStatus::Up);
+    let count = app.world().resource::<Count>();
+    assert_eq!(count.init, 1);
+    assert_eq!(count.up, 1);
+}
+
+#[derive(Resource, Default, Debug, PartialEq)]
+struct DownBecause {
+    uninitialized: bool,
+    spun_down: bool,
+    failed: bool,
+}
+
+#[test]
+fn down_because() {
+    let mut app = setup();
+    app.init_resource::<DownBecause>()
+        .register_service::<Simple>()
+        .register_service::<HookFailure>()
+        .add_systems(
+            Update,
+            (
+                (|mut ran: ResMut<DownBecause>| ran.uninitialized = true)
+                    .run_if(service_down_because::<Simple>(DownReason::Uninitialized)),
+                (|mut ran: ResMut<DownBecause>| ran.spun_down = true)
+                    .run_if(service_down_because::<Simple>(DownReason::SpunDown)),
+                (|mut ran: ResMut<DownBecause>| ran.failed = true).run_if(service_down_because::<
+                    HookFailure,
+                >(
+                    DownReason::failed("")
+                )),
+            ),
+        );
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    assert_eq!(
+        app.world().resource::<DownBecause>(),
+        &DownBecause {
+            uninitialized: false,
+            spun_down: false,
+            failed: true,
+        }
+    );
+
+    app.world_mut().commands().spin_service_down::<Simple>();
+    app.update();
+    assert!(app.world().resource::<DownBecause>().spun_down);
+    assert!(!app.world().resource::<DownBecause>().uninitialized);
+}
+
+#[derive(Resource, Debug, Default)]
+struct Idle;
+impl Service for Idle {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.is_startup(true);
+    }
+}
+
+#[test]
+fn idle_lifecycle_skipped() {
+    let mut app = setup();
+    app.register_service::<Idle>();
+    app.update();
+    app.update();
+    assert_status::<Idle>(app.world(), ServiceStatus::Up);
+
+    // polling tasks takes the service mutably, which would mark the cache
+    let last_changed = app.world().resource_ref::<GraphDataCache>().last_changed();
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world().resource_ref::<GraphDataCache>().last_changed(),
+        last_changed
+    );
+
+    app.world_mut().commands().spin_service_down::<Idle>();
+    app.update();
+    assert_status::<Idle>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Token(u32);
+#[derive(Resource, Default, Debug)]
+struct SeenToken(Option<Token>);
+
+#[derive(Resource, Debug, Default)]
+struct WithContext;
+impl Service for WithContext {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .init_with(context_init)
+            .deinit_with_context(context_deinit);
+    }
+}
+fn context_init() -> InitResult {
+    let hook = AsyncHook::io(async |queue: &mut CommandQueue| {
+        queue.push(|world: &mut World| {
+            world.set_service_context::<WithContext, _>(Token(7));
+        });
+        Ok(())
+    });
+    Ok(Some(hook))
+}
+fn context_deinit(token: In<Option<Token>>, mut seen: ResMut<SeenToken>) -> DeinitResult {
+    seen.0 = token.0;
+    Ok(None)
+}
+
+#[test]
+fn hook_context() {
+    let mut app = setup();
+    app.init_resource::<SeenToken>()
+        .register_service::<WithContext>();
+    app.world_mut().commands().spin_service_up::<WithContext>();
+    for _ in 0..10 {
+        app.update();
+        if app.world().service::<WithContext>().status().is_up() {
+            break;
+        }
+    }
+    assert_status::<WithContext>(app.world(), ServiceStatus::Up);
+    assert_eq!(
+        app.world().service_context::<WithContext, Token>(),
+        Some(&Token(7))
+    );
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<WithContext>();
+    app.update();
+    assert_status::<WithContext>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    assert_eq!(app.world().resource::<SeenToken>().0, Some(Token(7)));
+    assert_eq!(app.world().service_context::<WithContext, Token>(), None);
+}
+
+#[derive(Resource, Debug, Default)]
+struct WithTypedContext;
+impl Service for WithTypedContext {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .init_with_context(|| Ok((Token(3), None)))
+            .deinit_with_context(context_deinit);
+    }
+}
+
+#[test]
+fn typed_hook_context() {
+    let mut app = setup();
+    app.init_resource::<SeenToken>()
+        .register_service::<WithTypedContext>();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<WithTypedContext>();
+    app.update();
+    assert_status::<WithTypedContext>(app.world(), ServiceStatus::Up);
+    assert_eq!(
+        app.world().service_context::<WithTypedContext, Token>(),
+        Some(&Token(3))
+    );
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<WithTypedContext>();
+    app.update();
+    assert_eq!(app.world().resource::<SeenToken>().0, Some(Token(3)));
+    assert_eq!(
+        app.world().service_context::<WithTypedContext, Token>(),
+        None
+    );
+}
+
+#[derive(Resource, Debug, Default)]
+struct FailsWithContext;
+impl Service for FailsWithContext {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(set_context_then_fail);
+    }
+}
+fn set_context_then_fail(world: &mut World) -> InitResult {
+    world.set_service_context::<FailsWithContext, _>(Token(5));
+    Err(ServiceError::own("failed after opening").into())
+}
+
+#[test]
+fn failed_init_drops_context() {
+    let mut app = setup();
+    app.register_service::<FailsWithContext>();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<FailsWithContext>();
+    app.update();
+    status_matches!(
+        app.world(),
+        FailsWithContext,
+        ServiceStatus::Down(DownReason::Failed(_))
+    );
+    assert_eq!(
+        app.world().service_context::<FailsWithContext, Token>(),
+        None
+    );
+}
+
+static PROGRESS_STEP: AtomicU32 = AtomicU32::new(0);
+
+#[derive(Resource, Debug, Default)]
+struct Progress;
+impl Service for Progress {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(progress_init);
+    }
+}
+/// Reports half of its progress per step, waiting for the test to allow each.
+fn progress_init() -> InitResult {
+    let hook = AsyncHook::progress_task(async |_, progress: ProgressSender| {
+        for step in 1..=2 {
+            while PROGRESS_STEP.load(Ordering::Relaxed) < step {
+                bevy::tasks::futures_lite::future::yield_now().await;
+            }
+            progress.send(step as f32 / 2.0);
+        }
+        Ok(())
+    });
+    Ok(Some(hook))
+}
+
+#[test]
+fn init_progress() {
+    let mut app = setup();
+    app.register_service::<Progress>();
+    app.world_mut().commands().spin_service_up::<Progress>();
+    for _ in 0..3 {
+        app.update();
+    }
+    // nothing is reported until the first step is allowed
+    assert_status::<Progress>(app.world(), ServiceStatus::Init);
+    assert_eq!(app.world().service::<Progress>().init_progress(), None);
+
+    PROGRESS_STEP.store(1, Ordering::Relaxed);
+    for _ in 0..10 {
+        app.update();
+        if app.world().service::<Progress>().init_progress().is_some() {
+            break;
+        }
+    }
+    assert_status::<Progress>(app.world(), ServiceStatus::Init);
+    assert_eq!(app.world().service::<Progress>().init_progress(), Some(0.5));
+    app.update();
+    assert_eq!(app.world().service::<Progress>().init_progress(), Some(0.5));
+
+    PROGRESS_STEP.store(2, Ordering::Relaxed);
+    for _ in 0..10 {
+        app.update();
+        if app.world().service::<Progress>().status().is_up() {
+            break;
+        }
+    }
+    assert_status::<Progress>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().service::<Progress>().init_progress(), None);
+}
+
+#[derive(Resource, Debug, Default)]
+struct Cancellable;
+impl Service for Cancellable {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(endless_init);
+    }
+}
+fn endless_init() -> InitResult {
+    let hook = AsyncHook::io(async |_| {
+        bevy::tasks::futures_lite::future::pending::<()>().await;
+        Ok(())
+    });
+    Ok(Some(hook))
+}
+
+#[test]
+fn cancel_init_on_spin_down() {
+    let mut app = setup();
+    app.register_service::<Cancellable>();
+    app.world_mut().commands().spin_service_up::<Cancellable>();
+    app.update();
+    assert_status::<Cancellable>(app.world(), ServiceStatus::Init);
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<Cancellable>();
+    app.update();
+    assert_status::<Cancellable>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    let world = app.world_mut();
+    assert_eq!(world.query::<&AsyncHook>().iter(world).count(), 0);
+}
+
+#[test]
+fn transitioning() {
+    let mut app = setup();
+    app.register_service::<Cancellable>()
+        .register_service::<Hooks>()
+        .init_resource::<Count>();
+    app.world_mut().commands().spin_service_up::<Cancellable>();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.update();
+    let service = app.world().service::<Cancellable>();
+    assert!(service.status().is_transitioning());
+    assert_eq!(service.pending_tasks(), 1);
+    let service = app.world().service::<Hooks>();
+    assert!(!service.status().is_transitioning());
+    assert_eq!(service.pending_tasks(), 0);
+}
+
+#[derive(Resource, Debug, Default)]
+struct Bootstrap(u32);
+
+#[test]
+fn never_started() {
+    let mut app = setup();
+    app.init_resource::<Bootstrap>()
+        .register_service::<Simple>()
+        .add_systems(
+            Update,
+            (|mut bootstrap: ResMut<Bootstrap>| bootstrap.0 += 1)
+                .run_if(service_never_started::<Simple>()),
+        );
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Bootstrap>().0, 2);
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    assert_status::<Simple>(app.world(), ServiceStatus::Up);
+    app.world_mut().commands().spin_service_down::<Simple>();
+    app.update();
+    assert_status::<Simple>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    app.update();
+    assert_eq!(app.world().resource::<Bootstrap>().0, 2);
+}
+
+#[test]
+fn command_queue() {
+    let mut app = setup();
+    app.register_service::<Hooks>().init_resource::<Count>();
+    // startup runs the lifecycle twice in one frame
+    app.update();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.world_mut().commands().spin_service_down::<Hooks>();
+    app.world_mut().commands().restart_service::<Hooks>();
+
+    // restart has the highest priority
+    app.update();
+    assert_status::<Hooks>(app.world(), ServiceStatus::Up);
+    // the service is up, so spinning down comes before spinning up
+    app.update();
+    assert_status::<Hooks>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    app.update();
+    assert_status::<Hooks>(app.world(), ServiceStatus::Up);
+    // nothing left
+    app.update();
+    assert_status::<Hooks>(app.world(), ServiceStatus::Up);
+    assert_eq!(
+        app.world().resource::<Count>(),
+        &Count {
+            up: 2,
+            init: 2,
+            down: 1,
+            deinit: 1,
+        }
+    );
+}
+
+#[test]
+fn service_by_id() {
+    #[derive(Resource)]
+    struct Seen(NodeId, Option<ServiceStatus>);
+
+    let mut app = setup();
+    app.register_service::<Simple>();
+    let id = NodeId::Service(app.world().resource_id::<Simple>().unwrap());
+    app.insert_resource(Seen(id, None)).add_systems(
+        Update,
+        |services: ServiceById, mut seen: ResMut<Seen>| {
+            seen.1 = services.get(seen.0).map(|service| service.status());
+        },
+    );
+    app.update();
+    assert!(app.world().resource::<Seen>().1.as_ref().unwrap().is_down());
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    assert_eq!(app.world().resource::<Seen>().1, Some(ServiceStatus::Up));
+}
+
+#[test]
+fn service_updated_name() {
+    #[derive(Resource, Default)]
+    struct Names(Vec<String>);
+
+    let mut app = setup();
+    app.register_service::<Simple>()
+        .init_resource::<Names>()
+        .add_systems(
+            Update,
+            |mut reader: EventReader<ServiceUpdated>, mut names: ResMut<Names>| {
+                names.0.extend(reader.read().map(|e| e.name.clone()));
+            },
+        );
+    app.update();
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    let names = &app.world().resource::<Names>().0;
+    assert!(!names.is_empty());
+    assert!(names.iter().all(|name| *name == Simple::name()));
+}
+
+#[test]
+fn spin_down_uninitialized_is_silent() {
+    #[derive(Resource, Default)]
+    struct Updates(usize);
+
+    let mut app = setup();
+    app.register_service::<Hooks>()
+        .init_resource::<Count>()
+        .init_resource::<Updates>()
+        .add_systems(
+            Update,
+            |mut reader: EventReader<ServiceUpdated>, mut updates: ResMut<Updates>| {
+                updates.0 += reader.read().count();
+            },
+        );
+    app.update();
+    assert_status::<Hooks>(app.world(), ServiceStatus::Down(DownReason::Uninitialized));
+    app.world_mut().resource_mut::<Updates>().0 = 0;
+    app.world_mut().commands().spin_service_down::<Hooks>();
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Updates>().0, 0);
+    assert_eq!(app.world().resource::<Count>(), &Count::default());
+    assert_status::<Hooks>(app.world(), ServiceStatus::Down(DownReason::Uninitialized));
+}
+
+#[derive(Resource, Default, Debug)]
+struct AsyncDown;
+impl Service for AsyncDown {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.on_down(|_: In<DownReason>, mut count: ResMut<Count>| {
+            count.down += 1;
+            Some(AsyncHook::io(async |_| Ok(())))
+        });
+    }
+}
+
+#[test]
+fn async_on_down() {
+    let mut app = setup();
+    app.init_resource::<Count>().register_service::<AsyncDown>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<AsyncDown>();
+    app.update();
+    assert_status::<AsyncDown>(app.world(), ServiceStatus::Up);
+
+    app.world_mut().commands().spin_service_down::<AsyncDown>();
+    app.update();
+    assert_eq!(app.world().resource::<Count>().down, 1);
+    assert_status::<AsyncDown>(app.world(), ServiceStatus::Deinit(DownReason::SpunDown));
+
+    app.update();
+    assert_status::<AsyncDown>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+}
+
+#[derive(States, Debug, Default, Clone, PartialEq, Eq, Hash)]
+enum Online {
+    #[default]
+    No,
+    Yes,
+}
+
+#[test]
+fn mirror_to_state() {
+    #[derive(Resource, Default)]
+    struct Entered(Vec<Online>);
+
+    let mut app = setup();
+    app.add_plugins(bevy::state::app::StatesPlugin)
+        .init_state::<Online>()
+        .init_resource::<Entered>()
+        .register_service::<Simple>()
+        .mirror_service_to_state::<Simple, _>(|status| match status {
+            ServiceStatus::Up => Some(Online::Yes),
+            ServiceStatus::Down(_) => Some(Online::No),
+            _ => None,
+        })
+        .add_systems(OnEnter(Online::Yes), |mut e: ResMut<Entered>| {
+            e.0.push(Online::Yes)
+        })
+        .add_systems(OnEnter(Online::No), |mut e: ResMut<Entered>| {
+            e.0.push(Online::No)
+        });
+    app.update();
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    assert_eq!(*app.world().resource::<State<Online>>().get(), Online::Yes);
+
+    app.world_mut().commands().spin_service_down::<Simple>();
+    app.update();
+    assert_eq!(*app.world().resource::<State<Online>>().get(), Online::No);
+    assert_eq!(
+        app.world().resource::<Entered>().0,
+        vec![Online::No, Online::Yes, Online::No]
+    );
+}
+
+#[derive(Resource, Default, Debug)]
+struct Loading;
+impl Service for Loading {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .init_with(endless_init)
+            .add_systems_when(
+                Update,
+                |mut count: ResMut<Count>| count.init += 1,
+                ServiceStatus::Init,
+            )
+            .add_systems_when(
+                Update,
+                |mut count: ResMut<Count>| count.up += 1,
+                ServiceStatus::Up,
+            );
+    }
+}
+
+#[test]
+fn systems_when_status() {
+    let mut app = setup();
+    app.init_resource::<Count>().register_service::<Loading>();
+    app.update();
+    assert_eq!(app.world().resource::<Count>().init, 0);
+
+    app.world_mut().commands().spin_service_up::<Loading>();
+    app.update();
+    app.update();
+    assert_status::<Loading>(app.world(), ServiceStatus::Init);
+    assert_eq!(app.world().resource::<Count>().init, 2);
+
+    app.world_mut().commands().spin_service_down::<Loading>();
+    app.update();
+    assert_eq!(app.world().resource::<Count>().init, 2);
+    assert_eq!(app.world().resource::<Count>().up, 0);
+}
+
+#[test]
+fn patch_service() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service::<Simple>()
+        .patch_service::<Simple>(|patch| {
+            patch.add_systems(Update, |mut count: ResMut<Count>| count.up += 1);
+        });
+    app.update();
+    assert_eq!(app.world().resource::<Count>().up, 0);
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Count>().up, 2);
+
+    app.world_mut().commands().spin_service_down::<Simple>();
+    app.update();
+    assert_eq!(app.world().resource::<Count>().up, 2);
+}
+
+#[derive(Resource, Default, Debug)]
+struct Guarded;
+impl Service for Guarded {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.on_transition(|In((from, to)): In<(ServiceStatus, ServiceStatus)>| {
+            !(from.is_up() && to.is_deinitializing())
+        });
+    }
+}
+
+#[test]
+fn transition_guard() {
+    let mut app = setup();
+    app.register_service::<Guarded>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<Guarded>();
+    app.update();
+    assert_status::<Guarded>(app.world(), ServiceStatus::Up);
+
+    app.world_mut().commands().spin_service_down::<Guarded>();
+    app.update();
+    assert_status::<Guarded>(app.world(), ServiceStatus::Up);
+
+    // failures bypass the guard
+    app.world_mut()
+        .commands()
+        .fail_service::<Guarded>(ServiceError::own("oh no"));
+    app.update();
+    assert!(app.world().service::<Guarded>().status().is_failed());
+}
+
+#[derive(Resource, Default, Debug)]
+struct Network;
+impl Service for Network {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.with_tag("network").with_tag("io");
+    }
+}
+#[derive(Resource, Default, Debug)]
+struct Socket;
+impl Service for Socket {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.with_tag("network");
+    }
+}
+
+#[test]
+fn services_with_tag() {
+    let mut app = setup();
+    app.register_service::<Simple>()
+        .register_service::<Network>()
+        .register_service::<Socket>();
+    let world = app.world();
+    let network = world.service::<Network>().id();
+    let socket = world.service::<Socket>().id();
+    let tagged = world.services_with_tag("network");
+    assert_eq!(tagged.len(), 2);
+    assert!(tagged.contains(&network) && tagged.contains(&socket));
+    assert_eq!(world.services_with_tag("io"), vec![network]);
+    assert!(world.services_with_tag("storage").is_empty());
+}
+
+mod audio {
+    use super::*;
+    #[derive(Resource, Default, Debug)]
+    pub struct Backend;
+    impl Service for Backend {
+        fn build(scope: &mut ServiceScope<Self>) {
+            scope.with_name("AudioBackend");
+        }
+    }
+}
+mod render {
+    use super::*;
+    #[derive(Resource, Default, Debug)]
+    pub struct Backend;
+    impl Service for Backend {
+        fn build(scope: &mut ServiceScope<Self>) {
+            scope.with_name("RenderBackend");
+        }
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+struct DisplayNames(Vec<String>);
+
+#[test]
+fn name_override() {
+    let mut app = setup();
+    app.register_service::<audio::Backend>()
+        .register_service::<render::Backend>()
+        .add_systems(
+            Update,
+            |mut reader: EventReader<ServiceUpdated>, mut names: ResMut<DisplayNames>| {
+                names
+                    .0
+                    .extend(reader.read().map(|event| event.name.clone()));
+            },
+        )
+        .init_resource::<DisplayNames>();
+    app.update();
+    assert_eq!(audio::Backend::name(), render::Backend::name());
+    assert_eq!(
+        app.world().service::<audio::Backend>().name(),
+        "AudioBackend"
+    );
+    assert_eq!(
+        app.world().service::<render::Backend>().name(),
+        "RenderBackend"
+    );
+
+    app.world_mut()
+        .commands()
+        .spin_service_up::<audio::Backend>();
+    app.update();
+    assert!(
+        app.world()
+            .resource::<DisplayNames>()
+            .0
+            .iter()
+            .all(|name| name == "AudioBackend")
+    );
+    assert!(!app.world().resource::<DisplayNames>().0.is_empty());
+}
+
+#[test]
+fn status_ordering() {
+    let failed = ServiceStatus::failed(ServiceError::own("oh no"));
+    let ordered = [
+        ServiceStatus::uninit(),
+        ServiceStatus::down(),
+        failed.clone(),
+        ServiceStatus::deinit(),
+        ServiceStatus::failing(ServiceError::own("oh no")),
+        ServiceStatus::Init,
+        ServiceStatus::Standby,
+        ServiceStatus::Up,
+    ];
+    for pair in ordered.windows(2) {
+        assert!(pair[0] < pair[1], "{:?} < {:?}", pair[0], pair[1]);
+    }
+    let mut shuffled = ordered.to_vec();
+    shuffled.reverse();
+    shuffled.sort();
+    assert_eq!(shuffled, ordered);
+    assert_eq!(failed.cmp(&failed.clone()), std::cmp::Ordering::Equal);
+    assert!(ServiceStatus::Up >= ServiceStatus::Up);
+    assert!(failed < ServiceStatus::Init);
+}
+
+#[derive(Resource, Debug, Default)]
+struct NeverInit;
+impl Service for NeverInit {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(endless_init).stuck_warning_after(5);
+    }
+}
+
+#[test]
+fn stuck_warning() {
+    let mut app = setup();
+    app.register_service::<NeverInit>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<NeverInit>();
+    for _ in 0..4 {
+        app.update();
+        assert!(!app.world().service::<NeverInit>().is_stuck());
+    }
+    app.update();
+    assert_status::<NeverInit>(app.world(), ServiceStatus::Init);
+    assert!(app.world().service::<NeverInit>().is_stuck());
+
+    app.world_mut().commands().spin_service_down::<NeverInit>();
+    app.update();
+    assert!(!app.world().service::<NeverInit>().is_stuck());
+}
+
+#[test]
+fn registry_order() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service::<Hooks>()
+        .register_service::<NoDupes>()
+        .register_service::<Cancellable>()
+        .register_service::<NoDupes>();
+    let names = app
+        .world()
+        .registry()
+        .iter()
+        .map(|(_, name)| name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![Hooks::name(), NoDupes::name(), Cancellable::name()]
+    );
+    let id = app.world().service::<NoDupes>().id();
+    assert_eq!(app.world().registry()[1].0, id);
+}
+
+#[derive(Clone)]
+struct Retries(u32);
+
+#[derive(Resource, Debug, Default)]
+struct Configured;
+impl Service for Configured {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with_config(
+            Retries(3),
+            |retries: In<Retries>, mut count: ResMut<Count>| {
+                count.init = retries.0.0;
+                Ok(None)
+            },
+        );
+    }
+}
+
+#[test]
+fn init_with_config() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service::<Configured>();
+    app.world_mut().commands().spin_service_up::<Configured>();
+    app.update();
+    assert_status::<Configured>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().init, 3);
+    let config = app.world().resource::<ServiceConfig<Configured, Retries>>();
+    assert_eq!(config.0, 3);
+}
+
+#[derive(Resource, Clone)]
+struct RetryConfig(u32);
+
+#[derive(Resource)]
+struct RetryBudget(u32);
+
+#[derive(Resource, Debug, Default)]
+struct ResourceConfigured;
+impl Service for ResourceConfigured {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .init_with(init_from_resource(
+                |config: In<RetryConfig>, mut commands: Commands| {
+                    commands.insert_resource(RetryBudget(config.0.0 * 2));
+                    Ok(None)
+                },
+            ))
+            .deinit_with(deinit_remove_resource::<RetryBudget>());
+    }
+}
+
+#[test]
+fn init_from_resource_config() {
+    let mut app = setup();
+    app.register_service::<ResourceConfigured>();
+    // without the config, init fails
+    app.world_mut()
+        .commands()
+        .spin_service_up::<ResourceConfigured>();
+    app.update();
+    assert!(
+        app.world()
+            .service::<ResourceConfigured>()
+            .status()
+            .is_failed()
+    );
+
+    app.insert_resource(RetryConfig(3));
+    app.world_mut()
+        .commands()
+        .spin_service_up::<ResourceConfigured>();
+    app.update();
+    assert_status::<ResourceConfigured>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<RetryBudget>().0, 6);
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<ResourceConfigured>();
+    app.update();
+    assert_status::<ResourceConfigured>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    assert!(!app.world().contains_resource::<RetryBudget>());
+}
+
+#[derive(Resource, Debug, Default)]
+struct Announced;
+impl Service for Announced {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .on_enter(ServiceStatus::Up, |mut count: ResMut<Count>| count.up += 1)
+            .on_exit(ServiceStatus::Up, |mut count: ResMut<Count>| {
+                count.down += 1
+            });
+    }
+}
+
+#[test]
+fn status_observers() {
+    let mut app = setup();
+    app.init_resource::<Count>().register_service::<Announced>();
+    app.update();
+    for _ in 0..2 {
+        app.world_mut().commands().spin_service_up::<Announced>();
+        app.update();
+        assert_status::<Announced>(app.world(), ServiceStatus::Up);
+        app.world_mut().commands().spin_service_down::<Announced>();
+        app.update();
+    }
+    let count = app.world().resource::<Count>();
+    assert_eq!(count.up, 2);
+    assert_eq!(count.down, 2);
+}
+
+#[test]
+fn service_diagnostics() {
+    let mut app = setup();
+    app.add_plugins(ServiceDiagnosticsPlugin)
+        .register_service::<Simple>()
+        .register_service::<Configured>()
+        .register_service::<NeverInit>()
+        .register_service::<HookFailure>();
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.world_mut().commands().spin_service_up::<NeverInit>();
+    app.update();
+    app.update();
+
+    let store = app.world().resource::<DiagnosticsStore>();
+    let value = |path| store.get(&path).and_then(|d| d.value());
+    assert_eq!(value(ServiceDiagnosticsPlugin::SERVICES_UP), Some(1.0));
+    assert_eq!(
+        value(ServiceDiagnosticsPlugin::SERVICES_INITIALIZING),
+        Some(1.0)
+    );
+    assert_eq!(value(ServiceDiagnosticsPlugin::SERVICES_DOWN), Some(1.0));
+    assert_eq!(value(ServiceDiagnosticsPlugin::SERVICES_FAILED), Some(1.0));
+}
+
+#[derive(Resource, Default, Debug)]
+struct LastDownReason(Option<DownReason>);
+
+#[derive(Resource, Default, Debug)]
+struct Evictable;
+impl Service for Evictable {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.on_down(|reason: In<DownReason>, mut last: ResMut<LastDownReason>| {
+            last.0 = Some(reason.0);
+        });
+    }
+}
+
+#[test]
+fn custom_down_reason() {
+    let mut app = setup();
+    app.init_resource::<LastDownReason>()
+        .register_service::<Evictable>();
+    app.world_mut().commands().spin_service_up::<Evictable>();
+    app.update();
+    app.world_mut()
+        .commands()
+        .spin_service_down_with_reason::<Evictable>("Evicted");
+    app.update();
+    let reason = DownReason::Custom("Evicted".into());
+    assert_status::<Evictable>(app.world(), ServiceStatus::Down(reason.clone()));
+    assert_eq!(app.world().resource::<LastDownReason>().0, Some(reason));
+}
+
+#[derive(Resource, Default, Debug)]
+struct Changes(u32);
+
+#[test]
+fn status_changed_condition() {
+    let mut app = setup();
+    app.init_resource::<Changes>()
+        .register_service::<Simple>()
+        .add_systems(
+            Update,
+            (|mut changes: ResMut<Changes>| changes.0 += 1)
+                .run_if(service_status_changed::<Simple>()),
+        );
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Changes>().0, 0);
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Changes>().0, 1);
+
+    app.world_mut().commands().spin_service_down::<Simple>();
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Changes>().0, 2);
+}
+
+#[derive(Resource, Default, Debug)]
+struct Ready(Arc<AtomicBool>);
+
+#[derive(Resource, Default, Debug)]
+struct Polling;
+impl Service for Polling {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .init_with(|ready: Res<Ready>| {
+                let ready = ready.0.clone();
+                let hook = AsyncHook::io(async move |_| {
+                    while !ready.load(Ordering::Relaxed) {
+                        bevy::tasks::futures_lite::future::yield_now().await;
+                    }
+                    Ok(())
+                });
+                Ok(Some(hook))
+            })
+            .add_init_systems(Update, |mut count: ResMut<Count>, ready: Res<Ready>| {
+                count.init += 1;
+                if count.init == 3 {
+                    ready.0.store(true, Ordering::Relaxed);
+                }
+            })
+            .add_systems(Update, |mut count: ResMut<Count>| count.up += 1);
+    }
+}
+
+#[test]
+fn init_systems() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<Ready>()
+        .register_service::<Polling>();
+    app.world_mut().commands().spin_service_up::<Polling>();
+    while !app.world().service::<Polling>().status().is_up() {
+        app.update();
+    }
+    let init = app.world().resource::<Count>().init;
+    assert!(init >= 3);
+    // the service came up in PreUpdate
+    assert_eq!(app.world().resource::<Count>().up, 1);
+
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Count>().init, init);
+    assert_eq!(app.world().resource::<Count>().up, 3);
+}
+
+#[test]
+fn snapshot_and_restore() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<LastDownReason>()
+        .register_service::<NoDupes>()
+        .register_service::<Configured>()
+        .register_service::<Evictable>()
+        .register_service::<Simple>();
+    app.world_mut().commands().spin_service_up::<Configured>();
+    app.world_mut().commands().spin_service_up::<Evictable>();
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    app.world_mut().commands().spin_service_down::<Configured>();
+    app.world_mut()
+        .commands()
+        .spin_service_down_with_reason::<Evictable>("Evicted");
+    app.world_mut()
+        .commands()
+        .fail_service::<Simple>(ServiceError::own("oh no"));
+    app.update();
+    let snapshot = app.world().snapshot_statuses();
+    assert_eq!(snapshot.len(), 4);
+    assert_eq!(
+        snapshot[&app.world().service::<NoDupes>().id()],
+        ServiceStatus::Up
+    );
+
+    app.world_mut().commands().spin_all_services_down();
+    app.world_mut().commands().spin_service_up::<Configured>();
+    app.world_mut().commands().spin_service_up::<Evictable>();
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    assert_ne!(app.world().snapshot_statuses(), snapshot);
+
+    app.world_mut().restore_statuses(snapshot.clone());
+    app.update();
+    assert_eq!(app.world().snapshot_statuses(), snapshot);
+}
+
+#[derive(Resource, Default, Debug)]
+struct SlowStartup;
+impl Service for SlowStartup {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(slow_init).is_startup(true);
+    }
+}
+
+#[derive(Resource, Default, Debug)]
+struct Settled {
+    times: u32,
+    failed: Vec<NodeId>,
+}
+
+#[test]
+fn all_services_settled() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<Settled>()
+        .register_service::<NoDupes>()
+        .register_service::<HookFailure>()
+        .register_service::<SlowStartup>()
+        .add_systems(
+            Update,
+            |mut settled: ResMut<Settled>,
+             mut all: EventReader<AllServicesSettled>,
+             mut failed: EventReader<SomeServicesFailed>| {
+                settled.times += all.read().count() as u32;
+                settled
+                    .failed
+                    .extend(failed.read().flat_map(|e| e.0.clone()));
+            },
+        );
+    app.update();
+    assert_status::<SlowStartup>(app.world(), ServiceStatus::Init);
+    assert_eq!(app.world().resource::<Settled>().times, 0);
+
+    while !app.world().service::<SlowStartup>().status().is_up() {
+        app.update();
+    }
+    app.update();
+    app.update();
+    let settled = app.world().resource::<Settled>();
+    assert_eq!(settled.times, 1);
+    assert_eq!(
+        settled.failed,
+        vec![app.world().service::<HookFailure>().id()]
+    );
+}
+
+#[derive(Resource, Default, Debug)]
+struct Reentrant;
+impl Service for Reentrant {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.on_up(|world: &mut World| {
+            world.service_scope::<Reentrant, _>(|_, _| {});
+            Ok(())
+        });
+    }
+}
+
+#[test]
+fn reentrant_service_scope() {
+    let res = std::panic::catch_unwind(|| {
+        let mut app = setup();
+        app.register_service::<Reentrant>();
+        app.world_mut().commands().spin_service_up::<Reentrant>();
+        app.update();
+    });
+    let message = *res.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains("Reentrant is already in a service_scope"));
+}
+
+#[test]
+fn service_ready() {
+    let mut app = setup();
+    app.register_service::<Simple>();
+    app.update();
+    let ready = app.world_mut().service_ready::<Simple>();
+    assert!(!ready.is_resolved());
+    // awaited from outside of bevy
+    let waiter = std::thread::spawn(|| bevy::tasks::block_on(ready));
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    assert!(waiter.join().unwrap());
+
+    // already up
+    assert!(app.world_mut().service_ready::<Simple>().is_resolved());
+}
+
+#[derive(Resource, Default, Debug)]
+struct NeverReady;
+impl Service for NeverReady {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|| Err(ServiceError::own("never ready").into()));
+    }
+}
+
+#[test]
+fn service_ready_resolves_false_when_down() {
+    let mut app = setup();
+    app.register_service::<NeverReady>()
+        .register_service::<PendingIo>();
+    app.update();
+
+    let ready = app.world_mut().service_ready::<NeverReady>();
+    app.world_mut().commands().spin_service_up::<NeverReady>();
+    app.update();
+    status_matches!(
+        app.world(),
+        NeverReady,
+        ServiceStatus::Down(DownReason::Failed(_))
+    );
+    assert!(ready.is_resolved());
+    assert!(!bevy::tasks::block_on(ready));
+
+    app.world_mut().commands().spin_service_up::<PendingIo>();
+    app.update();
+    let ready = app.world_mut().service_ready::<PendingIo>();
+    app.world_mut().commands().force_service_down::<PendingIo>();
+    app.update();
+    assert!(!bevy::tasks::block_on(ready));
+}
+
+#[derive(Resource, Default, Debug)]
+struct InitOrder(Vec<String>);
+fn record_init<T: Service>(mut order: ResMut<InitOrder>) -> InitResult {
+    order.0.push(T::name());
+    Ok(None)
+}
+
+#[derive(Resource, Default, Debug)]
+struct BootLast;
+impl Service for BootLast {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(record_init::<Self>).startup_priority(10);
+    }
+}
+#[derive(Resource, Default, Debug)]
+struct BootFirst;
+impl Service for BootFirst {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(record_init::<Self>).startup_priority(-10);
+    }
+}
+
+#[test]
+fn startup_priority() {
+    let mut app = setup();
+    app.init_resource::<InitOrder>()
+        .register_service::<BootFirst>()
+        .register_service::<BootLast>();
+    app.update();
+    assert_eq!(
+        app.world().resource::<InitOrder>().0,
+        vec![BootFirst::name(), BootLast::name()]
+    );
+}
+
+#[derive(Resource, Default, Debug)]
+struct PrioA;
+impl Service for PrioA {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(record_init::<Self>).startup_priority(1);
+    }
+}
+#[derive(Resource, Default, Debug)]
+struct PrioB;
+impl Service for PrioB {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(record_init::<Self>).startup_priority(2);
+    }
+}
+#[derive(Resource, Default, Debug)]
+struct PrioC;
+impl Service for PrioC {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<PrioB>()
+            .init_with(record_init::<Self>)
+            .startup_priority(0);
+    }
+}
+
+#[test]
+fn startup_priority_against_deps() {
+    let mut app = setup();
+    app.init_resource::<InitOrder>()
+        .register_service::<PrioA>()
+        .register_service::<PrioB>()
+        .register_service::<PrioC>();
+    app.update();
+    // PrioC comes first, and brings its dep up with it
+    assert_eq!(
+        app.world().resource::<InitOrder>().0,
+        vec![PrioB::name(), PrioC::name(), PrioA::name()]
+    );
+}
+
+#[derive(Resource, Debug, PartialEq)]
+struct Loaded(u32);
+#[derive(Resource, Debug, PartialEq)]
+struct Doubled(u32);
+
+#[derive(Resource, Default, Debug)]
+struct CompletesTask;
+impl Service for CompletesTask {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|| {
+            let task = AsyncHook::async_compute(async |queue: &mut CommandQueue| {
+                queue.push(|world: &mut World| world.insert_resource(Loaded(21)));
+                Ok(())
+            })
+            .on_complete(|loaded: Res<Loaded>, mut commands: Commands| {
+                commands.insert_resource(Doubled(loaded.0 * 2));
+            });
+            Ok(Some(task))
+        });
+    }
+}
+
+#[test]
+fn task_on_complete() {
+    let mut app = setup();
+    app.register_service::<CompletesTask>();
+    let status = app.spin_service_up_blocking::<CompletesTask>(Duration::from_secs(1));
+    assert!(status.is_up());
+    assert_eq!(app.world().get_resource::<Doubled>(), Some(&Doubled(42)));
+}
+
+#[derive(Resource, Default, Debug)]
+struct Gate(Arc<AtomicBool>);
+
+#[derive(Resource, Default, Debug)]
+struct Gated;
+impl Service for Gated {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|gate: Res<Gate>, mut count: ResMut<Count>| {
+            count.init += 1;
+            let gate = gate.0.clone();
+            let hook = AsyncHook::io(async move |_| {
+                while !gate.load(Ordering::Relaxed) {
+                    bevy::tasks::futures_lite::future::yield_now().await;
+                }
+                Ok(())
+            });
+            Ok(Some(hook))
+        });
+    }
+}
+
+#[test]
+fn coalesce_restarts() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<Gate>()
+        .register_service::<Gated>();
+    app.update();
+
+    app.world_mut().commands().restart_service::<Gated>();
+    app.world_mut().commands().restart_service::<Gated>();
+    app.update();
+    assert!(app.world().service::<Gated>().status().is_initializing());
+    // a restart mid-restart joins the pending one
+    app.world_mut().commands().restart_service::<Gated>();
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<Count>().init, 1);
+    assert_eq!(app.world().service::<Gated>().pending_tasks(), 1);
+
+    // spinning down mid-restart cancels it
+    app.world_mut().commands().spin_service_down::<Gated>();
+    app.update();
+    assert_status::<Gated>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    assert_eq!(app.world().service::<Gated>().pending_tasks(), 0);
+
+    app.world()
+        .resource::<Gate>()
+        .0
+        .store(true, Ordering::Relaxed);
+    app.world_mut().commands().restart_service::<Gated>();
+    app.update();
+    let status = app.spin_service_up_blocking::<Gated>(Duration::from_secs(1));
+    assert!(status.is_up());
+    assert_eq!(app.world().resource::<Count>().init, 2);
+}
+
+#[test]
+fn pending_commands() {
+    let mut app = setup();
+    app.register_service::<Hooks>().init_resource::<Count>();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.update();
+    assert!(app.world().pending_commands::<Hooks>().is_empty());
+
+    app.world_mut().commands().spin_service_down::<Hooks>();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.world_mut().flush();
+    assert_eq!(
+        app.world().pending_commands::<Hooks>(),
+        vec![LifecycleCommandKind::SpinDown, LifecycleCommandKind::SpinUp]
+    );
+    // the service is up, so spinning down goes first
+    app.update();
+    assert!(app.world().service::<Hooks>().status().is_down());
+    assert_eq!(
+        app.world().pending_commands::<Hooks>(),
+        vec![LifecycleCommandKind::SpinUp]
+    );
+    app.update();
+    assert!(app.world().pending_commands::<Hooks>().is_empty());
+}
+
+#[test]
+fn up_down_up_in_one_frame() {
+    let mut app = setup();
+    app.register_service::<Hooks>().init_resource::<Count>();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.world_mut().commands().spin_service_down::<Hooks>();
+    app.world_mut().commands().spin_service_up::<Hooks>();
+    app.world_mut().flush();
+    assert_eq!(
+        app.world().pending_commands::<Hooks>(),
+        vec![
+            LifecycleCommandKind::SpinUp,
+            LifecycleCommandKind::SpinDown,
+            LifecycleCommandKind::SpinUp
+        ]
+    );
+    for _ in 0..3 {
+        app.update();
+    }
+    assert!(app.world().pending_commands::<Hooks>().is_empty());
+    assert_status::<Hooks>(app.world(), ServiceStatus::Up);
+    let count = app.world().resource::<Count>();
+    assert_eq!(count.up, 2);
+    assert_eq!(count.down, 1);
+}
+
+#[derive(Resource, Default, Debug)]
+struct Transaction(bool);
+
+#[derive(Resource, Default, Debug)]
+struct Inhibited;
+impl Service for Inhibited {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.can_spin_down(|transaction: Res<Transaction>| !transaction.0);
+    }
+}
+
+#[test]
+fn can_spin_down() {
+    let mut app = setup();
+    app.insert_resource(Transaction(true))
+        .register_service::<Inhibited>();
+    app.world_mut().commands().spin_service_up::<Inhibited>();
+    app.update();
+
+    app.world_mut().commands().spin_service_down::<Inhibited>();
+    for _ in 0..3 {
+        app.update();
+        assert_status::<Inhibited>(app.world(), ServiceStatus::Up);
+    }
+    assert_eq!(
+        app.world().pending_commands::<Inhibited>(),
+        vec![LifecycleCommandKind::SpinDown]
+    );
+
+    app.world_mut().resource_mut::<Transaction>().0 = false;
+    app.update();
+    assert_status::<Inhibited>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    assert!(app.world().pending_commands::<Inhibited>().is_empty());
+
+    // failures ignore the guard
+    app.world_mut().resource_mut::<Transaction>().0 = true;
+    app.world_mut().commands().spin_service_up::<Inhibited>();
+    app.update();
+    app.world_mut()
+        .commands()
+        .fail_service::<Inhibited>(ServiceError::own("oh no"));
+    app.update();
+    assert!(app.world().service::<Inhibited>().status().is_failed());
+}
+
+#[derive(Resource, Debug)]
+struct Listener {
+    port: u16,
+}
+impl Service for Listener {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct NeedsListener;
+impl Service for NeedsListener {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Listener>();
+    }
+}
+
+#[test]
+fn register_service_with() {
+    let mut app = setup();
+    app.register_service::<NeedsListener>()
+        .register_service_with(|| Listener { port: 8080 });
+    app.world_mut()
+        .commands()
+        .spin_service_up::<NeedsListener>();
+    app.update();
+    assert_status::<Listener>(app.world(), ServiceStatus::Up);
+    assert_status::<NeedsListener>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Listener>().port, 8080);
+}
+
+#[test]
+fn stable_service_key() {
+    let mut first = setup();
+    first.register_service::<Simple>();
+    // shift the component ids in the second app
+    let mut second = setup();
+    second
+        .register_service_with(|| Listener { port: 0 })
+        .register_service::<NeedsListener>()
+        .register_service::<Simple>();
+
+    let key = first.world().service::<Simple>().key().clone();
+    assert_eq!(&key, second.world().service::<Simple>().key());
+    assert_eq!(key, ServiceKey::of::<Simple>());
+    assert_ne!(
+        first.world().service::<Simple>().id(),
+        second.world().service::<Simple>().id()
+    );
+    for app in [&first, &second] {
+        assert_eq!(
+            app.world().key_to_node_id(key.clone()),
+            Some(app.world().service::<Simple>().id())
+        );
+    }
+    assert_eq!(
+        first
+            .world()
+            .key_to_node_id(ServiceKey::of::<NeedsListener>()),
+        None
+    );
+}
+
+#[derive(Resource, Debug, Default)]
+struct Left;
+impl Service for Left {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct Right;
+impl Service for Right {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct Both(u32, u32);
+
+#[test]
+fn multiple_services_up() {
+    let mut app = setup();
+    app.register_service::<Left>()
+        .register_service::<Right>()
+        .init_resource::<Both>()
+        .add_systems(
+            Update,
+            (
+                (|mut both: ResMut<Both>| both.0 += 1).run_if(all_services_up!(Left, Right)),
+                (|mut both: ResMut<Both>| both.1 += 1).run_if(any_service_up!(Left, Right)),
+            ),
+        );
+    app.update();
+    assert_eq!(app.world().resource::<Both>().0, 0);
+    assert_eq!(app.world().resource::<Both>().1, 0);
+
+    app.world_mut().commands().spin_service_up::<Left>();
+    app.update();
+    assert_eq!(app.world().resource::<Both>().0, 0);
+    assert_eq!(app.world().resource::<Both>().1, 1);
+
+    app.world_mut().commands().spin_service_up::<Right>();
+    app.update();
+    assert_eq!(app.world().resource::<Both>().0, 1);
+    assert_eq!(app.world().resource::<Both>().1, 2);
+}
+
+#[derive(Resource, Debug, Default)]
+struct RanById(u32);
+
+#[test]
+fn run_condition_by_id() {
+    let mut app = setup();
+    app.register_service::<Simple>().init_resource::<RanById>();
+    let id = app.world().service::<Simple>().id();
+    app.add_systems(
+        Update,
+        (|mut ran: ResMut<RanById>| ran.0 += 1)
+            .run_if(service_has_status_id(id, ServiceStatus::Up)),
+    );
+    app.update();
+    assert_eq!(app.world().resource::<RanById>().0, 0);
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    assert_eq!(app.world().resource::<RanById>().0, 1);
+}
+
+#[derive(Resource, Debug, Default)]
+struct StatusTicks {
+    seen: Tick,
+    invalidations: u32,
+}
+
+#[test]
+fn status_change_tick() {
+    let mut app = setup();
+    app.register_service::<Simple>()
+        .init_resource::<StatusTicks>()
+        .add_systems(
+            Update,
+            |service: ServiceRef<Simple>, mut ticks: ResMut<StatusTicks>| {
+                if service.changed_since(ticks.seen) {
+                    ticks.seen = service.last_status_change();
+                    ticks.invalidations += 1;
+                }
+            },
+        );
+    app.update();
+    let registered = app.world().service::<Simple>().last_status_change();
+    assert_eq!(app.world().resource::<StatusTicks>().invalidations, 1);
+
+    app.update();
+    app.update();
+    assert_eq!(
+        app.world().service::<Simple>().last_status_change(),
+        registered
+    );
+    assert_eq!(app.world().resource::<StatusTicks>().invalidations, 1);
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    let up = app.world().service::<Simple>().last_status_change();
+    assert!(up.is_newer_than(registered, app.world().read_change_tick()));
+    assert_eq!(app.world().resource::<StatusTicks>().invalidations, 2);
+
+    app.update();
+    assert_eq!(app.world().service::<Simple>().last_status_change(), up);
+    assert_eq!(app.world().resource::<StatusTicks>().invalidations, 2);
+}
+
+#[derive(Resource, Default, Debug)]
+struct AfterGate;
+impl Service for AfterGate {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Gated>().on_up(count_up);
+    }
+}
+
+#[test]
+fn deferred_on_up() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<Gate>()
+        .register_service::<Gated>()
+        .register_service::<AfterGate>();
+    app.world_mut().commands().spin_service_up::<AfterGate>();
+    app.update();
+    app.update();
+    // waiting on the dep
+    assert!(
+        app.world()
+            .service::<AfterGate>()
+            .status()
+            .is_initializing()
+    );
+    assert_eq!(app.world().resource::<Count>().up, 0);
+
+    app.world()
+        .resource::<Gate>()
+        .0
+        .store(true, Ordering::Relaxed);
+    while !app.world().service::<AfterGate>().status().is_up() {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Count>().up, 1);
+}
+
+#[derive(Resource)]
+struct NeverInserted;
+fn init_never_inserted(_: Res<NeverInserted>) -> InitResult {
+    Ok(None)
+}
+#[derive(Resource, Default, Debug)]
+struct InvalidInit;
+impl Service for InvalidInit {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(init_never_inserted);
+    }
+}
+
+#[test]
+fn invalid_hook_params_fail() {
+    let mut app = setup();
+    app.register_service::<InvalidInit>();
+    app.world_mut().commands().spin_service_up::<InvalidInit>();
+    app.update();
+    let ServiceStatus::Down(DownReason::Failed(error)) =
+        app.world().service::<InvalidInit>().status()
+    else {
+        panic!("InvalidInit should have failed");
+    };
+    assert!(error.to_string().contains("couldn't run hook"), "{error}");
+}
+
+#[derive(Resource, Default, Debug)]
+struct GatedDown;
+impl Service for GatedDown {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.deinit_with(|gate: Res<Gate>| {
+            let gate = gate.0.clone();
+            let hook = AsyncHook::io(async move |_| {
+                while !gate.load(Ordering::Relaxed) {
+                    bevy::tasks::futures_lite::future::yield_now().await;
+                }
+                Ok(())
+            });
+            Ok(Some(hook))
+        });
+    }
+}
+#[derive(Resource, Default, Debug)]
+struct BeforeGatedDown;
+impl Service for BeforeGatedDown {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<GatedDown>().on_down(count_down);
+    }
+}
+
+#[test]
+fn deferred_on_down() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .init_resource::<Gate>()
+        .register_service::<GatedDown>()
+        .register_service::<BeforeGatedDown>();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<BeforeGatedDown>();
+    app.update();
+    assert_status::<BeforeGatedDown>(app.world(), ServiceStatus::Up);
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<BeforeGatedDown>();
+    app.update();
+    app.update();
+    // waiting on the dep
+    assert!(
+        app.world()
+            .service::<BeforeGatedDown>()
+            .status()
+            .is_deinitializing()
+    );
+    assert_eq!(app.world().resource::<Count>().down, 0);
+
+    app.world()
+        .resource::<Gate>()
+        .0
+        .store(true, Ordering::Relaxed);
+    while !app.world().service::<BeforeGatedDown>().status().is_down() {
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Count>().down, 1);
+}
+
+fn gated_init(gate: Res<Gate>) -> InitResult {
+    let gate = gate.0.clone();
+    let hook = AsyncHook::io(async move |_| {
+        while !gate.load(Ordering::Relaxed) {
+            bevy::tasks::futures_lite::future::yield_now().await;
+        }
+        Ok(())
+    });
+    Ok(Some(hook))
+}
+
+#[derive(Resource, Default, Debug)]
+struct SlowPoll;
+impl Service for SlowPoll {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .task_poll_interval(Duration::from_secs(3600))
+            .init_with(gated_init);
+    }
+}
+#[derive(Resource, Default, Debug)]
+struct FastPoll;
+impl Service for FastPoll {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .task_poll_interval(Duration::from_millis(5))
+            .init_with(gated_init);
+    }
+}
+
+#[test]
+fn task_poll_interval() {
+    let mut app = setup();
+    app.init_resource::<Gate>()
+        .register_service::<SlowPoll>()
+        .register_service::<FastPoll>();
+    app.world_mut().commands().spin_service_up::<SlowPoll>();
+    app.world_mut().commands().spin_service_up::<FastPoll>();
+    // polled once as the task starts
+    app.update();
+    assert_eq!(app.world().service::<SlowPoll>().task_polls(), 1);
+    assert_eq!(app.world().service::<FastPoll>().task_polls(), 1);
+
+    app.world()
+        .resource::<Gate>()
+        .0
+        .store(true, Ordering::Relaxed);
+    // the task is done, but isn't polled again until the interval is up
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(app.world().service::<SlowPoll>().task_polls(), 1);
+    assert!(app.world().service::<SlowPoll>().status().is_initializing());
+
+    busy_wait(5);
+    app.update();
+    assert!(app.world().service::<FastPoll>().task_polls() > 1);
+    for _ in 0..10 {
+        if app.world().service::<FastPoll>().status().is_up() {
+            break;
+        }
+        busy_wait(5);
+        app.update();
+    }
+    assert_status::<FastPoll>(app.world(), ServiceStatus::Up);
+}
+
+#[derive(Resource, Debug, Default)]
+struct PanicsOnInit;
+impl Service for PanicsOnInit {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.catch_hook_panics(true).init_with(|| -> InitResult {
+            panic!("oh no");
+        });
+    }
+}
+
+#[test]
+fn catch_hook_panics() {
+    let mut app = setup();
+    app.register_service::<PanicsOnInit>()
+        .register_service::<Simple>();
+    app.world_mut().commands().spin_service_up::<PanicsOnInit>();
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    let ServiceStatus::Down(DownReason::Failed(ServiceError::Own(message, _))) =
+        app.world().service::<PanicsOnInit>().status()
+    else {
+        panic!("PanicsOnInit should have failed");
+    };
+    assert!(message.contains("hook panicked: oh no"), "{message}");
+    // the rest of the app keeps going
+    status_matches!(app.world(), Simple, ServiceStatus::Up);
+    app.update();
+}
+
+#[test]
+fn lost_hook_stays_failed() {
+    let mut app = setup();
+    app.register_service::<PanicsOnInit>();
+    app.world_mut().commands().spin_service_up::<PanicsOnInit>();
+    app.update();
+    status_matches!(
+        app.world(),
+        PanicsOnInit,
+        ServiceStatus::Down(DownReason::Failed(_))
+    );
+
+    // the panicking hook was dropped, so it can't be retried
+    app.world_mut().commands().spin_service_up::<PanicsOnInit>();
+    app.update();
+    let ServiceStatus::Down(DownReason::Failed(error)) =
+        app.world().service::<PanicsOnInit>().status()
+    else {
+        panic!("PanicsOnInit should have stayed failed");
+    };
+    assert!(error.to_string().contains("hook was lost"), "{error}");
+}
+
+#[derive(Resource)]
+struct LateConfig;
+#[derive(Resource, Debug, Default)]
+struct NeedsLateConfig;
+impl Service for NeedsLateConfig {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.on_up(|_: Res<LateConfig>| Ok(()));
+    }
+}
+
+#[test]
+fn register_when_resource() {
+    let mut app = setup();
+    app.register_service_when_resource::<NeedsLateConfig, LateConfig>();
+    app.update();
+    app.update();
+    assert!(app.world().get_service::<NeedsLateConfig>().is_none());
+
+    app.insert_resource(LateConfig);
+    app.update();
+    assert!(app.world().get_service::<NeedsLateConfig>().is_some());
+    app.world_mut()
+        .commands()
+        .spin_service_up::<NeedsLateConfig>();
+    app.update();
+    status_matches!(app.world(), NeedsLateConfig, ServiceStatus::Up);
+}
+
+#[derive(Resource, Default)]
+struct LateFrames(u32);
+#[derive(Resource, Debug, Default)]
+struct CountsLateFrames;
+impl Service for CountsLateFrames {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_systems(First, |mut frames: ResMut<LateFrames>| frames.0 += 1);
+    }
+}
+
+#[test]
+fn register_when_resource_adds_systems() {
+    let mut app = setup();
+    app.init_resource::<LateFrames>()
+        .register_service_when_resource::<CountsLateFrames, LateConfig>();
+    app.update();
+    app.insert_resource(LateConfig);
+    app.update();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<CountsLateFrames>();
+    app.update();
+    status_matches!(app.world(), CountsLateFrames, ServiceStatus::Up);
+    app.update();
+    // the system was added to First while the service was being registered,
+    // and has run once since the service came up
+    assert_eq!(app.world().resource::<LateFrames>().0, 1);
+}
+
+#[derive(Resource, Debug, Default)]
+struct PendingIo;
+impl Service for PendingIo {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|| {
+            Ok(Some(AsyncHook::io(async |_| {
+                bevy::tasks::futures_lite::future::pending::<()>().await;
+                Ok(())
+            })))
+        });
+    }
+}
+
+#[test]
+fn task_pools() {
+    // the task pools are initialized by the app's plugins
+    let mut app = setup();
+    app.register_service::<PendingIo>();
+    app.update();
+    async fn task(_: &mut CommandQueue) -> Result<(), BevyError> {
+        Ok(())
+    }
+    async fn progress(_: &mut CommandQueue, _: ProgressSender) -> Result<(), BevyError> {
+        Ok(())
+    }
+    assert_eq!(AsyncHook::io(task).pool(), TaskPoolKind::Io);
+    assert_eq!(AsyncHook::compute(task).pool(), TaskPoolKind::Compute);
+    assert_eq!(
+        AsyncHook::async_compute(task).pool(),
+        TaskPoolKind::AsyncCompute
+    );
+    assert_eq!(
+        AsyncHook::progress_task(progress).pool(),
+        TaskPoolKind::AsyncCompute
+    );
+
+    app.world_mut().commands().spin_service_up::<PendingIo>();
+    app.update();
+    let world = app.world();
+    assert_eq!(
+        world.service::<PendingIo>().pending_task_pools(world),
+        vec![TaskPoolKind::Io]
+    );
+}
+
+#[derive(Resource, Debug, Default)]
+struct OwnedQueue;
+impl Service for OwnedQueue {
+    fn build(scope: &mut ServiceScope<Self>) {
+        #[allow(deprecated)]
+        scope.init_with(|| Ok(Some(AsyncHook::io_task(async |_: CommandQueue| Ok(())))));
+    }
+}
+
+#[test]
+fn deprecated_task_constructors() {
+    let mut app = setup();
+    app.register_service::<OwnedQueue>();
+    app.world_mut().commands().spin_service_up::<OwnedQueue>();
+    for _ in 0..10 {
+        app.update();
+        if app.world().service::<OwnedQueue>().status().is_up() {
+            break;
+        }
+    }
+    status_matches!(app.world(), OwnedQueue, ServiceStatus::Up);
+}
@@ -1,7 +1,12 @@
 mod common;
-use bevy::prelude::*;
+use bevy::{
+    ecs::{component::ComponentId, world::CommandQueue},
+    prelude::*,
+};
 use common::*;
 use q_service::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 #[derive(Resource, Debug, Default)]
 struct Cycle1;
@@ -34,6 +39,89 @@ fn deps_fail_on_cycle() {
     assert!(err.contains(expected))
 }
 
+#[test]
+fn try_register_cycle() {
+    let mut app = setup();
+    assert!(app.try_register_service::<Cycle1>().is_ok());
+    let res = app.try_register_service::<Cycle2>();
+    assert!(matches!(res, Err(DepInitErr::DepCycle(_))));
+    assert!(!app.world().service::<Cycle2>().registered());
+    // the rest of the app is unaffected
+    app.register_service::<Simple>();
+    app.update();
+    status_matches!(app.world(), Cycle1, ServiceStatus::Down(_));
+}
+
+#[derive(Resource, Default)]
+struct CycleScratch;
+#[derive(Resource, Debug, Default)]
+struct CycleHead;
+impl Service for CycleHead {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<ScopedCycle>();
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct ScopedCycle;
+impl Service for ScopedCycle {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<CycleHead>()
+            .add_resource::<CycleScratch>()
+            .add_systems(Update, || {});
+    }
+}
+
+#[test]
+fn rejected_service_leaves_nothing_behind() {
+    let mut app = setup();
+    app.register_service::<CycleHead>();
+    let systems = |app: &App| {
+        let schedules = app.world().resource::<Schedules>();
+        [schedules.get(PreUpdate), schedules.get(Update)]
+            .map(|schedule| schedule.map_or(0, Schedule::systems_len))
+    };
+    let before = systems(&app);
+    assert!(app.try_register_service::<ScopedCycle>().is_err());
+    assert_eq!(systems(&app), before);
+    let scratch = NodeId::Resource(app.world().resource_id::<CycleScratch>().unwrap());
+    assert!(
+        !app.world()
+            .resource::<GraphDataCache>()
+            .contains_key(&scratch)
+    );
+}
+
+#[test]
+fn cycle_message_names_services() {
+    let mut app = setup();
+    app.register_service::<Cycle1>();
+    let Err(err) = app.try_register_service::<Cycle2>() else {
+        panic!("Expected a cycle.");
+    };
+    let message = err.to_string();
+    assert!(message.contains(&Cycle1::name()), "{message}");
+    assert!(message.contains(&Cycle2::name()), "{message}");
+    assert!(message.contains(" → "), "{message}");
+    assert!(!message.contains("ComponentId"), "{message}");
+}
+
+#[derive(Resource)]
+struct CycleConfig;
+
+#[test]
+fn deferred_cycle_is_not_registered() {
+    let mut app = setup();
+    app.register_service::<Cycle1>()
+        .register_service_when_resource::<Cycle2, CycleConfig>();
+    app.update();
+    app.insert_resource(CycleConfig);
+    app.update();
+    assert!(!app.world().service::<Cycle2>().registered());
+    app.update();
+    status_matches!(app.world(), Cycle1, ServiceStatus::Down(_));
+}
+
 #[derive(Resource, Debug, Default)]
 struct Loop;
 impl Service for Loop {
@@ -121,7 +209,7 @@ fn failure_propogation() {
     match status {
         ServiceStatus::Down(DownReason::Failed(ServiceError::Dependency(ref dep, ref e))) => {
             assert_eq!(*dep, DepFailure::name());
-            assert!(e.contains(&err_str));
+            assert!(e.to_string().contains(&err_str));
         }
         _ => {
             panic!()
@@ -132,7 +220,7 @@ fn failure_propogation() {
     match status {
         ServiceStatus::Down(DownReason::Failed(ServiceError::Dependency(ref dep, ref e))) => {
             assert_eq!(*dep, FailOnInit::name());
-            assert!(e.contains(&err_str));
+            assert!(e.to_string().contains(&err_str));
         }
         _ => {
             panic!()
@@ -141,7 +229,7 @@ fn failure_propogation() {
     debug!("Checking status for {} : {status:#?}", FailOnInit::name());
     let status = app.world().service::<FailOnInit>().status();
     match status {
-        ServiceStatus::Down(DownReason::Failed(ServiceError::Own(ref e))) => {
+        ServiceStatus::Down(DownReason::Failed(ServiceError::Own(ref e, _))) => {
             assert_eq!(e.trim(), err_str);
         }
         _ => {
@@ -150,6 +238,45 @@ fn failure_propogation() {
     }
 }
 
+#[test]
+fn cascade_failure_events() {
+    #[derive(Resource, Default)]
+    struct Cascades(Vec<ServiceCascadeFailure>);
+
+    let mut app = setup();
+    app.register_service::<DepDepFailure>()
+        .register_service::<DepFailure>()
+        .register_service::<FailOnInit>()
+        .init_resource::<Cascades>()
+        .add_systems(
+            Update,
+            |mut reader: EventReader<ServiceCascadeFailure>, mut cascades: ResMut<Cascades>| {
+                cascades.0.extend(reader.read().copied());
+            },
+        );
+    app.update();
+
+    let world = app.world();
+    let origin = world.service::<FailOnInit>().id();
+    let expected = [
+        world.service::<DepFailure>().id(),
+        world.service::<DepDepFailure>().id(),
+    ];
+    let cascades = &world.resource::<Cascades>().0;
+    assert!(
+        cascades
+            .iter()
+            .all(|cascade| cascade.origin == origin && expected.contains(&cascade.affected)),
+        "{cascades:?}"
+    );
+    for id in expected {
+        assert!(
+            cascades.iter().any(|cascade| cascade.affected == id),
+            "missing cascade for {id:?} in {cascades:?}"
+        );
+    }
+}
+
 #[derive(Resource, Debug, Default)]
 struct RedundantDep;
 impl Service for RedundantDep {
@@ -238,18 +365,83 @@ fn deps_spin_down() {
 }
 
 #[test]
-fn unregistered_dep() {
+fn spin_down_with_dep_down_for_custom_reason() {
     let mut app = setup();
     app.register_service::<SimpleDep>();
+    app.register_service::<Simple>();
     app.world_mut().commands().spin_service_up::<SimpleDep>();
     app.update();
+    status_matches!(app.world(), SimpleDep, ServiceStatus::Up);
+
+    app.world_mut()
+        .commands()
+        .spin_service_down_with_reason::<Simple>("Evicted");
+    app.update();
+    status_matches!(
+        app.world(),
+        Simple,
+        ServiceStatus::Down(DownReason::Custom(_))
+    );
+
+    // the dep stays down for its own reason, which still counts as down
+    app.world_mut().commands().spin_service_down::<SimpleDep>();
+    app.update();
     status_matches!(
         app.world(),
         SimpleDep,
-        ServiceStatus::Down(DownReason::Failed(ServiceError::Dependency(..)))
+        ServiceStatus::Down(DownReason::SpunDown)
     );
 }
 
+#[test]
+fn unregistered_dep() {
+    let mut app = setup();
+    app.register_service::<SimpleDep>();
+    app.world_mut().commands().spin_service_up::<SimpleDep>();
+    app.update();
+    let ServiceStatus::Down(DownReason::Failed(ServiceError::Dependency(name, message))) =
+        app.world().service::<SimpleDep>().status()
+    else {
+        panic!("SimpleDep should have failed on its dependency");
+    };
+    assert_eq!(name, Simple::name());
+    let message = message.to_string();
+    assert_eq!(message, DepInitErr::NotFound(Simple::name()).to_string());
+    assert!(message.contains("register_service::<Simple>()"));
+}
+
+#[derive(Resource, Debug, Default)]
+struct AsyncInitDep;
+impl Service for AsyncInitDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|| {
+            Ok(Some(AsyncHook::io(async |_| {
+                busy_wait(10);
+                Ok(())
+            })))
+        });
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct AsyncInitParent;
+impl Service for AsyncInitParent {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<AsyncInitDep>();
+    }
+}
+
+#[test]
+fn dep_registered_after_dependent() {
+    let mut app = setup();
+    // the dep's lifecycle systems are added by its own registration, not add_dep
+    app.register_service::<AsyncInitParent>()
+        .register_service::<AsyncInitDep>();
+    app.update();
+    let status = app.spin_service_up_blocking::<AsyncInitParent>(Duration::from_secs(5));
+    assert!(status.is_up());
+    assert_status::<AsyncInitDep>(app.world(), ServiceStatus::Up);
+}
+
 #[derive(Resource, Debug, Default, PartialEq)]
 struct TestPassed(bool);
 
@@ -277,3 +469,1164 @@ fn resource_dep() {
     app.update();
     assert_eq!(app.world().get_resource::<TestPassed>(), None);
 }
+
+#[test]
+fn resource_deps() {
+    let mut app = setup();
+    app.register_service::<ResourceDep>();
+    let world = app.world();
+    let service = world.service::<ResourceDep>();
+    let names = service
+        .resource_deps(world.resource::<GraphDataCache>())
+        .map(|dep| dep.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["TestPassed".to_string()]);
+    assert!(!service.is_resource_ready::<TestPassed>(world));
+
+    app.update();
+    let world = app.world();
+    assert!(
+        world
+            .service::<ResourceDep>()
+            .is_resource_ready::<TestPassed>(world)
+    );
+    assert!(
+        !world
+            .service::<ResourceDep>()
+            .is_resource_ready::<DownOrder>(world)
+    );
+}
+
+#[derive(Resource, Debug, Default)]
+struct DownOrder(Vec<String>);
+fn record_down<T: Service>(_: In<DownReason>, mut order: ResMut<DownOrder>) {
+    order.0.push(T::name());
+}
+
+#[derive(Resource, Debug, Default)]
+struct Top;
+impl Service for Top {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<Mid>()
+            .on_down(record_down::<Self>)
+            .is_startup(true);
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct Mid;
+impl Service for Mid {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Leaf>().on_down(record_down::<Self>);
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct Leaf;
+impl Service for Leaf {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.on_down(record_down::<Self>);
+    }
+}
+
+#[test]
+fn spin_all_services_down() {
+    let mut app = setup();
+    app.init_resource::<DownOrder>()
+        .register_service::<Leaf>()
+        .register_service::<Top>()
+        .register_service::<Mid>();
+    app.update();
+    status_matches!(app.world(), Top, ServiceStatus::Up);
+    status_matches!(app.world(), Mid, ServiceStatus::Up);
+    status_matches!(app.world(), Leaf, ServiceStatus::Up);
+
+    app.world_mut().commands().spin_all_services_down();
+    app.update();
+    for status in [
+        app.world().service::<Top>().status(),
+        app.world().service::<Mid>().status(),
+        app.world().service::<Leaf>().status(),
+    ] {
+        assert_eq!(status, ServiceStatus::down());
+    }
+    // Top is told to spin down first, so its deps finish before it does.
+    assert_eq!(
+        app.world().resource::<DownOrder>().0,
+        vec![Leaf::name(), Mid::name(), Top::name()]
+    );
+}
+
+#[test]
+fn spin_services_down() {
+    let mut app = setup();
+    app.init_resource::<DownOrder>()
+        .register_service::<Leaf>()
+        .register_service::<Top>()
+        .register_service::<Mid>();
+    app.update();
+
+    let ids = [app.world().service::<Leaf>().id()];
+    app.world_mut().commands().spin_services_down(&ids);
+    app.update();
+    assert_eq!(app.world().resource::<DownOrder>().0, vec![Leaf::name()]);
+    status_matches!(app.world(), Top, ServiceStatus::Up);
+}
+
+#[derive(Resource, Debug, Default)]
+struct IndepA;
+impl Service for IndepA {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct IndepB;
+impl Service for IndepB {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+
+#[test]
+fn topsort_tiebreaker() {
+    let mut app = setup();
+    app.register_service::<IndepA>()
+        .register_service::<IndepB>();
+    let a = app.world().service::<IndepA>().id();
+    let b = app.world().service::<IndepB>().id();
+    let position = |world: &World, id: NodeId| {
+        world
+            .resource::<DependencyGraph>()
+            .topsort()
+            .iter()
+            .position(|node| *node == id)
+            .unwrap()
+    };
+
+    let first = move |id: NodeId| move |x: NodeId, y: NodeId| (x != id).cmp(&(y != id));
+    app.world_mut()
+        .resource_mut::<DependencyGraph>()
+        .set_tiebreaker(first(b));
+    assert!(position(app.world(), b) < position(app.world(), a));
+
+    app.world_mut()
+        .resource_mut::<DependencyGraph>()
+        .set_tiebreaker(first(a));
+    assert!(position(app.world(), a) < position(app.world(), b));
+}
+
+#[derive(Resource, Debug, Default)]
+struct GraphSeen(Vec<String>);
+
+struct FirstPlugin;
+impl Plugin for FirstPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_service::<Top>().on_graph_built(|app| {
+            let cache = app.world().resource::<GraphDataCache>();
+            let mut names = cache
+                .values()
+                .filter_map(GraphData::as_service)
+                .filter(|service| service.registered())
+                .map(|service| service.name().to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            app.insert_resource(GraphSeen(names));
+        });
+    }
+}
+
+struct SecondPlugin;
+impl Plugin for SecondPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_service::<Mid>().register_service::<Leaf>();
+    }
+}
+
+#[test]
+fn on_graph_built() {
+    let mut app = setup();
+    app.init_resource::<DownOrder>()
+        .add_plugins((FirstPlugin, SecondPlugin));
+    assert!(!app.world().contains_resource::<GraphSeen>());
+    app.finish();
+    let mut expected = vec![Top::name(), Mid::name(), Leaf::name()];
+    expected.sort();
+    assert_eq!(app.world().resource::<GraphSeen>().0, expected);
+}
+
+#[derive(Resource, Debug, Default)]
+struct DepFailedRan(Option<ServiceStatus>);
+
+#[test]
+fn dep_failed_run_condition() {
+    let mut app = setup();
+    app.init_resource::<DownOrder>()
+        .init_resource::<DepFailedRan>()
+        .register_service::<Leaf>()
+        .register_service::<Mid>()
+        .register_service::<Top>()
+        .add_systems(
+            PreUpdate,
+            (|mut ran: ResMut<DepFailedRan>, top: ServiceRef<Top>| {
+                ran.0.get_or_insert(top.status());
+            })
+            .run_if(service_dep_failed::<Top>())
+            .after(GlobalLifecycleSystems::Before)
+            .before(GlobalLifecycleSystems::Services),
+        );
+    app.update();
+    status_matches!(app.world(), Top, ServiceStatus::Up);
+    assert!(app.world().resource::<DepFailedRan>().0.is_none());
+
+    app.world_mut().commands().queue(|world: &mut World| {
+        world.service_scope::<Leaf, _>(|world, leaf| leaf.fail(world, ServiceError::own("oh no")));
+    });
+    app.update();
+    // The gated system saw the failure before the cascade reached Top.
+    assert_eq!(
+        app.world().resource::<DepFailedRan>().0,
+        Some(ServiceStatus::Up)
+    );
+}
+
+fn slow_deinit(mut count: ResMut<Count>) -> DeinitResult {
+    count.deinit += 1;
+    let hook = AsyncHook::io(async |_| {
+        busy_wait(10);
+        Ok(())
+    });
+    Ok(Some(hook))
+}
+
+#[derive(Resource, Debug, Default)]
+struct Socket;
+impl Service for Socket {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.deinit_with(slow_deinit);
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct Server;
+impl Service for Server {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<Socket>()
+            .deinit_with(slow_deinit)
+            .is_startup(true);
+    }
+}
+
+#[test]
+fn shutdown_on_exit() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service::<Socket>()
+        .register_service::<Server>()
+        .run_service_shutdown_on_exit();
+    app.update();
+    status_matches!(app.world(), Server, ServiceStatus::Up);
+    status_matches!(app.world(), Socket, ServiceStatus::Up);
+
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+    status_matches!(
+        app.world(),
+        Server,
+        ServiceStatus::Down(DownReason::SpunDown)
+    );
+    status_matches!(
+        app.world(),
+        Socket,
+        ServiceStatus::Down(DownReason::SpunDown)
+    );
+    assert_eq!(app.world().resource::<Count>().deinit, 2);
+}
+
+#[derive(Resource, Debug, Default)]
+struct AsyncClose;
+impl Service for AsyncClose {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .on_down(|_: In<DownReason>| {
+                Some(AsyncHook::io(async |queue: &mut CommandQueue| {
+                    busy_wait(10);
+                    queue.push(|world: &mut World| world.resource_mut::<Count>().down += 1);
+                    Ok(())
+                }))
+            })
+            .is_startup(true);
+    }
+}
+
+#[test]
+fn shutdown_runs_async_on_down() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service::<AsyncClose>()
+        .run_service_shutdown_on_exit();
+    app.update();
+    status_matches!(app.world(), AsyncClose, ServiceStatus::Up);
+
+    app.world_mut().send_event(AppExit::Success);
+    app.update();
+    status_matches!(
+        app.world(),
+        AsyncClose,
+        ServiceStatus::Down(DownReason::SpunDown)
+    );
+    assert_eq!(app.world().resource::<Count>().down, 1);
+}
+
+#[test]
+fn topsort_recomputed_only_on_change() {
+    let ids = (0..100)
+        .map(|i| NodeId::Service(ComponentId::new(i)))
+        .collect::<Vec<_>>();
+    let edges = ids
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect::<Vec<_>>();
+
+    let mut graph = DependencyGraph::default();
+    graph.add_edges(&edges).unwrap();
+    assert_eq!(graph.sort_count(), 1);
+    assert_eq!(graph.topsort(), ids.as_slice());
+    // nothing changed, so nothing to recompute
+    graph.add_edges(&edges).unwrap();
+    graph.update_topsort().unwrap();
+    assert_eq!(graph.sort_count(), 1);
+
+    // a cycle is still caught
+    assert!(graph.add_edges(&[(ids[99], ids[0])]).is_err());
+}
+
+#[test]
+fn topsort_bounded_during_registration() {
+    let mut app = setup();
+    app.init_resource::<DownOrder>()
+        .register_service::<Leaf>()
+        .register_service::<Mid>()
+        .register_service::<Top>();
+    let sorts = app.world().resource::<DependencyGraph>().sort_count();
+    assert!(sorts <= 3, "sorted {sorts} times for 3 registrations");
+    // re-registering adds no edges
+    app.register_service::<Top>();
+    assert_eq!(
+        app.world().resource::<DependencyGraph>().sort_count(),
+        sorts
+    );
+}
+
+#[test]
+fn reachable_topsort_matches_subgraph() {
+    // a -> b -> d, a -> c -> d, plus an unrelated e -> d
+    let [a, b, c, d, e] = [0, 1, 2, 3, 4].map(|i| NodeId::Service(ComponentId::new(i)));
+    let mut graph = DependencyGraph::default();
+    graph
+        .add_edges(&[(a, b), (a, c), (b, d), (c, d), (e, d)])
+        .unwrap();
+
+    let expected = graph.subgraph(a).topsort_graph().unwrap();
+    let topsort = graph.reachable_topsort(a);
+    assert_eq!(topsort, expected);
+    assert_eq!(topsort[0], a);
+    assert_eq!(topsort.len(), 4);
+    assert!(!topsort.contains(&e));
+    assert_eq!(graph.reachable_topsort(d), vec![d]);
+}
+
+#[test]
+fn topsort_reports_whole_cycle() {
+    // a -> b -> c -> a
+    // b is only known to be in a's cycle through c, which is visited while b's
+    // neighbors are still being walked.
+    let [a, b, c] = [0, 1, 2].map(|i| NodeId::Service(ComponentId::new(i)));
+    let mut graph = DependencyGraph::default();
+    graph.add_edge(a, b);
+    graph.add_edge(b, c);
+    graph.add_edge(c, a);
+    let message = graph.topsort_graph().unwrap_err().to_string();
+    assert!(
+        message.contains("Service has 1 before/after cycle(s)"),
+        "{message}"
+    );
+}
+
+#[test]
+fn find_cycles() {
+    // a -> b -> c -> a, plus d -> a
+    let [a, b, c, d] = [0, 1, 2, 3].map(|i| NodeId::Service(ComponentId::new(i)));
+    let mut graph = DependencyGraph::default();
+    graph.add_edges(&[(d, a), (a, b), (b, c)]).unwrap();
+    assert!(graph.is_acyclic());
+    assert!(graph.find_cycles().is_empty());
+
+    graph.add_edge(c, a);
+    assert!(!graph.is_acyclic());
+    let cycles = graph.find_cycles();
+    assert_eq!(cycles.len(), 1);
+    assert_eq!(cycles[0].len(), 3);
+    assert!([a, b, c].iter().all(|id| cycles[0].contains(id)));
+}
+
+#[derive(thiserror::Error, Debug, PartialEq)]
+#[error("connection refused on port {0}")]
+struct ConnectionError(u16);
+
+#[derive(Resource, Debug, Default)]
+struct TypedFailureParent;
+impl Service for TypedFailureParent {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<TypedFailure>();
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct TypedFailure;
+impl Service for TypedFailure {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|| Err(ConnectionError(8080).into()));
+    }
+}
+
+#[test]
+fn typed_dependency_error() {
+    let mut app = setup();
+    app.register_service::<TypedFailureParent>()
+        .register_service::<TypedFailure>();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<TypedFailureParent>();
+    app.update();
+
+    let ServiceStatus::Down(DownReason::Failed(error)) =
+        app.world().service::<TypedFailureParent>().status()
+    else {
+        panic!("TypedFailureParent should have failed");
+    };
+    assert!(
+        matches!(error, ServiceError::Dependency(ref name, _) if *name == TypedFailure::name())
+    );
+    assert_eq!(
+        error.downcast_ref::<ConnectionError>(),
+        Some(&ConnectionError(8080))
+    );
+    let source = std::error::Error::source(&error).unwrap();
+    assert_eq!(source.to_string(), ConnectionError(8080).to_string());
+}
+
+#[derive(Resource, Debug, Default, PartialEq)]
+struct SharedResource;
+
+#[derive(Resource, Debug, Default)]
+struct Sharer1;
+impl Service for Sharer1 {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_resource::<SharedResource>();
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct Sharer2;
+impl Service for Sharer2 {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_resource::<SharedResource>();
+    }
+}
+
+#[test]
+fn shared_resource_dep() {
+    let mut app = setup();
+    app.register_service::<Sharer1>()
+        .register_service::<Sharer2>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<Sharer1>();
+    app.world_mut().commands().spin_service_up::<Sharer2>();
+    app.update();
+    assert!(app.world().contains_resource::<SharedResource>());
+
+    app.world_mut().commands().spin_service_down::<Sharer1>();
+    app.update();
+    status_matches!(app.world(), Sharer1, ServiceStatus::Down(_));
+    assert!(app.world().contains_resource::<SharedResource>());
+
+    app.world_mut().commands().spin_service_down::<Sharer2>();
+    app.update();
+    status_matches!(app.world(), Sharer2, ServiceStatus::Down(_));
+    assert!(!app.world().contains_resource::<SharedResource>());
+}
+
+#[test]
+fn shared_resource_registered_late() {
+    let mut app = setup();
+    app.register_service::<Sharer1>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<Sharer1>();
+    app.update();
+    let id = NodeId::Resource(app.world().resource_id::<SharedResource>().unwrap());
+    let cache = app.world().resource::<GraphDataCache>();
+    let before = cache.get_resource(id).unwrap().clone();
+    assert!(before.status.is_up());
+
+    // the second service shares the first one's data instead of resetting it
+    app.register_service::<Sharer2>();
+    let cache = app.world().resource::<GraphDataCache>();
+    assert_eq!(cache.get_resource(id), Some(&before));
+
+    app.world_mut().commands().spin_service_up::<Sharer2>();
+    app.update();
+    app.world_mut().commands().spin_service_down::<Sharer1>();
+    app.update();
+    assert!(app.world().contains_resource::<SharedResource>());
+    app.world_mut().commands().spin_service_down::<Sharer2>();
+    app.update();
+    assert!(!app.world().contains_resource::<SharedResource>());
+}
+
+static RELEASE_RESOURCE: AtomicBool = AtomicBool::new(false);
+
+#[derive(Resource, Debug, PartialEq)]
+struct Loaded(u32);
+
+#[derive(Resource, Debug, Default)]
+struct AsyncResourceDep;
+impl Service for AsyncResourceDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_resource_async_with(async || {
+            while !RELEASE_RESOURCE.load(Ordering::Relaxed) {
+                bevy::tasks::futures_lite::future::yield_now().await;
+            }
+            Ok(Loaded(42))
+        });
+    }
+}
+
+#[test]
+fn async_resource_dep() {
+    let mut app = setup();
+    app.register_service::<AsyncResourceDep>();
+    app.update();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<AsyncResourceDep>();
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_status::<AsyncResourceDep>(app.world(), ServiceStatus::Init);
+    assert_eq!(app.world().get_resource::<Loaded>(), None);
+
+    RELEASE_RESOURCE.store(true, Ordering::Relaxed);
+    for _ in 0..10 {
+        app.update();
+        if app.world().service::<AsyncResourceDep>().status().is_up() {
+            break;
+        }
+    }
+    assert_status::<AsyncResourceDep>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().get_resource::<Loaded>(), Some(&Loaded(42)));
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<AsyncResourceDep>();
+    app.update();
+    assert_eq!(app.world().get_resource::<Loaded>(), None);
+}
+
+#[derive(Resource, Debug)]
+struct Unreachable;
+
+#[derive(Resource, Debug, Default)]
+struct FailingResourceDep;
+impl Service for FailingResourceDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_resource_async_with(async || -> Result<Unreachable, BevyError> {
+            Err(ConnectionError(5432).into())
+        });
+    }
+}
+
+#[test]
+fn async_resource_dep_failure() {
+    let mut app = setup();
+    app.register_service::<FailingResourceDep>();
+    app.update();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<FailingResourceDep>();
+    for _ in 0..10 {
+        app.update();
+        if app
+            .world()
+            .service::<FailingResourceDep>()
+            .status()
+            .is_failed()
+        {
+            break;
+        }
+    }
+    let ServiceStatus::Down(DownReason::Failed(error)) =
+        app.world().service::<FailingResourceDep>().status()
+    else {
+        panic!("expected the service to fail");
+    };
+    // wrapped in a dependency error exactly once
+    let ServiceError::Dependency(_, inner) = &error else {
+        panic!("expected a dependency error, got {error:?}");
+    };
+    assert!(
+        !matches!(**inner, ServiceError::Dependency(..)),
+        "{error:?}"
+    );
+    assert!(error.dependency_name().is_some());
+    assert_eq!(error.dependency_error(), Some(&**inner));
+    assert_eq!(error.message(), inner.message());
+    assert_eq!(error.downcast_ref(), Some(&ConnectionError(5432)));
+}
+
+#[derive(Resource, Debug, Default)]
+struct Blocker;
+impl Service for Blocker {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(|| {
+            Ok(Some(AsyncHook::io(async |_| {
+                bevy::tasks::futures_lite::future::pending::<()>().await;
+                Ok(())
+            })))
+        });
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct Blocked;
+impl Service for Blocked {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Simple>().add_dep::<Blocker>();
+    }
+}
+
+#[test]
+fn deps_status() {
+    let mut app = setup();
+    app.register_service::<Simple>()
+        .register_service::<Blocker>()
+        .register_service::<Blocked>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<Blocked>();
+    app.update();
+    assert_status::<Blocked>(app.world(), ServiceStatus::Init);
+
+    let world = app.world();
+    let summary = world
+        .service::<Blocked>()
+        .deps_status(world.resource::<GraphDataCache>());
+    assert_eq!(summary.len(), 2);
+    assert!(summary.contains(&(
+        NodeId::Service(world.resource_id::<Simple>().unwrap()),
+        Simple::name(),
+        ServiceStatus::Up
+    )));
+    assert_eq!(
+        world.why_not_up::<Blocked>(),
+        Some((
+            NodeId::Service(world.resource_id::<Blocker>().unwrap()),
+            Blocker::name(),
+            ServiceStatus::Init
+        ))
+    );
+}
+
+#[derive(Resource, Debug, Default)]
+struct SeenInfo(Option<(ServiceStatus, String, usize, usize, Vec<ServiceStatus>)>);
+
+#[test]
+fn service_info() {
+    let mut app = setup();
+    app.register_service::<Simple>()
+        .register_service::<Blocker>()
+        .register_service::<Blocked>()
+        .init_resource::<SeenInfo>()
+        .add_systems(
+            Update,
+            |info: ServiceInfo<Blocked>, mut seen: ResMut<SeenInfo>| {
+                let mut statuses = info
+                    .deps_status()
+                    .into_iter()
+                    .map(|(_, _, status)| status)
+                    .collect::<Vec<_>>();
+                statuses.sort();
+                seen.0 = Some((
+                    info.status(),
+                    info.name().to_string(),
+                    info.deps().len(),
+                    info.pending_tasks(),
+                    statuses,
+                ));
+            },
+        );
+    app.update();
+    app.world_mut().commands().spin_service_up::<Blocked>();
+    app.update();
+
+    let (status, name, deps, tasks, statuses) =
+        app.world().resource::<SeenInfo>().0.clone().unwrap();
+    assert_eq!(status, ServiceStatus::Init);
+    assert_eq!(name, Blocked::name());
+    assert_eq!(deps, 2);
+    assert_eq!(tasks, 0);
+    assert_eq!(statuses, vec![ServiceStatus::Init, ServiceStatus::Up]);
+}
+
+#[test]
+fn unregistered_dep_panics_on_finish() {
+    let res = std::panic::catch_unwind(|| {
+        let mut app = setup();
+        app.register_service::<SimpleDep>();
+        app.finish();
+    });
+    let message = *res.unwrap_err().downcast::<String>().unwrap();
+    assert!(message.contains(&SimpleDep::name()));
+    assert!(message.contains(&format!("register_service::<{}>()", Simple::name())));
+}
+
+#[test]
+fn unregistered_dep_fails_on_spin_up() {
+    let mut app = setup();
+    app.register_service::<SimpleDep>();
+    // never finished, so only warned about, see tests/tracing.rs
+    app.update();
+    app.world_mut().commands().spin_service_up::<SimpleDep>();
+    app.update();
+    let ServiceStatus::Down(DownReason::Failed(error)) =
+        app.world().service::<SimpleDep>().status()
+    else {
+        panic!("expected the service to fail");
+    };
+    assert!(
+        error
+            .to_string()
+            .contains(&format!("register_service::<{}>()", Simple::name()))
+    );
+}
+
+#[test]
+fn service_bundle() {
+    let mut app = setup();
+    app.add_plugins(
+        ServiceBundle::new()
+            .add::<Simple>()
+            .add::<SimpleDepDep>()
+            .add::<SimpleDep>()
+            .build(),
+    );
+    app.update();
+    let world = app.world();
+    assert!(world.service::<SimpleDep>().registered());
+    status_matches!(world, SimpleDepDep, ServiceStatus::Up);
+    status_matches!(world, SimpleDep, ServiceStatus::Up);
+    status_matches!(world, Simple, ServiceStatus::Up);
+}
+
+#[derive(Resource, Debug, Default)]
+struct UpOrder(Vec<String>);
+
+#[test]
+fn dep_up_events_before_parent() {
+    let mut app = setup();
+    app.init_resource::<DownOrder>()
+        .init_resource::<UpOrder>()
+        .register_service::<Top>()
+        .register_service::<Mid>()
+        .register_service::<Leaf>()
+        .add_systems(
+            Update,
+            |mut reader: EventReader<ServiceUpdated>, mut order: ResMut<UpOrder>| {
+                for event in reader.read() {
+                    if event.new_status.is_up() {
+                        order.0.push(event.name.clone());
+                    }
+                }
+            },
+        );
+    app.update();
+    app.update();
+    let expected = vec![Leaf::name(), Mid::name(), Top::name()];
+    assert_eq!(app.world().resource::<UpOrder>().0, expected);
+
+    app.world_mut().commands().spin_service_down::<Top>();
+    app.update();
+    app.world_mut().resource_mut::<UpOrder>().0.clear();
+    app.world_mut().commands().spin_service_up::<Top>();
+    app.update();
+    app.update();
+    assert_eq!(app.world().resource::<UpOrder>().0, expected);
+}
+
+#[test]
+fn spin_up_if_ready() {
+    let mut app = setup();
+    app.register_service::<SimpleDep>()
+        .register_service::<Simple>();
+    app.update();
+
+    app.world_mut()
+        .commands()
+        .spin_service_up_if_ready::<SimpleDep>();
+    app.update();
+    app.update();
+    status_matches!(app.world(), SimpleDep, ServiceStatus::Down(_));
+    status_matches!(app.world(), Simple, ServiceStatus::Down(_));
+
+    app.world_mut().commands().spin_service_up::<Simple>();
+    app.update();
+    status_matches!(app.world(), Simple, ServiceStatus::Up);
+    app.world_mut()
+        .commands()
+        .spin_service_up_if_ready::<SimpleDep>();
+    app.update();
+    status_matches!(app.world(), SimpleDep, ServiceStatus::Up);
+}
+
+#[test]
+fn services_topo() {
+    let mut app = setup();
+    app.init_resource::<DownOrder>()
+        .register_service::<Leaf>()
+        .register_service::<Sharer1>()
+        .register_service::<Top>()
+        .register_service::<Mid>();
+    app.update();
+    let world = app.world();
+    let order = world.services_topo();
+    assert_eq!(order.len(), 4);
+    assert!(order.iter().all(|id| world.service_by_id(*id).is_some()));
+    let position = |id: NodeId| order.iter().position(|other| *other == id).unwrap();
+    for id in order.iter().copied() {
+        for dep in world.service_by_id(id).unwrap().deps() {
+            if dep.service_id().is_some() {
+                assert!(position(id) < position(*dep));
+            }
+        }
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct Connection;
+impl Service for Connection {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct ConnectionCache;
+impl Service for ConnectionCache {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<Connection>()
+            .restart_on_dep_change::<Connection>()
+            .init_with(count_init);
+    }
+}
+
+#[test]
+fn restart_on_dep_change() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service::<Connection>()
+        .register_service::<ConnectionCache>();
+    app.update();
+    app.world_mut()
+        .commands()
+        .spin_service_up::<ConnectionCache>();
+    app.update();
+    app.update();
+    assert_status::<ConnectionCache>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().init, 1);
+
+    app.world_mut().commands().restart_service::<Connection>();
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_status::<Connection>(app.world(), ServiceStatus::Up);
+    assert_status::<ConnectionCache>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().init, 2);
+}
+
+#[derive(Resource, Debug, Default)]
+struct Headless(bool);
+
+#[derive(Resource, Debug, Default)]
+struct Window;
+impl Service for Window {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Simple>();
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct Renderer;
+impl Service for Renderer {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep_if::<Window, _>(|headless: Res<Headless>| !headless.0);
+    }
+}
+
+fn conditional_dep_app(headless: bool) -> App {
+    let mut app = setup();
+    app.insert_resource(Headless(headless))
+        .register_service::<Simple>()
+        .register_service::<Window>()
+        .register_service::<Renderer>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<Renderer>();
+    app.update();
+    app.update();
+    app
+}
+
+#[test]
+fn conditional_dep() {
+    let app = conditional_dep_app(true);
+    assert_status::<Renderer>(app.world(), ServiceStatus::Up);
+    status_matches!(app.world(), Window, ServiceStatus::Down(_));
+    status_matches!(app.world(), Simple, ServiceStatus::Down(_));
+
+    let app = conditional_dep_app(false);
+    assert_status::<Renderer>(app.world(), ServiceStatus::Up);
+    assert_status::<Window>(app.world(), ServiceStatus::Up);
+    assert_status::<Simple>(app.world(), ServiceStatus::Up);
+}
+
+#[derive(Resource, Debug, Default)]
+struct Exclusive;
+impl Service for Exclusive {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct Shared;
+impl Service for Shared {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct StopsDeps;
+impl Service for StopsDeps {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<Exclusive>()
+            .add_dep::<Shared>()
+            .stop_deps_on_failure(true);
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct SharesDep;
+impl Service for SharesDep {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Shared>();
+    }
+}
+
+#[test]
+fn stop_deps_on_failure() {
+    let mut app = setup();
+    app.register_service::<Exclusive>()
+        .register_service::<Shared>()
+        .register_service::<StopsDeps>()
+        .register_service::<SharesDep>();
+    app.update();
+    app.world_mut().commands().spin_service_up::<StopsDeps>();
+    app.world_mut().commands().spin_service_up::<SharesDep>();
+    app.update();
+    assert_status::<StopsDeps>(app.world(), ServiceStatus::Up);
+    assert_status::<SharesDep>(app.world(), ServiceStatus::Up);
+
+    app.world_mut()
+        .commands()
+        .fail_service::<StopsDeps>(ServiceError::own("oh no"));
+    app.update();
+    status_matches!(
+        app.world(),
+        StopsDeps,
+        ServiceStatus::Down(DownReason::Failed(ServiceError::Own(..)))
+    );
+    assert_status::<Exclusive>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    assert_status::<Shared>(app.world(), ServiceStatus::Up);
+    assert_status::<SharesDep>(app.world(), ServiceStatus::Up);
+}
+
+#[derive(Resource, Debug, Default)]
+struct OneFrameLeaf;
+impl Service for OneFrameLeaf {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(count_init).on_up(count_up);
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct OneFrameMid;
+impl Service for OneFrameMid {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<OneFrameLeaf>()
+            .add_resource_with(|| TestPassed(true))
+            .init_with(count_init)
+            .on_up(count_up);
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct OneFrameTop;
+impl Service for OneFrameTop {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_dep::<OneFrameMid>()
+            .init_with(count_init)
+            .on_up(count_up);
+    }
+}
+
+#[test]
+fn sync_spin_up_takes_one_update() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service::<OneFrameLeaf>()
+        .register_service::<OneFrameMid>()
+        .register_service::<OneFrameTop>();
+    app.update();
+
+    app.world_mut().commands().spin_service_up::<OneFrameTop>();
+    app.update();
+    assert_status::<OneFrameLeaf>(app.world(), ServiceStatus::Up);
+    assert_status::<OneFrameMid>(app.world(), ServiceStatus::Up);
+    assert_status::<OneFrameTop>(app.world(), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().init, 3);
+    assert_eq!(app.world().resource::<Count>().up, 3);
+
+    app.world_mut()
+        .commands()
+        .spin_service_down::<OneFrameTop>();
+    app.update();
+    status_matches!(app.world(), OneFrameLeaf, ServiceStatus::Down(_));
+    status_matches!(app.world(), OneFrameMid, ServiceStatus::Down(_));
+    status_matches!(app.world(), OneFrameTop, ServiceStatus::Down(_));
+}
+
+#[derive(Resource, Debug, Default)]
+struct Scratch;
+#[derive(Resource, Default)]
+struct RemovableRuns(u32);
+#[derive(Resource, Debug, Default)]
+struct Removable;
+impl Service for Removable {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .add_resource::<Scratch>()
+            .add_dep::<RemovableDep>()
+            .add_systems(
+                Update,
+                |_: Res<Scratch>, mut runs: ResMut<RemovableRuns>| runs.0 += 1,
+            );
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct RemovableDep;
+impl Service for RemovableDep {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+
+#[test]
+fn deregister_service() {
+    let mut app = setup();
+    app.init_resource::<RemovableRuns>()
+        .register_service::<Removable>()
+        .register_service::<RemovableDep>();
+    app.world_mut().commands().spin_service_up::<Removable>();
+    app.update();
+    assert_status::<Removable>(app.world(), ServiceStatus::Up);
+
+    // still depended on
+    assert!(matches!(
+        app.world_mut().deregister_service::<RemovableDep>(),
+        Err(DeregisterErr::HasDependents(..))
+    ));
+
+    let id = app.world().service::<Removable>().id();
+    let scratch = NodeId::Resource(app.world().resource_id::<Scratch>().unwrap());
+    app.world_mut().deregister_service::<Removable>().unwrap();
+    let graph = app.world().resource::<DependencyGraph>();
+    assert!(!graph.contains_node(id));
+    assert!(!graph.contains_node(scratch));
+    let cache = app.world().resource::<GraphDataCache>();
+    assert!(!cache.contains_key(&id));
+    assert!(!cache.contains_key(&scratch));
+    assert!(app.world().get_service::<Removable>().is_none());
+    assert!(!app.world().contains_resource::<Scratch>());
+    assert_status::<RemovableDep>(app.world(), ServiceStatus::Down(DownReason::SpunDown));
+    assert!(matches!(
+        app.world_mut().deregister_service::<Removable>(),
+        Err(DeregisterErr::NotRegistered(_))
+    ));
+
+    // its systems are skipped from now on
+    app.update();
+    app.update();
+
+    // the dep is free to go now
+    app.world_mut()
+        .deregister_service::<RemovableDep>()
+        .unwrap();
+    assert!(app.world().registry().is_empty());
+
+    // and both can come back
+    app.register_service::<Removable>()
+        .register_service::<RemovableDep>();
+    app.world_mut().commands().spin_service_up::<Removable>();
+    app.update();
+    assert_status::<Removable>(app.world(), ServiceStatus::Up);
+    assert_status::<RemovableDep>(app.world(), ServiceStatus::Up);
+
+    // the scoped system wasn't added a second time
+    let runs = app.world().resource::<RemovableRuns>().0;
+    app.update();
+    assert_eq!(app.world().resource::<RemovableRuns>().0, runs + 1);
+}
+
+#[derive(Resource, Debug, Default)]
+struct PairA1;
+impl Service for PairA1 {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<PairA2>();
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct PairA2;
+impl Service for PairA2 {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<PairA1>();
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct PairB1;
+impl Service for PairB1 {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<PairB2>();
+    }
+}
+#[derive(Resource, Debug, Default)]
+struct PairB2;
+impl Service for PairB2 {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<PairB1>();
+    }
+}
+
+#[test]
+fn validate_services() {
+    let mut app = setup();
+    app.register_service::<Simple>();
+    assert!(app.validate_services().is_ok());
+
+    let _ = app.try_register_service::<PairA1>();
+    let _ = app.try_register_service::<PairA2>();
+    let _ = app.try_register_service::<PairB1>();
+    let _ = app.try_register_service::<PairB2>();
+    let errors = app.validate_services().unwrap_err();
+    assert_eq!(errors.len(), 2, "{errors:?}");
+    for (first, second) in [
+        (PairA1::name(), PairA2::name()),
+        (PairB1::name(), PairB2::name()),
+    ] {
+        assert!(
+            errors.iter().any(|e| matches!(e, DepInitErr::DepCycle(_))
+                && e.to_string().contains(&first)
+                && e.to_string().contains(&second)),
+            "{errors:?}"
+        );
+    }
+}
@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use q_service::prelude::*;
+mod common;
+use common::*;
+
+#[derive(Resource, Default, Debug)]
+struct Network;
+impl Service for Network {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+
+#[derive(Resource, Default, Debug)]
+struct Storage;
+impl Service for Storage {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+
+struct Core;
+impl ServiceGroup for Core {}
+
+#[derive(Resource, Default, Debug)]
+struct GroupCount {
+    events: u32,
+    condition: u32,
+}
+
+#[test]
+fn group_up_event() {
+    let mut app = setup();
+    app.init_resource::<GroupCount>()
+        .register_service::<Network>()
+        .register_service::<Storage>()
+        .add_service_to_group::<Network>(Core)
+        .add_service_to_group::<Storage>(Core)
+        .add_systems(
+            Update,
+            (
+                |mut reader: EventReader<GroupUp<Core>>, mut count: ResMut<GroupCount>| {
+                    count.events += reader.read().count() as u32;
+                },
+                (|mut count: ResMut<GroupCount>| count.condition += 1).run_if(group_up::<Core>()),
+            ),
+        );
+    app.update();
+    assert_eq!(
+        app.world()
+            .resource::<ServiceGroups>()
+            .members::<Core>()
+            .len(),
+        2
+    );
+
+    // flush so the command is seen regardless of lifecycle system order
+    app.world_mut().commands().spin_service_up::<Network>();
+    app.world_mut().flush();
+    app.update();
+    assert_eq!(app.world().resource::<GroupCount>().events, 0);
+    assert_eq!(app.world().resource::<GroupCount>().condition, 0);
+
+    app.world_mut().commands().spin_service_up::<Storage>();
+    app.world_mut().flush();
+    app.update();
+    assert_eq!(app.world().resource::<GroupCount>().events, 1);
+    assert_eq!(app.world().resource::<GroupCount>().condition, 1);
+
+    app.update();
+    assert_eq!(app.world().resource::<GroupCount>().events, 1);
+    assert_eq!(app.world().resource::<GroupCount>().condition, 2);
+}
+
+struct Unrelated;
+impl ServiceGroup for Unrelated {}
+
+#[test]
+fn spin_group_up_and_down() {
+    let mut app = setup();
+    app.register_service::<Network>()
+        .register_service::<Storage>()
+        .add_service_to_group::<Network>(Unrelated)
+        .add_service_to_group::<Storage>(Unrelated);
+    app.update();
+    status_matches!(app.world(), Network, ServiceStatus::Down(_));
+    status_matches!(app.world(), Storage, ServiceStatus::Down(_));
+
+    app.world_mut().commands().spin_group_up(Unrelated);
+    app.update();
+    status_matches!(app.world(), Network, ServiceStatus::Up);
+    status_matches!(app.world(), Storage, ServiceStatus::Up);
+
+    app.world_mut().commands().spin_group_down(Unrelated);
+    app.update();
+    status_matches!(
+        app.world(),
+        Network,
+        ServiceStatus::Down(DownReason::SpunDown)
+    );
+    status_matches!(
+        app.world(),
+        Storage,
+        ServiceStatus::Down(DownReason::SpunDown)
+    );
+}
@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use q_service::prelude::*;
+mod common;
+use common::*;
+
+#[derive(Resource, Default, Debug)]
+struct Connection;
+impl Service for Connection {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.init_with(count_init).deinit_with(count_deinit);
+    }
+}
+
+fn instance_status(world: &World, key: &str) -> ServiceStatus {
+    world.service_instance::<Connection>(key).unwrap().status()
+}
+
+#[test]
+fn independent_instances() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service_instance::<Connection>("primary")
+        .register_service_instance::<Connection>("replica");
+    app.update();
+    assert!(instance_status(app.world(), "primary").is_down());
+    assert!(instance_status(app.world(), "replica").is_down());
+
+    app.world_mut()
+        .commands()
+        .spin_service_instance_up::<Connection>("primary");
+    app.update();
+    assert_eq!(instance_status(app.world(), "primary"), ServiceStatus::Up);
+    assert!(instance_status(app.world(), "replica").is_down());
+    assert_eq!(app.world().resource::<Count>().init, 1);
+
+    app.world_mut()
+        .commands()
+        .spin_service_instance_up::<Connection>("replica");
+    app.update();
+    assert_eq!(instance_status(app.world(), "replica"), ServiceStatus::Up);
+
+    app.world_mut()
+        .commands()
+        .spin_service_instance_down::<Connection>("primary");
+    app.update();
+    assert_eq!(
+        instance_status(app.world(), "primary"),
+        ServiceStatus::Down(DownReason::SpunDown)
+    );
+    assert_eq!(instance_status(app.world(), "replica"), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().init, 2);
+    assert_eq!(app.world().resource::<Count>().deinit, 1);
+}
+
+#[test]
+fn restart_and_fail_instances() {
+    let mut app = setup();
+    app.init_resource::<Count>()
+        .register_service_instance::<Connection>("primary")
+        .register_service_instance::<Connection>("replica");
+    app.world_mut()
+        .commands()
+        .spin_service_instance_up::<Connection>("primary");
+    app.update();
+    assert_eq!(instance_status(app.world(), "primary"), ServiceStatus::Up);
+
+    app.world_mut()
+        .commands()
+        .restart_service_instance::<Connection>("primary");
+    app.update();
+    assert_eq!(instance_status(app.world(), "primary"), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().init, 2);
+
+    app.world_mut()
+        .commands()
+        .fail_service_instance::<Connection>("primary", ServiceError::own("lost"));
+    app.update();
+    assert!(instance_status(app.world(), "primary").is_failed());
+    assert!(instance_status(app.world(), "replica").is_down());
+    assert!(!instance_status(app.world(), "replica").is_failed());
+
+    app.world_mut()
+        .commands()
+        .restart_service_instance::<Connection>("primary");
+    app.update();
+    assert_eq!(instance_status(app.world(), "primary"), ServiceStatus::Up);
+    assert_eq!(app.world().resource::<Count>().init, 3);
+}
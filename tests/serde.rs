@@ -0,0 +1,20 @@
+use q_service::prelude::*;
+
+#[test]
+fn status_round_trip() {
+    let status = ServiceStatus::failed(ServiceError::dependency(
+        "Dep",
+        ServiceError::own("Dep failed."),
+    ));
+    let serialized = ron::to_string(&status).unwrap();
+    let deserialized: ServiceStatus = ron::from_str(&serialized).unwrap();
+    assert_eq!(status, deserialized);
+}
+
+#[test]
+fn key_round_trip() {
+    let key = ServiceKey::from_string("my_crate::MyService");
+    let serialized = ron::to_string(&key).unwrap();
+    assert_eq!(serialized, "\"my_crate::MyService\"");
+    assert_eq!(ron::from_str::<ServiceKey>(&serialized).unwrap(), key);
+}
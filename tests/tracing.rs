@@ -0,0 +1,176 @@
+use bevy::{
+    log::{
+        tracing::{
+            self, Event, Level, Subscriber,
+            field::{Field, Visit},
+            span::Attributes,
+        },
+        tracing_subscriber::{self, Layer, layer::Context, prelude::*, registry::LookupSpan},
+    },
+    prelude::*,
+};
+use q_service::prelude::*;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Records the name and `service` field of every new span, the message of
+/// every warning, and the message and names of every dependency event.
+#[derive(Clone, Default)]
+struct Capture {
+    spans: Arc<Mutex<Vec<(String, String)>>>,
+    warnings: Arc<Mutex<Vec<String>>>,
+    deps: Arc<Mutex<Vec<(String, String, String)>>>,
+}
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Capture {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _: &tracing::span::Id, _: Context<'_, S>) {
+        let mut visitor = FieldVisitor("service", None);
+        attrs.record(&mut visitor);
+        if let Some(service) = visitor.1 {
+            let name = attrs.metadata().name().to_string();
+            self.spans.lock().unwrap().push((name, service));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let field = |name| {
+            let mut visitor = FieldVisitor(name, None);
+            event.record(&mut visitor);
+            visitor.1
+        };
+        let Some(message) = field("message") else {
+            return;
+        };
+        if *event.metadata().level() == Level::WARN {
+            self.warnings.lock().unwrap().push(message);
+        } else if let (Some(parent), Some(dep)) = (field("parent_name"), field("dep_name")) {
+            self.deps.lock().unwrap().push((message, parent, dep));
+        }
+    }
+}
+
+/// Records the field with the given name.
+struct FieldVisitor(&'static str, Option<String>);
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == self.0 {
+            self.1 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+        if field.name() == self.0 {
+            self.1 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+/// The subscriber can only be set once per process, so tests share it.
+fn capture() -> &'static Capture {
+    static CAPTURE: OnceLock<Capture> = OnceLock::new();
+    CAPTURE.get_or_init(|| {
+        let capture = Capture::default();
+        tracing::subscriber::set_global_default(
+            tracing_subscriber::registry().with(capture.clone()),
+        )
+        .unwrap();
+        capture
+    })
+}
+
+#[derive(Resource, Debug, Default)]
+struct Traced;
+impl Service for Traced {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope
+            .init_with(|| Ok(None))
+            .on_up(|| Ok(()))
+            .on_down(|_: In<DownReason>| {});
+    }
+}
+
+#[test]
+fn service_spans() {
+    let capture = capture();
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .register_service::<Traced>();
+    app.world_mut().commands().spin_service_up::<Traced>();
+    app.update();
+    assert!(app.world().service::<Traced>().status().is_up());
+    app.world_mut().commands().spin_service_down::<Traced>();
+    app.update();
+
+    let spans = capture.spans.lock().unwrap();
+    for name in ["initialize", "poll_tasks", "on_up", "deinit", "on_down"] {
+        assert!(
+            spans
+                .iter()
+                .any(|(span, service)| span == name && *service == Traced::name()),
+            "missing span {name} in {spans:?}"
+        );
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+struct Forgotten;
+impl Service for Forgotten {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct Forgetful;
+impl Service for Forgetful {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Forgotten>();
+    }
+}
+
+#[test]
+fn unregistered_dep_warning() {
+    let capture = capture();
+
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .register_service::<Forgetful>();
+    app.update();
+
+    let warnings = capture.warnings.lock().unwrap();
+    assert!(
+        warnings.iter().any(
+            |warning| warning.contains("'Forgetful' depends on 'Forgotten'")
+                && warning.contains("register_service::<Forgotten>()")
+        ),
+        "missing warning in {warnings:?}"
+    );
+}
+
+#[derive(Resource, Debug, Default)]
+struct Upstream;
+impl Service for Upstream {
+    fn build(_: &mut ServiceScope<Self>) {}
+}
+#[derive(Resource, Debug, Default)]
+struct Downstream;
+impl Service for Downstream {
+    fn build(scope: &mut ServiceScope<Self>) {
+        scope.add_dep::<Upstream>();
+    }
+}
+
+#[test]
+fn dep_logs() {
+    let capture = capture();
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+        .register_service::<Upstream>()
+        .register_service::<Downstream>();
+    app.world_mut().commands().spin_service_up::<Downstream>();
+    app.update();
+
+    let deps = capture.deps.lock().unwrap();
+    let logged = |message: &str| {
+        deps.iter().any(|(logged, parent, dep)| {
+            logged.contains(message) && *parent == Downstream::name() && *dep == Upstream::name()
+        })
+    };
+    assert!(logged("Adding dep: Downstream -> Upstream"), "{deps:?}");
+    assert!(logged("Initializing dep Upstream"), "{deps:?}");
+}